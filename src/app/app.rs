@@ -1,10 +1,27 @@
-use crate::app::state::{AppState, Mode, Note, View};
+use crate::app::state::{AppState, CalendarScale, DeletedItem, EditSnapshot, Mode, NoteSort, View};
 use crate::app::ui::ui;
+use crate::cli::NoteTarget;
+use crate::utils::checklist::{parse_checklist_item, push_checklist_item};
+use crate::utils::config::{Config, ConfirmAction, SETTINGS, expand_path};
 use crate::utils::data_handler::DataHandler;
-use chrono::{NaiveDate, Utc};
+use crate::utils::dates::{DATE_FORMAT, daily_note_date};
+use crate::utils::hooks::{editor_command, run_editor, spawn_shell_command};
+use crate::utils::keymap::KeySpec;
+use crate::utils::links::{link_at, linking_notes};
+use crate::utils::search::{SearchMode, note_match};
+use crate::utils::tags::{dedup_tags, parse_inline_tags, tag_key};
+use crate::utils::templates;
+use crate::utils::text::{
+    TAB_STOP, line_bounds, list_marker, next_word_end, next_word_start, offset_at,
+    parse_substitution, previous_word_start, word_bounds,
+};
+use crate::utils::theme::Theme;
+use crate::utils::watcher::watch_notes;
+use chrono::{Local, NaiveDate, Utc};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
@@ -14,10 +31,124 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
 };
 use std::{
+    collections::{BTreeSet, HashMap},
     io::{self, Result},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
 };
 
+/// Maximum delay between clicks for them to count as a double or triple click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Shortest wait for input when autosave is off, so an idle session rarely wakes up.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long change events for a file the app itself wrote or renamed are ignored.
+const OWN_WRITE_GRACE: Duration = Duration::from_secs(2);
+
+/// Number of notes kept in the recently opened list.
+const RECENT_LIMIT: usize = 20;
+
+/// Status bar prompt for a task's due date.
+const DUE_DATE_PROMPT: &str = "Due date (YYYY-MM-DD, empty to clear): ";
+const PROJECT_PROMPT: &str = "Project (empty to clear): ";
+
+/// Where an action bound in the config's `[keys]` table can be used.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Global,
+    NoteList,
+    NoteEditor,
+    Calendar,
+    Tasks,
+}
+
+/// Returns the message for a bindable action name and the view it belongs to.
+fn bindable_action(name: &str) -> Option<(Scope, Message)> {
+    let action = match name {
+        "Quit" => (Scope::Global, Message::Quit),
+        "Save" => (Scope::Global, Message::Save),
+        "EnterCommandMode" => (Scope::Global, Message::EnterCommandMode),
+        "EnterSearch" => (Scope::Global, Message::EnterSearch),
+        "ToggleHelp" => (Scope::Global, Message::ToggleHelp),
+        "SwitchToNoteList" => (Scope::Global, Message::SwitchToNoteList),
+        "SwitchToCalendar" => (Scope::Global, Message::SwitchToCalendar),
+        "SwitchToTasks" => (Scope::Global, Message::SwitchToTasks),
+        "SwitchToAgenda" => (Scope::Global, Message::SwitchToAgenda),
+        "UndoDelete" => (Scope::Global, Message::UndoDelete),
+        "OpenQuickSwitcher" => (Scope::Global, Message::OpenQuickSwitcher),
+        "NextNote" => (Scope::NoteList, Message::NextNote),
+        "PreviousNote" => (Scope::NoteList, Message::PreviousNote),
+        "NextNoteColumn" => (Scope::NoteList, Message::NextNoteColumn),
+        "PreviousNoteColumn" => (Scope::NoteList, Message::PreviousNoteColumn),
+        "OpenNote" => (Scope::NoteList, Message::OpenNote),
+        "NewNote" => (Scope::NoteList, Message::NewNote),
+        "DeleteNote" => (Scope::NoteList, Message::DeleteNote),
+        "CycleSort" => (Scope::NoteList, Message::CycleSort),
+        "ToggleNoteDetails" => (Scope::NoteList, Message::ToggleNoteDetails),
+        "ArchiveNote" => (Scope::NoteList, Message::ArchiveNote),
+        "TogglePin" => (Scope::NoteList, Message::TogglePin),
+        "DuplicateNote" => (Scope::NoteList, Message::DuplicateNote),
+        "ToggleShowArchived" => (Scope::NoteList, Message::ToggleShowArchived),
+        "ToggleFocus" => (Scope::NoteList, Message::ToggleFocus),
+        "RenameNote" => (Scope::NoteEditor, Message::RenameNote),
+        "EnterInsertMode" => (Scope::NoteEditor, Message::EnterInsertMode),
+        "EnterTagInput" => (Scope::NoteEditor, Message::EnterTagInput),
+        "Undo" => (Scope::NoteEditor, Message::Undo),
+        "Redo" => (Scope::NoteEditor, Message::Redo),
+        "Paste" => (Scope::NoteEditor, Message::Paste),
+        "ToggleZenMode" => (Scope::NoteEditor, Message::ToggleZenMode),
+        "TogglePreview" => (Scope::NoteEditor, Message::TogglePreview),
+        "StartFind" => (Scope::NoteEditor, Message::StartFind),
+        "FollowLink" => (Scope::NoteEditor, Message::FollowLink),
+        "LineToTask" => (Scope::NoteEditor, Message::LineToTask),
+        "EditExternally" => (Scope::NoteEditor, Message::EditExternally),
+        "ShowBacklinks" => (Scope::Global, Message::ShowBacklinks),
+        "PreviousMonth" => (Scope::Calendar, Message::PreviousMonth),
+        "NextMonth" => (Scope::Calendar, Message::NextMonth),
+        "JumpToToday" => (Scope::Calendar, Message::JumpToToday),
+        "CycleCalendarScale" => (Scope::Calendar, Message::CycleCalendarScale),
+        "OpenDailyNote" => (Scope::Calendar, Message::OpenDailyNote),
+        "NextTask" => (Scope::Tasks, Message::NextTask),
+        "PreviousTask" => (Scope::Tasks, Message::PreviousTask),
+        "NewTask" => (Scope::Tasks, Message::NewTask),
+        "DeleteTask" => (Scope::Tasks, Message::DeleteTask),
+        "RenameTask" => (Scope::Tasks, Message::RenameTask),
+        "EnterEditTask" => (Scope::Tasks, Message::EnterEditTask),
+        "ToggleTaskComplete" => (Scope::Tasks, Message::ToggleTaskComplete),
+        "CycleTaskGrouping" => (Scope::Tasks, Message::CycleTaskGrouping),
+        "CycleTaskFilter" => (Scope::Tasks, Message::CycleTaskFilter),
+        "CycleProjectFilter" => (Scope::Tasks, Message::CycleProjectFilter),
+        "CyclePriority" => (Scope::Tasks, Message::CyclePriority),
+        "MoveTaskUp" => (Scope::Tasks, Message::MoveTaskUp),
+        "MoveTaskDown" => (Scope::Tasks, Message::MoveTaskDown),
+        "TaskToNote" => (Scope::Tasks, Message::TaskToNote),
+        "SetDueDate" => (Scope::Tasks, Message::SetDueDate),
+        "SetTaskProject" => (Scope::Tasks, Message::SetTaskProject),
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Parses the config's `[keys]` table, returning the valid bindings and a description
+/// of each invalid one.
+fn parse_keybindings(config: &Config) -> (Vec<(KeySpec, String)>, Vec<String>) {
+    let mut bindings = Vec::new();
+    let mut errors = Vec::new();
+    for (action, key) in &config.keys {
+        if bindable_action(action).is_none() {
+            errors.push(format!("unknown action '{}'", action));
+            continue;
+        }
+        match KeySpec::parse(key) {
+            Ok(spec) => bindings.push((spec, action.clone())),
+            Err(e) => errors.push(format!("{} = \"{}\": {}", action, key, e)),
+        }
+    }
+    (bindings, errors)
+}
+
 pub enum Focus {
     NoteList,
     TagList,
@@ -27,16 +158,33 @@ pub enum Focus {
 pub enum Message {
     Quit,
     ForceQuit,
+    Redraw,
     SwitchToNoteList,
     SwitchToCalendar,
     SwitchToTasks,
+    SwitchToAgenda,
+    ScrollAgenda(i16),
+    CycleSort,
     PreviousMonth,
     NextMonth,
+    MoveCalendarDay(i64),
+    JumpToToday,
+    CycleCalendarScale,
+    OpenDailyNote,
     Save,
     Char(char),
     Backspace,
     EnterSearch,
     ExitSearch,
+    PreviousNoteColumn,
+    NextNoteColumn,
+    FocusSearchResults,
+    ToggleSearchMode,
+    FocusSearchInput,
+    PreviousSearchResult,
+    NextSearchResult,
+    OpenSearchResult,
+    OpenNthSearchResult(usize),
     PreviousNote,
     NextNote,
     OpenNote,
@@ -45,19 +193,92 @@ pub enum Message {
     SetNoteTitle,
     DeleteNote,
     ConfirmDelete,
+    ConfirmImport,
+    UndoDelete,
+    OpenTrash,
+    OpenRecent,
+    OpenOrphans,
+    PreviousOrphan,
+    NextOrphan,
+    OpenOrphan,
+    ShowBacklinks,
+    PreviousBacklink,
+    NextBacklink,
+    OpenBacklink,
+    PreviousRecentNote,
+    NextRecentNote,
+    OpenRecentNote,
+    OpenQuickSwitcher,
+    ArchiveNote,
+    TogglePin,
+    DuplicateNote,
+    ToggleShowArchived,
+    PreviousSwitcherNote,
+    NextSwitcherNote,
+    OpenSwitcherNote,
+    PreviousTrashedNote,
+    NextTrashedNote,
+    RestoreNote,
+    PurgeNote,
     ToggleHelp,
+    ToggleZenMode,
+    TogglePreview,
+    ScrollPreview(i16),
     ToggleFocus,
     PreviousTag,
     NextTag,
     SelectTag,
+    ToggleTagMark,
+    ClearTagFilter,
     NewLine,
+    InsertTab,
+    DedentLine,
     PreviousTask,
     NextTask,
     ToggleTaskComplete,
+    CyclePriority,
+    MoveTaskUp,
+    MoveTaskDown,
+    TaskToNote,
+    LineToTask,
+    EditExternally,
+    SetDueDate,
+    CommitDueDate,
     NewTask,
+    RenameTask,
     DeleteTask,
     EnterEditTask,
+    CycleTaskGrouping,
+    SetTaskProject,
+    CommitTaskProject,
+    CycleTaskFilter,
+    CycleProjectFilter,
+    OpenSettings,
+    PreviousSetting,
+    NextSetting,
+    EditSetting,
+    CommitSetting,
+    CancelSettingEdit,
+    SaveSettings,
+    CloseSettings,
+    Undo,
+    Redo,
+    StartOperator(char),
+    CancelOperator,
+    DeleteLine,
+    DeleteWord,
+    Yank,
+    Cut,
+    Paste,
+    FollowLink,
+    StartFind,
+    CommitFind,
+    CancelFind,
+    ClearFind,
+    FindNext,
+    FindPrevious,
     ExitEditTask,
+    CommitEditTask,
     SwitchTaskEditFocus,
     CyclePriorityForward,
     CyclePriorityBackward,
@@ -65,12 +286,23 @@ pub enum Message {
     CursorRight,
     CursorUp,
     CursorDown,
+    CursorLineStart,
+    CursorLineEnd,
+    CursorWordLeft,
+    CursorWordRight,
     EnterTagInput,
+    ToggleTagMatch,
+    ToggleNoteDetails,
+    RenameTag,
+    CommitTagRename,
+    DeleteTag,
     AddTag,
     EnterInsertMode,
+    EnterVisualMode,
     EnterNormalMode,
     EnterCommandMode,
     ExecuteCommand,
+    MouseClick(u16, u16),
 }
 
 /// The main application struct.
@@ -79,39 +311,146 @@ pub struct App {
     pub(crate) state: AppState,
     /// Handles data persistence.
     pub(crate) data_handler: DataHandler,
+    /// The user configuration.
+    pub(crate) config: Config,
+    /// Colors for the widgets, from the configured theme.
+    pub(crate) theme: Theme,
+    /// Keys bound to actions in the config, tried before the built-in keys.
+    keybindings: Vec<(KeySpec, String)>,
     pub(crate) focus: Focus,
+    /// Sends failures from background hook commands back to the main loop.
+    hook_sender: Sender<String>,
+    /// Receives failures from background hook commands.
+    hook_receiver: Receiver<String>,
+    /// When dirty notes were last written by the autosave timer.
+    last_autosave: Instant,
+    /// Watches the notes directory; kept alive for as long as the app runs.
+    _note_watcher: Option<notify::RecommendedWatcher>,
+    /// Receives the paths of note files created or changed on disk.
+    note_changes: Option<Receiver<PathBuf>>,
+    /// Note files the app wrote or renamed itself, and when, so the watcher's events for
+    /// them aren't mistaken for changes made by another program.
+    own_writes: HashMap<PathBuf, Instant>,
+    /// Note file to open in the external editor once the current message is handled,
+    /// since only the main loop has the terminal.
+    external_edit: Option<PathBuf>,
 }
 
 impl App {
-    /// Creates a new `App`.
+    /// Creates a new `App`. If the data directory can't be set up, the app runs in memory
+    /// with a warning rather than failing.
     pub fn new() -> Self {
-        let data_handler = DataHandler::new().expect("Failed to initialize data handler");
         let mut state = AppState::new();
 
+        let config = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                state.status_message = format!("Error loading config ({}). Using defaults.", e);
+                Config::default()
+            }
+        };
+        let mut storage_warning = None;
+        let data_handler = match DataHandler::new(&config) {
+            Ok(data_handler) => data_handler,
+            Err(e) => {
+                storage_warning = Some(format!(
+                    "Warning: could not set up the data directory ({}). Running in memory: \
+                     nothing will be saved, but :export <dir> still works.",
+                    e
+                ));
+                DataHandler::in_memory(&config)
+            }
+        };
+
+        state.wrap = config.wrap;
+        state.line_numbers = config.line_numbers;
+        state.note_details = config.note_details;
+        state.first_weekday = config.week_starts_on.weekday();
+
+        // A missing or unreadable list just starts empty
+        state.recent = data_handler.load_recent().unwrap_or_default();
+        // The calendar reopens on the date it was left at
+        if let Ok(Some(date)) = data_handler.load_calendar_date() {
+            state.set_calendar_date(date);
+        }
+
         let notes_result = data_handler.load_notes();
         let tasks_result = data_handler.load_tasks();
 
         let mut errors = vec![];
 
         match notes_result {
-            Ok(notes) => state.notes = notes,
+            Ok(notes) => {
+                state.notes = notes;
+                state.sort_notes();
+                state.refresh_filter();
+            }
             Err(e) => errors.push(format!("notes ({})", e)),
         }
 
         match tasks_result {
-            Ok(tasks) => state.tasks = tasks,
+            Ok(tasks) => state.set_tasks(tasks),
             Err(e) => errors.push(format!("tasks ({})", e)),
         }
 
+        if errors.is_empty() && config.seed_on_first_run && !data_handler.is_in_memory() {
+            match data_handler.seed_first_run(&state.notes) {
+                Ok(Some(note)) => state.notes.push(note),
+                Ok(None) => {}
+                Err(e) => errors.push(format!("welcome note ({})", e)),
+            }
+        }
+
+        let lossy_count = state.notes.iter().filter(|note| note.lossy).count();
         if !errors.is_empty() {
-            state.status_message =
-                format!("Error loading {}. Using sample data.", errors.join(", "));
+            state.status_message = format!("Error loading {}.", errors.join(", "));
+        } else if lossy_count > 0 {
+            state.status_message = format!(
+                "Warning: {} note(s) are not valid UTF-8 and show replacement characters.",
+                lossy_count
+            );
+        }
+
+        let (keybindings, key_errors) = parse_keybindings(&config);
+        if !key_errors.is_empty() && state.status_message.is_empty() {
+            state.status_message = format!("Ignored key bindings: {}", key_errors.join("; "));
+        }
+
+        if !state.notes.is_empty() {
+            state.note_list_state.select(Some(0));
         }
+        if !state.tasks.is_empty() {
+            state.task_list_state.select(Some(0));
+        }
+        // Not being able to save matters more than anything else reported at startup
+        if let Some(warning) = storage_warning {
+            state.status_message = warning;
+        }
+
+        let (hook_sender, hook_receiver) = mpsc::channel();
+        let (note_watcher, note_changes) = match watch_notes(&data_handler.notes_dir) {
+            Ok((watcher, changes)) => (Some(watcher), Some(changes)),
+            Err(_) if data_handler.is_in_memory() => (None, None),
+            Err(e) => {
+                state.status_message = format!("Error watching notes for changes ({})", e);
+                (None, None)
+            }
+        };
 
         let mut app = Self {
             state,
             data_handler,
+            theme: config.theme(),
+            keybindings,
+            config,
             focus: Focus::NoteList,
+            hook_sender,
+            hook_receiver,
+            last_autosave: Instant::now(),
+            _note_watcher: note_watcher,
+            note_changes,
+            own_writes: HashMap::new(),
+            external_edit: None,
         };
         app.update_tags();
         app
@@ -120,23 +459,19 @@ impl App {
     /// Runs the application's main loop.
     pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         while self.state.running {
+            // Surface any failures reported by background hook commands
+            while let Ok(message) = self.hook_receiver.try_recv() {
+                self.state.status_message = message;
+            }
+            self.reload_changed_notes();
+
             // Draw the UI
-            let cursor_position = if let Mode::Insert = self.state.mode {
-                self.get_cursor_position()
-            } else {
-                None
-            };
-            terminal.draw(|frame| ui(frame, self, cursor_position))?;
+            terminal.draw(|frame| ui(frame, self))?;
 
             // Show/hide cursor based on mode
+            // The editor already placed the cursor while drawing, scroll included
             match self.state.mode {
-                Mode::Insert => {
-                    if let Some(pos) = self.get_cursor_position() {
-                        // We show the cursor before drawing to avoid flicker
-                        terminal.set_cursor(pos.0 + 1, pos.1 + 1)?;
-                    }
-                    terminal.show_cursor()?
-                }
+                Mode::Insert | Mode::Visual => terminal.show_cursor()?,
                 _ => terminal.hide_cursor()?,
             }
 
@@ -144,971 +479,3653 @@ impl App {
             if let Some(message) = self.handle_events()? {
                 // Update the state
                 self.update(message);
+                // Any message may have changed the notes, their tags, or the tag filter
+                self.state.refresh_filter();
+            }
+            if let Some(path) = self.external_edit.take() {
+                self.edit_externally(terminal, &path)?;
             }
+
+            // The event poll times out regularly, so this runs even while the user is idle
+            self.autosave_if_due();
         }
+        // Nowhere is left to report a failure, which only costs the calendar its place
+        let _ = self
+            .data_handler
+            .save_calendar_date(self.state.calendar_date());
         Ok(())
     }
 
-    /// Updates the search results based on the current query.
-    fn update_search_results(&mut self) {
-        let query = self.state.search_query.to_lowercase();
-        if query.is_empty() {
-            self.state.search_results.clear();
-        } else {
-            self.state.search_results = self
+    /// Hands the terminal to the user's editor on a note file, then reloads the note.
+    /// The terminal is set up again whether or not the editor could be run.
+    fn edit_externally<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        path: &Path,
+    ) -> Result<()> {
+        let editor = editor_command();
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+        let status = run_editor(&editor, path);
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        terminal.clear()?;
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                self.state.status_message = match status.code() {
+                    Some(code) => format!("Error: '{}' exited with status {}", editor, code),
+                    None => format!("Error: '{}' was terminated by a signal", editor),
+                };
+                return Ok(());
+            }
+            Err(e) => {
+                self.state.status_message = format!(
+                    "Error: couldn't run '{}' ({}); set $EDITOR to your editor",
+                    editor, e
+                );
+                return Ok(());
+            }
+        }
+
+        let on_disk = match self.data_handler.parse_note(path) {
+            Ok(note) => note,
+            Err(e) => {
+                self.state.status_message = format!("Error reloading the note: {}", e);
+                return Ok(());
+            }
+        };
+        let Some(index) = self.state.notes.iter().position(|note| note.path == path) else {
+            return Ok(());
+        };
+        self.state.status_message = format!("Reloaded '{}' after editing", on_disk.title);
+        self.state.clear_history(path);
+        if self.state.note_list_state.selected() == Some(index) {
+            self.state.cursor_offset = self
                 .state
-                .notes
-                .iter()
-                .enumerate()
-                .filter(|(_, note)| {
-                    note.title.to_lowercase().contains(&query)
-                        || note.content.to_lowercase().contains(&query)
-                        || note
-                            .tags
-                            .iter()
-                            .any(|tag| tag.to_lowercase().contains(&query))
-                })
-                .map(|(i, _)| i)
-                .collect();
+                .cursor_offset
+                .min(on_disk.content.chars().count());
+            self.state.selection_anchor = None;
         }
+        self.state.notes[index] = on_disk;
+        self.state.invalidate_note_caches();
+        self.update_tags();
+        self.state.refresh_filter();
+        Ok(())
     }
 
-    /// Handles terminal events and returns a message if an action is required.
-    /// Calculates the cursor (x, y) position based on the character offset.
-    fn get_cursor_position(&self) -> Option<(u16, u16)> {
-        if let Some(index) = self.state.note_list_state.selected() {
-            if let Some(note) = self.state.notes.get(index) {
-                let content = &note.content;
-                let offset = self.state.cursor_offset.min(content.chars().count());
-
-                let mut x = 0;
-                let mut y = 0;
-
-                for (i, c) in content.chars().enumerate() {
-                    if i == offset {
-                        break;
+    /// Picks up note files changed by other programs. A note without unsaved edits is
+    /// reloaded; one with unsaved edits is left alone and a conflict is reported instead.
+    /// Files that match the note in memory, such as those just saved, are ignored.
+    fn reload_changed_notes(&mut self) {
+        let Some(changes) = &self.note_changes else {
+            return;
+        };
+        // A single save usually produces several events for the same file
+        let paths: BTreeSet<PathBuf> = changes.try_iter().collect();
+        self.own_writes
+            .retain(|_, written| written.elapsed() < OWN_WRITE_GRACE);
+        let selection = self.state.save_selection();
+        let mut reloaded = false;
+        for path in paths {
+            if self.own_writes.contains_key(&path) {
+                continue;
+            }
+            let Ok(on_disk) = self.data_handler.parse_note(&path) else {
+                continue;
+            };
+            let existing = self.state.notes.iter().position(|note| note.path == path);
+            match existing {
+                Some(index) => {
+                    let note = &self.state.notes[index];
+                    if note.content == on_disk.content
+                        && note.title == on_disk.title
+                        && note.tags == on_disk.tags
+                        && note.archived == on_disk.archived
+                        && note.pinned == on_disk.pinned
+                    {
+                        continue;
                     }
-                    if c == '\n' {
-                        x = 0;
-                        y += 1;
-                    } else {
-                        x += 1; // Does not handle wide characters
+                    if note.dirty {
+                        self.state.status_message = format!(
+                            "Warning: '{}' changed on disk; saving will overwrite those changes",
+                            note.title
+                        );
+                        continue;
+                    }
+                    self.state.status_message =
+                        format!("Reloaded '{}' (changed on disk)", on_disk.title);
+                    self.state.clear_history(&path);
+                    if self.state.note_list_state.selected() == Some(index) {
+                        self.state.cursor_offset = self
+                            .state
+                            .cursor_offset
+                            .min(on_disk.content.chars().count());
+                        self.state.selection_anchor = None;
                     }
+                    self.state.notes[index] = on_disk;
+                }
+                None => {
+                    self.state.status_message = format!("Added '{}' from disk", on_disk.title);
+                    self.state.notes.push(on_disk);
                 }
-
-                return Some((x as u16, y as u16));
             }
+            reloaded = true;
+        }
+        if reloaded {
+            self.state.invalidate_note_caches();
+            self.update_tags();
+            self.state.restore_selection(selection);
+            self.state.refresh_filter();
         }
-        None
-    }
-
-    /// Updates the global tag list from all notes.
-    fn update_tags(&mut self) {
-        let mut tags: Vec<String> = self
-            .state
-            .notes
-            .iter()
-            .flat_map(|note| note.tags.clone())
-            .collect();
-        tags.sort_unstable();
-        tags.dedup();
-        self.state.tags = tags;
     }
 
-    /// Saves the tasks to disk and updates the status message on failure.
-    fn save_tasks(&mut self) {
-        if let Err(e) = self.data_handler.save_tasks(&self.state.tasks) {
-            self.state.status_message = format!("Error auto-saving tasks: {}", e);
+    /// Quietly writes dirty notes once the configured autosave interval has passed.
+    /// Tasks need no timer since every task change is saved right away.
+    fn autosave_if_due(&mut self) {
+        let interval = self.config.autosave_secs;
+        if interval == 0
+            || self.data_handler.is_in_memory()
+            || self.last_autosave.elapsed() < Duration::from_secs(interval)
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        if !self.state.notes.iter().any(|note| note.dirty) {
+            return;
+        }
+        if let Err(e) = self.write_dirty_notes() {
+            self.state.status_message = format!("Error autosaving notes: {}", e);
         }
     }
 
-    fn handle_events(&self) -> Result<Option<Message>> {
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind != KeyEventKind::Press {
-                    return Ok(None);
-                }
-
-                // Handle modes first
-                match self.state.mode {
-                    Mode::Insert => {
-                        return match key.code {
-                            KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            KeyCode::Enter => Ok(Some(Message::NewLine)),
-                            KeyCode::Left => Ok(Some(Message::CursorLeft)),
-                            KeyCode::Right => Ok(Some(Message::CursorRight)),
-                            KeyCode::Up => Ok(Some(Message::CursorUp)),
-                            KeyCode::Down => Ok(Some(Message::CursorDown)),
-                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::TitleInput => {
-                        return match key.code {
-                            KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            KeyCode::Enter => Ok(Some(Message::SetNoteTitle)),
-                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::ConfirmDeletion => {
-                        return match key.code {
-                            KeyCode::Char('y') => Ok(Some(Message::ConfirmDelete)),
-                            KeyCode::Char('n') | KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::TagInput => {
-                        return match key.code {
-                            KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            KeyCode::Enter => Ok(Some(Message::AddTag)),
-                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::Command => {
-                        return match key.code {
-                            KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            KeyCode::Enter => Ok(Some(Message::ExecuteCommand)),
-                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::ConfirmQuit => {
-                        return match key.code {
-                            KeyCode::Char('y') => Ok(Some(Message::ForceQuit)),
-                            KeyCode::Char('n') | KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
-                            _ => Ok(None),
-                        };
-                    }
-                    Mode::EditTask => {
-                        return match self.state.task_edit_focus {
-                            crate::app::state::TaskEditFocus::Description => match key.code {
-                                KeyCode::Esc => Ok(Some(Message::ExitEditTask)),
-                                KeyCode::Tab => Ok(Some(Message::SwitchTaskEditFocus)),
-                                KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                                KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                                _ => Ok(None),
-                            },
-                            crate::app::state::TaskEditFocus::Priority => match key.code {
-                                KeyCode::Esc => Ok(Some(Message::ExitEditTask)),
-                                KeyCode::Tab => Ok(Some(Message::SwitchTaskEditFocus)),
-                                KeyCode::Left => Ok(Some(Message::CyclePriorityBackward)),
-                                KeyCode::Right => Ok(Some(Message::CyclePriorityForward)),
-                                _ => Ok(None),
-                            },
-                            crate::app::state::TaskEditFocus::DueDate => match key.code {
-                                KeyCode::Esc => Ok(Some(Message::ExitEditTask)),
-                                KeyCode::Tab => Ok(Some(Message::SwitchTaskEditFocus)),
-                                KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                                KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                                _ => Ok(None),
-                            },
-                        };
-                    }
-                    Mode::Normal => {
-                        // Fall through to view-specific and global handlers
-                    }
+    /// Writes every dirty note to disk and marks them clean.
+    /// Returns the number of notes written.
+    fn write_dirty_notes(&mut self) -> std::io::Result<usize> {
+        if self.config.lift_inline_tags {
+            for note in &mut self.state.notes {
+                let tags = note.all_tags();
+                if tags != note.tags {
+                    note.tags = tags;
+                    note.dirty = true;
                 }
+            }
+        }
+        let now = Utc::now();
+        for note in self.state.notes.iter_mut().filter(|note| note.dirty) {
+            note.updated_at = now;
+            self.own_writes.insert(note.path.clone(), Instant::now());
+        }
+        let written = self.data_handler.save_notes(&self.state.notes)?;
+        self.state.dirty = false;
+        for note in &mut self.state.notes {
+            note.dirty = false;
+        }
+        self.update_tags();
+        Ok(written)
+    }
 
-                // Handle special views like Search that have their own input
-                if let View::Search = self.state.current_view {
-                    return match key.code {
-                        KeyCode::Esc => Ok(Some(Message::ExitSearch)),
-                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
-                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
-                        _ => Ok(None),
-                    };
-                }
+    /// Applies a `:set` option such as `wrap` or `nonumber`. With `local`, the option is
+    /// stored in the selected note's front matter instead of the session defaults.
+    fn set_view_option(&mut self, option: &str, local: bool) {
+        let (name, value) = match option.strip_prefix("no") {
+            Some(name) => (name, false),
+            None => (option, true),
+        };
+        if !matches!(name, "wrap" | "number" | "details") {
+            self.state.status_message = format!("Error: unknown option: {}", option);
+            return;
+        }
+        if local && name == "details" {
+            self.state.status_message = "Error: details can only be set globally".to_string();
+            return;
+        }
 
-                if let View::Help = self.state.current_view {
-                    return match key.code {
-                        KeyCode::Char('?') | KeyCode::Esc => Ok(Some(Message::ToggleHelp)),
-                        _ => Ok(None),
-                    };
-                }
+        if local {
+            let Some(note) = self
+                .state
+                .note_list_state
+                .selected()
+                .and_then(|index| self.state.notes.get_mut(index))
+            else {
+                self.state.status_message = "Error: no note selected.".to_string();
+                return;
+            };
+            match name {
+                "wrap" => note.view.wrap = Some(value),
+                _ => note.view.line_numbers = Some(value),
+            }
+            note.dirty = true;
+            self.state.dirty = true;
+        } else {
+            match name {
+                "wrap" => self.state.wrap = value,
+                "details" => self.state.note_details = value,
+                _ => self.state.line_numbers = value,
+            }
+        }
+        self.state.status_message =
+            format!("{}{}", if local { "setlocal " } else { "set " }, option);
+    }
 
-                // View-specific keybindings in Normal mode
-                match self.state.current_view {
-                    View::NoteList => {
-                        if let KeyCode::Tab = key.code {
-                            return Ok(Some(Message::ToggleFocus));
-                        }
-                        match self.focus {
-                            Focus::NoteList => match key.code {
-                                KeyCode::Char('j') | KeyCode::Down => {
-                                    return Ok(Some(Message::NextNote));
-                                }
-                                KeyCode::Char('k') | KeyCode::Up => {
-                                    return Ok(Some(Message::PreviousNote));
-                                }
-                                KeyCode::Enter => return Ok(Some(Message::OpenNote)),
-                                KeyCode::Char('a') => return Ok(Some(Message::NewNote)),
-                                KeyCode::Char('r') => return Ok(Some(Message::RenameNote)),
-                                KeyCode::Char('d') => return Ok(Some(Message::DeleteNote)),
-                                _ => {}
-                            },
-                            Focus::TagList => match key.code {
-                                KeyCode::Char('j') | KeyCode::Down => {
-                                    return Ok(Some(Message::NextTag));
-                                }
-                                KeyCode::Char('k') | KeyCode::Up => {
-                                    return Ok(Some(Message::PreviousTag));
-                                }
-                                KeyCode::Enter => return Ok(Some(Message::SelectTag)),
-                                _ => {}
-                            },
-                        }
-                    }
-                    View::NoteEditor => match key.code {
-                        KeyCode::Char('t') => return Ok(Some(Message::EnterTagInput)),
-                        KeyCode::Char('i') => return Ok(Some(Message::EnterInsertMode)),
-                        KeyCode::Char('r') => return Ok(Some(Message::RenameNote)),
-                        KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
-                        _ => {}
-                    },
-                    View::Calendar => match key.code {
-                        KeyCode::Left => return Ok(Some(Message::PreviousMonth)),
-                        KeyCode::Right => return Ok(Some(Message::NextMonth)),
-                        _ => {}
-                    },
-                    View::Tasks => match key.code {
-                        KeyCode::Char('j') | KeyCode::Down => return Ok(Some(Message::NextTask)),
-                        KeyCode::Char('k') | KeyCode::Up => return Ok(Some(Message::PreviousTask)),
-                        KeyCode::Char('a') => return Ok(Some(Message::NewTask)),
-                        KeyCode::Char('d') => return Ok(Some(Message::DeleteTask)),
-                        KeyCode::Char('e') => return Ok(Some(Message::EnterEditTask)),
-                        KeyCode::Char(' ') => return Ok(Some(Message::ToggleTaskComplete)),
-                        _ => {}
-                    },
-                    _ => {}
-                }
+    /// Moves a note to the front of the recently opened list and persists the list.
+    fn remember_recent(&mut self, index: usize) {
+        let Some(note) = self.state.notes.get(index) else {
+            return;
+        };
+        let path = note.path.clone();
+        self.state.recent.retain(|recent| recent != &path);
+        self.state.recent.insert(0, path);
+        self.state.recent.truncate(RECENT_LIMIT);
+        if let Err(e) = self.data_handler.save_recent(&self.state.recent) {
+            self.state.status_message = format!("Error saving recent notes: {}", e);
+        }
+    }
 
-                // Global keybindings in Normal mode
-                match key.code {
-                    KeyCode::Char(':') => return Ok(Some(Message::EnterCommandMode)),
-                    KeyCode::Char('/') => return Ok(Some(Message::EnterSearch)),
-                    KeyCode::Char('?') => return Ok(Some(Message::ToggleHelp)),
-                    KeyCode::Char('q') => return Ok(Some(Message::Quit)),
-                    KeyCode::Char('n') => return Ok(Some(Message::SwitchToNoteList)),
-                    KeyCode::Char('c') => return Ok(Some(Message::SwitchToCalendar)),
-                    KeyCode::Char('T') => return Ok(Some(Message::SwitchToTasks)),
-                    _ => {}
+    /// Renames a note's file after its title changed, and follows the new path in the
+    /// recent list and edit history.
+    fn rename_note_file(&mut self, index: usize) {
+        let Some(note) = self.state.notes.get(index) else {
+            return;
+        };
+        let old_path = note.path.clone();
+        let new_path = match self.data_handler.rename_note(note, &self.state.notes) {
+            Ok(path) => path,
+            Err(e) => {
+                self.state.status_message = format!("Error renaming note file: {}", e);
+                return;
+            }
+        };
+        if new_path == old_path {
+            return;
+        }
+        let now = Instant::now();
+        self.own_writes.insert(old_path.clone(), now);
+        self.own_writes.insert(new_path.clone(), now);
+        self.state.notes[index].path = new_path.clone();
+        let snapshots = self.state.undo_stack.iter_mut();
+        for snapshot in snapshots.chain(self.state.redo_stack.iter_mut()) {
+            if snapshot.path == old_path {
+                snapshot.path = new_path.clone();
+            }
+        }
+        if self.state.recent.contains(&old_path) {
+            for path in &mut self.state.recent {
+                if *path == old_path {
+                    *path = new_path.clone();
                 }
             }
+            if let Err(e) = self.data_handler.save_recent(&self.state.recent) {
+                self.state.status_message = format!("Error saving recent notes: {}", e);
+            }
         }
-        Ok(None)
     }
 
-    /// Updates the application state based on a message.
-    fn update(&mut self, message: Message) {
-        match message {
-            Message::Quit => {
-                if self.state.dirty {
-                    self.state.mode = Mode::ConfirmQuit;
-                    self.state.status_message =
-                        "You have unsaved changes. Quit without saving? (y/n)".to_string();
-                } else {
-                    self.state.running = false;
-                }
-            }
-            Message::ForceQuit => {
-                self.state.running = false;
+    /// Exports the selected note to HTML using the configured markdown flavor.
+    fn export_selected_note(&mut self) {
+        let Some(note) = self
+            .state
+            .note_list_state
+            .selected()
+            .and_then(|index| self.state.notes.get(index))
+        else {
+            self.state.status_message = "No note selected.".to_string();
+            return;
+        };
+        self.state.status_message = match self
+            .data_handler
+            .export_html(note, self.config.markdown_flavor)
+        {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(e) => format!("Error exporting note: {}", e),
+        };
+    }
+
+    /// Exports every note and the tasks to a directory, for `:export`.
+    fn export_vault(&mut self, dest: &str) {
+        let dest = expand_path(dest);
+        self.state.status_message =
+            match self
+                .data_handler
+                .export(&self.state.notes, &self.state.tasks, &dest)
+            {
+                Ok(count) => format!(
+                    "Exported {} note(s) and {} task(s) to {}",
+                    count,
+                    self.state.tasks.len(),
+                    dest.display()
+                ),
+                Err(e) => format!("Error exporting vault: {}", e),
+            };
+    }
+
+    /// Restores the most recently trashed note, for `:restore`.
+    fn restore_latest_trashed(&mut self) {
+        let trashed = match self.data_handler.load_trash() {
+            Ok(trash) => trash.into_iter().next(),
+            Err(e) => {
+                self.state.status_message = format!("Error loading trash: {}", e);
+                return;
             }
-            Message::SwitchToNoteList => self.state.current_view = View::NoteList,
-            Message::SwitchToCalendar => self.state.current_view = View::Calendar,
-            Message::SwitchToTasks => self.state.current_view = View::Tasks,
-            Message::PreviousMonth => {
-                if self.state.calendar_month == 1 {
-                    self.state.calendar_month = 12;
-                    self.state.calendar_year -= 1;
-                } else {
-                    self.state.calendar_month -= 1;
+        };
+        let Some(trashed) = trashed else {
+            self.state.status_message = "Trash is empty.".to_string();
+            return;
+        };
+        match self.data_handler.restore_note(
+            &trashed.trash_path,
+            &trashed.original_path,
+            &self.state.notes,
+        ) {
+            Ok(note) => {
+                // The file is back, so `u` has nothing left to undo for it
+                if matches!(&self.state.last_deleted,
+                    Some(DeletedItem::Note(_, _, path)) if *path == trashed.trash_path)
+                {
+                    self.state.last_deleted = None;
                 }
+                self.state.status_message = format!("'{}' restored.", note.title);
+                self.state.notes.push(note);
+                self.state
+                    .note_list_state
+                    .select(Some(self.state.notes.len() - 1));
+                self.state.invalidate_note_caches();
+                self.update_tags();
             }
-            Message::NextMonth => {
-                if self.state.calendar_month == 12 {
-                    self.state.calendar_month = 1;
-                    self.state.calendar_year += 1;
-                } else {
-                    self.state.calendar_month += 1;
-                }
+            Err(e) => self.state.status_message = format!("Error restoring note: {}", e),
+        }
+    }
+
+    /// Selects and opens the note given on the command line.
+    /// A file outside the notes directory triggers an import prompt instead.
+    pub fn open_note_target(&mut self, target: NoteTarget) {
+        let index = match &target {
+            NoteTarget::Path(path) => {
+                let wanted = path.canonicalize().unwrap_or_else(|_| path.clone());
+                self.state.notes.iter().position(|note| {
+                    note.path
+                        .canonicalize()
+                        .unwrap_or_else(|_| note.path.clone())
+                        == wanted
+                })
             }
-            Message::Save => {
-                if self.state.dirty {
-                    if let Err(e) = self.data_handler.save_notes(&self.state.notes) {
-                        self.state.status_message = format!("Error saving notes: {}", e);
-                    } else {
-                        self.state.status_message = "Notes saved successfully!".to_string();
-                        self.state.dirty = false;
-                        self.update_tags();
-                    }
-                } else {
-                    self.state.status_message = "No changes to save.".to_string();
-                }
+            NoteTarget::Title(title) => self
+                .state
+                .notes
+                .iter()
+                .position(|note| note.title == *title)
+                .or_else(|| {
+                    self.state
+                        .notes
+                        .iter()
+                        .position(|note| note.title.eq_ignore_ascii_case(title))
+                }),
+        };
+
+        match (index, target) {
+            (Some(index), _) => {
+                self.state.note_list_state.select(Some(index));
+                self.update(Message::OpenNote);
             }
-            Message::EnterInsertMode => {
-                self.state.mode = Mode::Insert;
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        self.state.cursor_offset = note.content.chars().count();
-                    }
-                }
-                self.state.status_message = "-- INSERT --".to_string();
+            (None, NoteTarget::Path(path)) if path.is_file() => {
+                self.state.ask_confirmation(
+                    Mode::ConfirmImport,
+                    format!(
+                        "'{}' is outside the notes directory. Import it?",
+                        path.display()
+                    ),
+                );
+                self.state.pending_import = Some(path);
             }
-            Message::EnterNormalMode => {
-                if let Mode::Insert = self.state.mode {
-                    self.state.dirty = true;
-                }
-                self.state.mode = Mode::Normal;
-                self.state.status_message = "".to_string();
-                self.state.command_input.clear();
+            (None, NoteTarget::Path(path)) => {
+                self.state.status_message = format!("No note found at '{}'", path.display());
             }
-            Message::EnterCommandMode => {
-                self.state.mode = Mode::Command;
-                self.state.command_input.push(':');
-                self.state.status_message = self.state.command_input.clone();
+            (None, NoteTarget::Title(title)) => {
+                self.state.status_message = format!("No note titled '{}'", title);
             }
-            Message::ExecuteCommand => {
-                let command = self.state.command_input.drain(1..).collect::<String>();
-                match command.as_str() {
-                    "w" | "write" => self.update(Message::Save),
-                    "q" | "quit" => self.update(Message::Quit),
-                    "wq" => {
-                        self.update(Message::Save);
-                        if !self.state.dirty {
-                            // only quit if save was successful
-                            self.update(Message::Quit);
-                        }
-                    }
-                    _ => self.state.status_message = format!("Not a command: {}", command),
-                }
-                if self.state.running {
-                    // if not quitting, return to normal mode
+        }
+    }
+
+    /// Updates the search results based on the current query.
+    fn update_search_results(&mut self) {
+        let mut scored: Vec<_> = self
+            .state
+            .notes
+            .iter()
+            .enumerate()
+            .filter(|(_, note)| self.state.show_archived || !note.archived)
+            .filter_map(|(i, note)| {
+                note_match(note, &self.state.search_query, self.state.search_mode)
+                    .map(|(score, search_match)| (i, score, search_match))
+            })
+            .collect();
+        // Best first; the stable sort keeps equal scores in note order
+        scored.sort_by_key(|&(_, score, _)| std::cmp::Reverse(score));
+        self.state.search_results = scored
+            .into_iter()
+            .map(|(i, _, search_match)| (i, search_match))
+            .collect();
+        // The ranking changed, so an old selection would point at a different note
+        self.state.search_list_state.select(None);
+    }
+
+    /// Returns the status bar prompt for the search query, showing the match mode.
+    fn search_prompt(&self) -> String {
+        match self.state.search_mode {
+            SearchMode::Substring => format!("/{}", self.state.search_query),
+            SearchMode::Fuzzy => format!("(fuzzy) /{}", self.state.search_query),
+        }
+    }
+
+    /// Converts an (x, y) cell within the editor's visible text area into a character offset,
+    /// accounting for the scroll offset. Clamped to the note's lines.
+    fn offset_from_screen(&self, x: u16, y: u16) -> usize {
+        if let Some(index) = self.state.note_list_state.selected()
+            && let Some(note) = self.state.notes.get(index)
+        {
+            return offset_at(
+                &note.content,
+                (x + self.state.scroll_column) as usize,
+                (y + self.state.scroll_offset) as usize,
+                self.state.wrap_width(note),
+            );
+        }
+        0
+    }
+
+    /// Returns the status bar prompt for the current title input.
+    fn title_input_prompt(&self) -> &'static str {
+        match self.state.current_view {
+            View::Tasks if self.state.task_list_state.selected().is_none() => "New Task: ",
+            View::Tasks => "Edit task: ",
+            _ if self.state.note_list_state.selected().is_none() => "New note title: ",
+            _ => "Rename note to: ",
+        }
+    }
+
+    /// Updates the global tag list from all notes.
+    fn update_tags(&mut self) {
+        // The notes have already changed by now, so a note selection saved here would be
+        // stale; callers that move notes around select the right one themselves
+        let selection = self.state.save_tag_selection();
+        let mut tags = dedup_tags(self.state.notes.iter().flat_map(|note| note.all_tags()));
+        tags.sort_by_cached_key(|tag| tag_key(tag));
+        self.state.tags = tags;
+        self.state.restore_selection(selection);
+    }
+
+    /// Applies a new tag filter and moves the note selection to the top of the filtered list.
+    fn apply_tag_filter(&mut self, filter: Vec<String>) {
+        self.state.tag_filter = filter;
+        self.state.refresh_filter();
+        let first = self.state.filtered_indices.first().copied();
+        self.state.note_list_state.select(first);
+    }
+
+    /// Moves the note selection within the filtered list. `step` maps the selected
+    /// note's position (if it is listed) and the list length to the new position.
+    fn step_note_selection(&mut self, step: impl Fn(Option<usize>, usize) -> Option<usize>) {
+        let len = self.state.filtered_indices.len();
+        if len == 0 {
+            return;
+        }
+        if let Some(position) = step(self.state.filtered_position(), len) {
+            let index = self.state.filtered_indices[position.min(len - 1)];
+            self.state.note_list_state.select(Some(index));
+        }
+    }
+
+    /// Records the open note's content before an edit and discards the redo history.
+    fn push_undo_snapshot(&mut self) {
+        if let Some(index) = self.state.note_list_state.selected()
+            && let Some(note) = self.state.notes.get(index)
+        {
+            self.state.undo_stack.push(EditSnapshot {
+                path: note.path.clone(),
+                content: note.content.clone(),
+                cursor_offset: self.state.cursor_offset,
+            });
+            self.state.redo_stack.clear();
+        }
+    }
+
+    /// Drops the latest snapshot if the edit after it changed nothing, so `u` never
+    /// has to be pressed for an empty edit.
+    fn drop_unchanged_snapshot(&mut self) {
+        if let Some(snapshot) = self.state.undo_stack.last()
+            && let Some(index) = self.state.note_list_state.selected()
+            && let Some(note) = self.state.notes.get(index)
+            && snapshot.path == note.path
+            && snapshot.content == note.content
+        {
+            self.state.undo_stack.pop();
+        }
+    }
+
+    /// Swaps the open note with the latest undo (or redo) snapshot, pushing its current
+    /// state onto the opposite stack. Returns false if there is nothing to restore for
+    /// this note, in which case neither stack changes.
+    fn step_history(&mut self, undo: bool) -> bool {
+        let (from, to) = if undo {
+            (&mut self.state.undo_stack, &mut self.state.redo_stack)
+        } else {
+            (&mut self.state.redo_stack, &mut self.state.undo_stack)
+        };
+        let Some(index) = self.state.note_list_state.selected() else {
+            return false;
+        };
+        let Some(note) = self.state.notes.get_mut(index) else {
+            return false;
+        };
+        if from
+            .last()
+            .is_none_or(|snapshot| snapshot.path != note.path)
+        {
+            return false;
+        }
+        let Some(snapshot) = from.pop() else {
+            return false;
+        };
+        to.push(EditSnapshot {
+            path: note.path.clone(),
+            content: std::mem::replace(&mut note.content, snapshot.content),
+            cursor_offset: self.state.cursor_offset,
+        });
+        note.dirty = true;
+        self.state.dirty = true;
+        self.state.cursor_offset = snapshot.cursor_offset.min(note.content.chars().count());
+        self.state.selection_anchor = None;
+        self.state.invalidate_note_caches();
+        true
+    }
+
+    /// Copies the focused task field's buffer into the draft task. Returns false, leaving
+    /// the draft untouched, if the description is blank or the due date does not parse.
+    fn apply_task_edit_buffer(&mut self) -> bool {
+        let Some(task) = &mut self.state.task_edit_draft else {
+            return false;
+        };
+        match self.state.task_edit_focus {
+            crate::app::state::TaskEditFocus::Description => {
+                if self.state.task_edit_buffer.trim().is_empty() {
+                    self.state.status_message = "Task description cannot be empty".to_string();
+                    return false;
+                }
+                task.description = self.state.task_edit_buffer.clone();
+            }
+            crate::app::state::TaskEditFocus::DueDate => {
+                let buffer = self.state.task_edit_buffer.trim();
+                if buffer.is_empty() {
+                    task.due_date = None;
+                } else if let Ok(date) = NaiveDate::parse_from_str(buffer, DATE_FORMAT) {
+                    task.due_date = Some(date);
+                } else {
+                    self.state.status_message = "Invalid date format (YYYY-MM-DD)".to_string();
+                    return false;
+                }
+            }
+            crate::app::state::TaskEditFocus::Priority => {}
+        }
+        true
+    }
+
+    /// Returns the display form already in use for a tag, or the trimmed tag if it is new.
+    fn canonical_tag(&self, tag: &str) -> String {
+        let key = tag_key(tag);
+        self.state
+            .tags
+            .iter()
+            .find(|t| tag_key(t) == key)
+            .cloned()
+            .unwrap_or_else(|| tag.trim().to_string())
+    }
+
+    /// Rewrites every note's front-matter tags to their canonical forms.
+    /// Returns the number of notes that changed.
+    fn normalize_tags(&mut self) -> usize {
+        self.update_tags();
+        let mut changed = 0;
+        for index in 0..self.state.notes.len() {
+            let tags = dedup_tags(
+                self.state.notes[index]
+                    .tags
+                    .iter()
+                    .map(|tag| self.canonical_tag(tag)),
+            );
+            let note = &mut self.state.notes[index];
+            if note.tags != tags {
+                note.tags = tags;
+                note.dirty = true;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.state.dirty = true;
+        }
+        changed
+    }
+
+    /// Returns the tag highlighted in the Tags pane.
+    fn selected_tag(&self) -> Option<String> {
+        self.state
+            .tag_list_state
+            .selected()
+            .and_then(|index| self.state.tags.get(index))
+            .cloned()
+    }
+
+    /// Returns the prompt for renaming the highlighted tag.
+    fn tag_rename_prompt(&self) -> String {
+        format!("Rename '{}' to: ", self.selected_tag().unwrap_or_default())
+    }
+
+    /// Replaces `old` with `new` in every note's front-matter tags, or removes it when
+    /// `new` is `None`. A rename onto a tag the note already has merges the two. The tag
+    /// filter follows along. Returns the number of notes that changed.
+    fn retag(&mut self, old: &str, new: Option<&str>) -> usize {
+        let key = tag_key(old);
+        let replace = |tags: &[String]| {
+            dedup_tags(tags.iter().filter_map(|tag| {
+                if tag_key(tag) == key {
+                    new.map(str::to_string)
+                } else {
+                    Some(tag.clone())
+                }
+            }))
+        };
+
+        let mut changed = 0;
+        for note in &mut self.state.notes {
+            let tags = replace(&note.tags);
+            if note.tags != tags {
+                note.tags = tags;
+                note.dirty = true;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.state.dirty = true;
+        }
+        self.state.active_tags = replace(&self.state.active_tags);
+        self.state.tag_filter = replace(&self.state.tag_filter);
+        self.update_tags();
+        self.state.refresh_filter();
+        changed
+    }
+
+    /// Returns a note for the status bar if `tag` is still written inline in some note,
+    /// since only front-matter tags are rewritten.
+    fn inline_tag_hint(&self, tag: &str) -> String {
+        let key = tag_key(tag);
+        let count = self
+            .state
+            .notes
+            .iter()
+            .filter(|note| {
+                parse_inline_tags(&note.content)
+                    .iter()
+                    .any(|t| tag_key(t) == key)
+            })
+            .count();
+        if count == 0 {
+            String::new()
+        } else {
+            format!(" Still written inline as #{} in {} note(s).", tag, count)
+        }
+    }
+
+    /// Saves the tasks to disk and updates the status message on failure.
+    fn save_tasks(&mut self) {
+        if let Err(e) = self.data_handler.save_tasks(&self.state.tasks) {
+            self.state.status_message = format!("Error auto-saving tasks: {}", e);
+        }
+    }
+
+    /// Returns how long to wait for input before the run loop checks its timers again.
+    fn poll_timeout(&self) -> Duration {
+        // A zero timeout would spin the CPU
+        let timeout = Duration::from_millis(self.config.poll_ms.max(1));
+        if self.config.autosave_secs == 0 || self.data_handler.is_in_memory() {
+            timeout.max(IDLE_POLL_INTERVAL)
+        } else {
+            timeout
+        }
+    }
+
+    fn handle_events(&self) -> Result<Option<Message>> {
+        if event::poll(self.poll_timeout())? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::Mouse(mouse) => return Ok(self.handle_mouse(mouse)),
+                Event::Resize(..) => return Ok(Some(Message::Redraw)),
+                _ => return Ok(None),
+            };
+            if key.kind != KeyEventKind::Press {
+                return Ok(None);
+            }
+
+            // Handle modes first. There is deliberately no wildcard arm, so a new mode
+            // doesn't compile until it is given its keys here.
+            match self.state.mode {
+                Mode::Insert => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::NewLine)),
+                        KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            Ok(Some(Message::CursorWordLeft))
+                        }
+                        KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            Ok(Some(Message::CursorWordRight))
+                        }
+                        KeyCode::Left => Ok(Some(Message::CursorLeft)),
+                        KeyCode::Right => Ok(Some(Message::CursorRight)),
+                        KeyCode::Home => Ok(Some(Message::CursorLineStart)),
+                        KeyCode::End => Ok(Some(Message::CursorLineEnd)),
+                        KeyCode::Up => Ok(Some(Message::CursorUp)),
+                        KeyCode::Down => Ok(Some(Message::CursorDown)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        KeyCode::Tab => Ok(Some(Message::InsertTab)),
+                        KeyCode::BackTab => Ok(Some(Message::DedentLine)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::TitleInput => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::SetNoteTitle)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::ConfirmDeletion => {
+                    return match key.code {
+                        KeyCode::Char('y') => Ok(Some(Message::ConfirmDelete)),
+                        KeyCode::Char('n') | KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::TagRenameInput => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::CommitTagRename)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::TagInput => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::AddTag)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::DueDateInput => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::CommitDueDate)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::FindInNote => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::CancelFind)),
+                        KeyCode::Enter => Ok(Some(Message::CommitFind)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::ProjectInput => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::CommitTaskProject)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::Command => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::ExecuteCommand)),
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::ConfirmQuit => {
+                    return match key.code {
+                        KeyCode::Char('y') => Ok(Some(Message::ForceQuit)),
+                        KeyCode::Char('n') | KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::ConfirmImport => {
+                    return match key.code {
+                        KeyCode::Char('y') => Ok(Some(Message::ConfirmImport)),
+                        KeyCode::Char('n') | KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::EditTask => {
+                    match key.code {
+                        KeyCode::Esc => return Ok(Some(Message::ExitEditTask)),
+                        KeyCode::Enter => return Ok(Some(Message::CommitEditTask)),
+                        KeyCode::Tab => return Ok(Some(Message::SwitchTaskEditFocus)),
+                        _ => {}
+                    }
+                    return match self.state.task_edit_focus {
+                        crate::app::state::TaskEditFocus::Priority => match key.code {
+                            KeyCode::Left => Ok(Some(Message::CyclePriorityBackward)),
+                            KeyCode::Right => Ok(Some(Message::CyclePriorityForward)),
+                            _ => Ok(None),
+                        },
+                        crate::app::state::TaskEditFocus::Description
+                        | crate::app::state::TaskEditFocus::DueDate => match key.code {
+                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                            _ => Ok(None),
+                        },
+                    };
+                }
+                Mode::EditSettings => {
+                    if self.state.settings_buffer.is_some() {
+                        return match key.code {
+                            KeyCode::Esc => Ok(Some(Message::CancelSettingEdit)),
+                            KeyCode::Enter => Ok(Some(Message::CommitSetting)),
+                            KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                            KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                            _ => Ok(None),
+                        };
+                    }
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::CloseSettings)),
+                        KeyCode::Char('j') | KeyCode::Down => Ok(Some(Message::NextSetting)),
+                        KeyCode::Char('k') | KeyCode::Up => Ok(Some(Message::PreviousSetting)),
+                        KeyCode::Enter => Ok(Some(Message::EditSetting)),
+                        KeyCode::Char('s') => Ok(Some(Message::SaveSettings)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::BrowseBacklinks => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Char('j') | KeyCode::Down => Ok(Some(Message::NextBacklink)),
+                        KeyCode::Char('k') | KeyCode::Up => Ok(Some(Message::PreviousBacklink)),
+                        KeyCode::Enter => Ok(Some(Message::OpenBacklink)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::QuickSwitch => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Enter => Ok(Some(Message::OpenSwitcherNote)),
+                        KeyCode::Down => Ok(Some(Message::NextSwitcherNote)),
+                        KeyCode::Up => Ok(Some(Message::PreviousSwitcherNote)),
+                        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            Ok(Some(Message::NextSwitcherNote))
+                        }
+                        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            Ok(Some(Message::PreviousSwitcherNote))
+                        }
+                        KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                        KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::BrowseOrphans => {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Char('j') | KeyCode::Down => Ok(Some(Message::NextOrphan)),
+                        KeyCode::Char('k') | KeyCode::Up => Ok(Some(Message::PreviousOrphan)),
+                        KeyCode::Enter => Ok(Some(Message::OpenOrphan)),
+                        _ => Ok(None),
+                    };
+                }
+                // Motions extend the selection; an operator ends the mode
+                Mode::Visual => {
+                    return match key.code {
+                        KeyCode::Esc | KeyCode::Char('v') => Ok(Some(Message::EnterNormalMode)),
+                        KeyCode::Char('h') | KeyCode::Left => Ok(Some(Message::CursorLeft)),
+                        KeyCode::Char('l') | KeyCode::Right => Ok(Some(Message::CursorRight)),
+                        KeyCode::Char('k') | KeyCode::Up => Ok(Some(Message::CursorUp)),
+                        KeyCode::Char('j') | KeyCode::Down => Ok(Some(Message::CursorDown)),
+                        KeyCode::Char('b') => Ok(Some(Message::CursorWordLeft)),
+                        KeyCode::Char('w') => Ok(Some(Message::CursorWordRight)),
+                        KeyCode::Char('0') | KeyCode::Home => Ok(Some(Message::CursorLineStart)),
+                        KeyCode::Char('$') | KeyCode::End => Ok(Some(Message::CursorLineEnd)),
+                        KeyCode::Char('d' | 'x') => Ok(Some(Message::Cut)),
+                        KeyCode::Char('y') => Ok(Some(Message::Yank)),
+                        _ => Ok(None),
+                    };
+                }
+                Mode::Normal => {
+                    // Fall through to view-specific and global handlers
+                }
+            }
+
+            if key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(Some(Message::OpenQuickSwitcher));
+            }
+
+            // Quick save without the command-line round-trip
+            if key.code == KeyCode::Char('s')
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+                && matches!(self.state.current_view, View::NoteList | View::NoteEditor)
+            {
+                return Ok(Some(Message::Save));
+            }
+
+            // Handle special views like Search that have their own input
+            if let View::Search = self.state.current_view {
+                if key.code == KeyCode::Char('f') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(Some(Message::ToggleSearchMode));
+                }
+                if self.state.search_results_focus {
+                    return match key.code {
+                        KeyCode::Esc => Ok(Some(Message::FocusSearchInput)),
+                        KeyCode::Char('j') | KeyCode::Down => Ok(Some(Message::NextSearchResult)),
+                        KeyCode::Char('k') | KeyCode::Up => Ok(Some(Message::PreviousSearchResult)),
+                        KeyCode::Enter => Ok(Some(Message::OpenSearchResult)),
+                        KeyCode::Char(c @ '1'..='9') => Ok(Some(Message::OpenNthSearchResult(
+                            c as usize - '1' as usize,
+                        ))),
+                        _ => Ok(None),
+                    };
+                }
+                return match key.code {
+                    KeyCode::Esc => Ok(Some(Message::ExitSearch)),
+                    // Ctrl+n / Ctrl+p pick a result without leaving the query
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        Ok(Some(Message::NextSearchResult))
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        Ok(Some(Message::PreviousSearchResult))
+                    }
+                    KeyCode::Enter if self.state.search_list_state.selected().is_some() => {
+                        Ok(Some(Message::OpenSearchResult))
+                    }
+                    KeyCode::Enter | KeyCode::Down => Ok(Some(Message::FocusSearchResults)),
+                    KeyCode::Char(c) => Ok(Some(Message::Char(c))),
+                    KeyCode::Backspace => Ok(Some(Message::Backspace)),
+                    _ => Ok(None),
+                };
+            }
+
+            if let View::Help = self.state.current_view {
+                return match key.code {
+                    KeyCode::Char('?') | KeyCode::Esc => Ok(Some(Message::ToggleHelp)),
+                    _ => Ok(None),
+                };
+            }
+
+            // Bindings from the config win over the built-in keys below
+            if !self.state.preview
+                && self.state.pending_operator.is_none()
+                && let Some(message) = self.bound_message(&key)
+            {
+                return Ok(Some(message));
+            }
+
+            // View-specific keybindings in Normal mode
+            match self.state.current_view {
+                View::NoteList => {
+                    if let KeyCode::Tab = key.code {
+                        return Ok(Some(Message::ToggleFocus));
+                    }
+                    match self.focus {
+                        Focus::NoteList => match key.code {
+                            KeyCode::Char('j') | KeyCode::Down => {
+                                return Ok(Some(Message::NextNote));
+                            }
+                            KeyCode::Char('k') | KeyCode::Up => {
+                                return Ok(Some(Message::PreviousNote));
+                            }
+                            KeyCode::Char('h') | KeyCode::Left => {
+                                return Ok(Some(Message::PreviousNoteColumn));
+                            }
+                            KeyCode::Char('l') | KeyCode::Right => {
+                                return Ok(Some(Message::NextNoteColumn));
+                            }
+                            KeyCode::Enter => return Ok(Some(Message::OpenNote)),
+                            KeyCode::Char('a') => return Ok(Some(Message::NewNote)),
+                            KeyCode::Char('r') => return Ok(Some(Message::RenameNote)),
+                            KeyCode::Char('d') => return Ok(Some(Message::DeleteNote)),
+                            KeyCode::Char('u') => return Ok(Some(Message::UndoDelete)),
+                            KeyCode::Char('o') => return Ok(Some(Message::CycleSort)),
+                            KeyCode::Char('v') => return Ok(Some(Message::ToggleNoteDetails)),
+                            KeyCode::Char('B') => return Ok(Some(Message::ShowBacklinks)),
+                            KeyCode::Char('A') => return Ok(Some(Message::ArchiveNote)),
+                            KeyCode::Char('P') => return Ok(Some(Message::TogglePin)),
+                            KeyCode::Char('y') => return Ok(Some(Message::DuplicateNote)),
+                            KeyCode::Char('.') => return Ok(Some(Message::ToggleShowArchived)),
+                            _ => {}
+                        },
+                        Focus::TagList => match key.code {
+                            KeyCode::Char('j') | KeyCode::Down => {
+                                return Ok(Some(Message::NextTag));
+                            }
+                            KeyCode::Char('k') | KeyCode::Up => {
+                                return Ok(Some(Message::PreviousTag));
+                            }
+                            KeyCode::Enter => return Ok(Some(Message::SelectTag)),
+                            KeyCode::Char(' ') => return Ok(Some(Message::ToggleTagMark)),
+                            KeyCode::Char('c') => return Ok(Some(Message::ClearTagFilter)),
+                            KeyCode::Char('m') => return Ok(Some(Message::ToggleTagMatch)),
+                            KeyCode::Char('r') => return Ok(Some(Message::RenameTag)),
+                            KeyCode::Char('d') => return Ok(Some(Message::DeleteTag)),
+                            _ => {}
+                        },
+                    }
+                }
+                // The preview is read-only; only scrolling and leaving it are allowed
+                View::NoteEditor if self.state.preview => match key.code {
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::TogglePreview));
+                    }
+                    KeyCode::Char('i') => return Ok(Some(Message::EnterInsertMode)),
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        return Ok(Some(Message::ScrollPreview(1)));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        return Ok(Some(Message::ScrollPreview(-1)));
+                    }
+                    KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
+                    _ => {}
+                },
+                View::NoteEditor if self.state.pending_operator.is_some() => {
+                    return match (self.state.pending_operator, key.code) {
+                        (Some('d'), KeyCode::Char('d')) => Ok(Some(Message::DeleteLine)),
+                        (Some('d'), KeyCode::Char('w')) => Ok(Some(Message::DeleteWord)),
+                        (Some('y'), KeyCode::Char('y')) => Ok(Some(Message::Yank)),
+                        _ => Ok(Some(Message::CancelOperator)),
+                    };
+                }
+                View::NoteEditor => match key.code {
+                    KeyCode::Char('d') => return Ok(Some(Message::StartOperator('d'))),
+                    // With a selection, `y` yanks it at once; otherwise `yy` yanks the line
+                    KeyCode::Char('y') if self.state.selection_anchor.is_some() => {
+                        return Ok(Some(Message::Yank));
+                    }
+                    KeyCode::Char('y') => return Ok(Some(Message::StartOperator('y'))),
+                    KeyCode::Char('x') => return Ok(Some(Message::Cut)),
+                    KeyCode::Char('p') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::Paste));
+                    }
+                    // Terminals report Ctrl+/ differently, so Ctrl+f works as well
+                    KeyCode::Char('/' | 'f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::StartFind));
+                    }
+                    KeyCode::Char('n') if !self.state.find_query.is_empty() => {
+                        return Ok(Some(Message::FindNext));
+                    }
+                    KeyCode::Char('N') if !self.state.find_query.is_empty() => {
+                        return Ok(Some(Message::FindPrevious));
+                    }
+                    KeyCode::Esc if !self.state.find_query.is_empty() => {
+                        return Ok(Some(Message::ClearFind));
+                    }
+                    KeyCode::Enter => return Ok(Some(Message::FollowLink)),
+                    KeyCode::Char('B') => return Ok(Some(Message::ShowBacklinks)),
+                    KeyCode::Char('+') => return Ok(Some(Message::LineToTask)),
+                    KeyCode::Char('E') => return Ok(Some(Message::EditExternally)),
+                    KeyCode::Char('t') => return Ok(Some(Message::EnterTagInput)),
+                    KeyCode::Char('i') => return Ok(Some(Message::EnterInsertMode)),
+                    KeyCode::Char('v') => return Ok(Some(Message::EnterVisualMode)),
+                    KeyCode::Char('u') => return Ok(Some(Message::Undo)),
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::Redo));
+                    }
+                    KeyCode::Char('r') => return Ok(Some(Message::RenameNote)),
+                    KeyCode::Char('z') => return Ok(Some(Message::ToggleZenMode)),
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::TogglePreview));
+                    }
+                    KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
+                    _ => {}
+                },
+                View::Calendar => match key.code {
+                    // Left and right page by whatever the calendar currently shows
+                    KeyCode::Left | KeyCode::Right => {
+                        let forward = key.code == KeyCode::Right;
+                        return Ok(Some(match self.state.calendar_scale {
+                            CalendarScale::Month if forward => Message::NextMonth,
+                            CalendarScale::Month => Message::PreviousMonth,
+                            CalendarScale::Week => {
+                                Message::MoveCalendarDay(if forward { 7 } else { -7 })
+                            }
+                            CalendarScale::Day => {
+                                Message::MoveCalendarDay(if forward { 1 } else { -1 })
+                            }
+                        }));
+                    }
+                    KeyCode::Char('h') => return Ok(Some(Message::MoveCalendarDay(-1))),
+                    KeyCode::Char('l') => return Ok(Some(Message::MoveCalendarDay(1))),
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        return Ok(Some(Message::MoveCalendarDay(-7)));
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        return Ok(Some(Message::MoveCalendarDay(7)));
+                    }
+                    KeyCode::Char('t') => return Ok(Some(Message::JumpToToday)),
+                    KeyCode::Char('s') => return Ok(Some(Message::CycleCalendarScale)),
+                    KeyCode::Enter => return Ok(Some(Message::OpenDailyNote)),
+                    _ => {}
+                },
+                View::Tasks => match key.code {
+                    KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::MoveTaskUp));
+                    }
+                    KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Some(Message::MoveTaskDown));
+                    }
+                    KeyCode::Char('N') => return Ok(Some(Message::TaskToNote)),
+                    KeyCode::Char('K') => return Ok(Some(Message::MoveTaskUp)),
+                    KeyCode::Char('J') => return Ok(Some(Message::MoveTaskDown)),
+                    KeyCode::Char('j') | KeyCode::Down => return Ok(Some(Message::NextTask)),
+                    KeyCode::Char('k') | KeyCode::Up => return Ok(Some(Message::PreviousTask)),
+                    KeyCode::Char('a') => return Ok(Some(Message::NewTask)),
+                    KeyCode::Char('d') => return Ok(Some(Message::DeleteTask)),
+                    KeyCode::Char('u') => return Ok(Some(Message::UndoDelete)),
+                    KeyCode::Char('e') => return Ok(Some(Message::EnterEditTask)),
+                    KeyCode::Char('c') | KeyCode::Enter => return Ok(Some(Message::RenameTask)),
+                    KeyCode::Char(' ') => return Ok(Some(Message::ToggleTaskComplete)),
+                    KeyCode::Char('g') => return Ok(Some(Message::CycleTaskGrouping)),
+                    KeyCode::Char('m') => return Ok(Some(Message::SetTaskProject)),
+                    KeyCode::Char('f') => return Ok(Some(Message::CycleTaskFilter)),
+                    KeyCode::Char('P') => return Ok(Some(Message::CycleProjectFilter)),
+                    KeyCode::Char('p') => return Ok(Some(Message::CyclePriority)),
+                    KeyCode::Char('D') => return Ok(Some(Message::SetDueDate)),
+                    _ => {}
+                },
+                View::Trash => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        return Ok(Some(Message::NextTrashedNote));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        return Ok(Some(Message::PreviousTrashedNote));
+                    }
+                    KeyCode::Char('u') | KeyCode::Enter => return Ok(Some(Message::RestoreNote)),
+                    KeyCode::Char('d') => return Ok(Some(Message::PurgeNote)),
+                    KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
+                    _ => {}
+                },
+                View::Agenda => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        return Ok(Some(Message::ScrollAgenda(1)));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        return Ok(Some(Message::ScrollAgenda(-1)));
+                    }
+                    KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
+                    _ => {}
+                },
+                View::Recent => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        return Ok(Some(Message::NextRecentNote));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        return Ok(Some(Message::PreviousRecentNote));
+                    }
+                    KeyCode::Enter => return Ok(Some(Message::OpenRecentNote)),
+                    KeyCode::Esc => return Ok(Some(Message::SwitchToNoteList)),
+                    _ => {}
+                },
+                _ => {}
+            }
+
+            // Global keybindings in Normal mode
+            match key.code {
+                KeyCode::Char(':') => return Ok(Some(Message::EnterCommandMode)),
+                KeyCode::Char('/') => return Ok(Some(Message::EnterSearch)),
+                KeyCode::Char('?') => return Ok(Some(Message::ToggleHelp)),
+                KeyCode::Char('q') => return Ok(Some(Message::Quit)),
+                KeyCode::Char('n') => return Ok(Some(Message::SwitchToNoteList)),
+                KeyCode::Char('c') => return Ok(Some(Message::SwitchToCalendar)),
+                KeyCode::Char('T') => return Ok(Some(Message::SwitchToTasks)),
+                KeyCode::Char('W') => return Ok(Some(Message::SwitchToAgenda)),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Selects the note at a 1-based position in the listed notes, for `:goto`. In the
+    /// editor the note is opened as well.
+    fn goto_note(&mut self, position: &str) {
+        let len = self.state.filtered_indices.len();
+        match position.parse::<usize>() {
+            Ok(n @ 1..) if n <= len => {
+                let index = self.state.filtered_indices[n - 1];
+                self.state.note_list_state.select(Some(index));
+                match self.state.current_view {
+                    View::NoteEditor => self.update(Message::OpenNote),
+                    View::NoteList => {}
+                    _ => self.state.current_view = View::NoteList,
+                }
+                self.state.status_message = format!("Note {} of {}", n, len);
+            }
+            _ => {
+                self.state.status_message =
+                    format!("Error: no note {} (the list has {})", position, len);
+            }
+        }
+    }
+
+    /// Runs a `:s/pattern/replacement/` command on the open note, replacing the next
+    /// occurrence at or after the cursor, wrapping around, or every occurrence with the
+    /// `g` flag. The pattern is matched literally and case-sensitively.
+    fn substitute(&mut self, spec: &str) {
+        let substitution = match parse_substitution(spec) {
+            Ok(substitution) => substitution,
+            Err(error) => {
+                self.state.status_message = format!("Error: {}", error);
+                return;
+            }
+        };
+        let Some(index) = self
+            .state
+            .note_list_state
+            .selected()
+            .filter(|_| matches!(self.state.current_view, View::NoteEditor))
+        else {
+            self.state.status_message = "Error: open a note to substitute in it".to_string();
+            return;
+        };
+
+        // Everything is worked out before taking a snapshot, which clears the redo
+        // history, so a substitution that changes nothing leaves the history alone
+        let note = &self.state.notes[index];
+        let pattern = substitution.pattern.as_str();
+        let cursor = note
+            .content
+            .char_indices()
+            .nth(self.state.cursor_offset)
+            .map_or(note.content.len(), |(byte, _)| byte);
+        let Some(start) = note.content[cursor..]
+            .find(pattern)
+            .map(|i| cursor + i)
+            .or_else(|| note.content.find(pattern))
+        else {
+            self.state.status_message = format!("Pattern not found: {}", pattern);
+            return;
+        };
+        let (content, count, cursor_offset) = if substitution.global {
+            let count = note.content.matches(pattern).count();
+            let content = note.content.replace(pattern, &substitution.replacement);
+            (content, count, self.state.cursor_offset)
+        } else {
+            let mut content = note.content.clone();
+            content.replace_range(start..start + pattern.len(), &substitution.replacement);
+            (content, 1, note.content[..start].chars().count())
+        };
+        self.state.status_message = format!("Replaced {} occurrence(s).", count);
+        if content == note.content {
+            return;
+        }
+
+        self.push_undo_snapshot();
+        let note = &mut self.state.notes[index];
+        note.content = content;
+        note.dirty = true;
+        self.state.dirty = true;
+        self.state.selection_anchor = None;
+        self.state.cursor_offset = cursor_offset.min(note.content.chars().count());
+        self.state.invalidate_note_caches();
+    }
+
+    /// Moves the cursor to the first match of the find query at or after `offset`, or with
+    /// `forward` unset the last match before it, wrapping around the note. The status bar
+    /// shows which match is selected.
+    fn find_from(&mut self, offset: usize, forward: bool) {
+        let matches = self.state.find_matches();
+        let position = if forward {
+            matches
+                .iter()
+                .position(|&m| m >= offset)
+                .or(matches.first().map(|_| 0))
+        } else {
+            matches
+                .iter()
+                .rposition(|&m| m < offset)
+                .or(matches.len().checked_sub(1))
+        };
+        let prompt = match self.state.mode {
+            Mode::FindInNote => "Find: ",
+            _ => "/",
+        };
+        self.state.status_message = match position {
+            Some(i) => {
+                self.state.cursor_offset = matches[i];
+                format!(
+                    "{}{} ({}/{})",
+                    prompt,
+                    self.state.find_query,
+                    i + 1,
+                    matches.len()
+                )
+            }
+            None if self.state.find_query.is_empty() => prompt.to_string(),
+            None => format!("{}{} (no matches)", prompt, self.state.find_query),
+        };
+    }
+
+    /// Returns the message for a key bound in the config, if the binding applies to the
+    /// current view.
+    fn bound_message(&self, key: &KeyEvent) -> Option<Message> {
+        self.keybindings
+            .iter()
+            .filter(|(spec, _)| spec.matches(key))
+            .filter_map(|(_, action)| bindable_action(action))
+            .find(|(scope, _)| match scope {
+                Scope::Global => true,
+                Scope::NoteList => matches!(self.state.current_view, View::NoteList),
+                Scope::NoteEditor => matches!(self.state.current_view, View::NoteEditor),
+                Scope::Calendar => matches!(self.state.current_view, View::Calendar),
+                Scope::Tasks => matches!(self.state.current_view, View::Tasks),
+            })
+            .map(|(_, message)| message)
+    }
+
+    /// Translates a mouse event into a message.
+    fn handle_mouse(&self, mouse: MouseEvent) -> Option<Message> {
+        match (&self.state.current_view, mouse.kind) {
+            (View::NoteEditor, MouseEventKind::Down(MouseButton::Left))
+                if !self.state.preview && !matches!(self.state.mode, Mode::Visual) =>
+            {
+                Some(Message::MouseClick(mouse.column, mouse.row))
+            }
+            _ => None,
+        }
+    }
+
+    /// Updates the application state based on a message.
+    fn update(&mut self, message: Message) {
+        // Keyboard edits and motions in the editor drop any mouse selection, while in
+        // Visual mode motions extend the selection instead
+        if !matches!(self.state.mode, Mode::Visual)
+            && matches!(
+                message,
+                Message::Char(_)
+                    | Message::Backspace
+                    | Message::NewLine
+                    | Message::CursorLeft
+                    | Message::CursorRight
+                    | Message::CursorUp
+                    | Message::CursorDown
+                    | Message::CursorLineStart
+                    | Message::CursorLineEnd
+                    | Message::CursorWordLeft
+                    | Message::CursorWordRight
+                    | Message::EnterInsertMode
+                    | Message::OpenNote
+            )
+        {
+            self.state.selection_anchor = None;
+        }
+
+        match message {
+            Message::Quit => {
+                if self.state.dirty && self.config.should_confirm(ConfirmAction::QuitUnsaved) {
+                    let unsaved = self.state.notes.iter().filter(|note| note.dirty).count();
+                    let prompt = match unsaved {
+                        0 => "You have unsaved changes. Quit without saving?".to_string(),
+                        1 => "1 note has unsaved changes. Quit without saving?".to_string(),
+                        n => format!("{} notes have unsaved changes. Quit without saving?", n),
+                    };
+                    self.state.ask_confirmation(Mode::ConfirmQuit, prompt);
+                } else {
+                    self.state.running = false;
+                }
+            }
+            Message::ForceQuit => {
+                self.state.running = false;
+            }
+            // Nothing to update: the next frame lays everything out at the new size, and the
+            // editor records its area and places the cursor as it is drawn
+            Message::Redraw => {}
+            Message::SwitchToNoteList => {
+                self.state.current_view = View::NoteList;
+                self.state.find_query.clear();
+            }
+            Message::SwitchToCalendar => self.state.current_view = View::Calendar,
+            Message::SwitchToTasks => self.state.current_view = View::Tasks,
+            Message::SwitchToAgenda => {
+                self.state.agenda_scroll = 0;
+                self.state.current_view = View::Agenda;
+            }
+            Message::ScrollAgenda(rows) => {
+                self.state.agenda_scroll = self.state.agenda_scroll.saturating_add_signed(rows);
+            }
+            Message::PreviousMonth => {
+                if self.state.calendar_month == 1 {
+                    self.state.calendar_month = 12;
+                    self.state.calendar_year -= 1;
+                } else {
+                    self.state.calendar_month -= 1;
+                }
+            }
+            Message::NextMonth => {
+                if self.state.calendar_month == 12 {
+                    self.state.calendar_month = 1;
+                    self.state.calendar_year += 1;
+                } else {
+                    self.state.calendar_month += 1;
+                }
+            }
+            Message::CycleSort => {
+                self.state.note_sort = self.state.note_sort.next();
+                self.state.sort_notes();
+                self.state.status_message =
+                    format!("Notes sorted by {}", self.state.note_sort.label());
+            }
+            Message::MoveCalendarDay(days) => {
+                let date = self.state.calendar_date() + chrono::Duration::days(days);
+                self.state.set_calendar_date(date);
+            }
+            Message::CycleCalendarScale => {
+                self.state.calendar_scale = self.state.calendar_scale.next();
+                self.state.status_message =
+                    format!("Calendar shows a {}", self.state.calendar_scale.label());
+            }
+            Message::JumpToToday => {
+                self.state.set_calendar_date(Local::now().date_naive());
+            }
+            Message::OpenDailyNote => {
+                let date = self.state.calendar_date();
+                let existing = self
+                    .state
+                    .notes
+                    .iter()
+                    .position(|note| daily_note_date(&note.path) == Some(date));
+                let index = match existing {
+                    Some(index) => index,
+                    None => {
+                        self.state
+                            .notes
+                            .push(self.data_handler.new_daily_note(date));
+                        self.state.invalidate_note_caches();
+                        self.state.dirty = true;
+                        self.state.notes.len() - 1
+                    }
+                };
+                self.state.note_list_state.select(Some(index));
+                self.update(Message::OpenNote);
+                if existing.is_none() {
+                    self.update(Message::EnterInsertMode);
+                }
+            }
+            Message::Save => {
+                if self.state.dirty {
+                    match self.write_dirty_notes() {
+                        Err(e) => {
+                            self.state.status_message = format!("Error saving notes: {}", e);
+                        }
+                        Ok(written) => {
+                            self.state.status_message = format!(
+                                "{} note{} saved",
+                                written,
+                                if written == 1 { "" } else { "s" }
+                            );
+                            if let Some(command) = &self.config.on_save_command {
+                                spawn_shell_command(
+                                    command.clone(),
+                                    self.data_handler.notes_dir.clone(),
+                                    self.hook_sender.clone(),
+                                );
+                            }
+                        }
+                    }
+                } else {
+                    self.state.status_message = "No changes to save.".to_string();
+                }
+            }
+            Message::EnterInsertMode => {
+                self.state.preview = false;
+                self.state.find_query.clear();
+                self.state.mode = Mode::Insert;
+                self.push_undo_snapshot();
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    self.state.cursor_offset = note.content.chars().count();
+                }
+                self.state.status_message = "-- INSERT --".to_string();
+            }
+            Message::EnterVisualMode => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let len = note.content.chars().count();
+                    // The cursor sits on a character, so the selection starts with one
+                    self.state.cursor_offset = self.state.cursor_offset.min(len.saturating_sub(1));
+                    self.state.selection_anchor = Some(self.state.cursor_offset);
+                    self.state.mode = Mode::Visual;
+                    self.state.status_message = "-- VISUAL --".to_string();
+                }
+            }
+            Message::EnterNormalMode => {
+                match self.state.mode {
+                    Mode::Insert => {
+                        self.state.dirty = true;
+                        self.drop_unchanged_snapshot();
+                    }
+                    Mode::Visual => self.state.selection_anchor = None,
+                    _ => {}
+                }
+                self.state.mode = Mode::Normal;
+                self.state.status_message = "".to_string();
+                self.state.command_input.clear();
+                self.state.note_template = None;
+            }
+            Message::EnterCommandMode => {
+                self.state.mode = Mode::Command;
+                self.state.command_input.push(':');
+                self.state.status_message = self.state.command_input.clone();
+            }
+            Message::ExecuteCommand => {
+                let command = self.state.command_input.drain(1..).collect::<String>();
+                let mut keep_status = false;
+                if let Some(option) = command.strip_prefix("set ") {
+                    self.set_view_option(option.trim(), false);
+                    keep_status = true;
+                } else if let Some(option) = command.strip_prefix("setlocal ") {
+                    self.set_view_option(option.trim(), true);
+                    keep_status = true;
+                } else if let Some(spec) = command
+                    .strip_prefix('%')
+                    .unwrap_or(&command)
+                    .strip_prefix('s')
+                    && !spec.starts_with(char::is_alphanumeric)
+                {
+                    self.substitute(spec);
+                    keep_status = true;
+                } else {
+                    let mut words = command.split_whitespace();
+                    let name = words.next().unwrap_or_default();
+                    let args: Vec<&str> = words.collect();
+                    match (name, args.as_slice()) {
+                        ("w" | "write", []) => self.update(Message::Save),
+                        ("q" | "quit", []) => self.update(Message::Quit),
+                        ("trash", []) => self.update(Message::OpenTrash),
+                        ("restore", []) => {
+                            self.restore_latest_trashed();
+                            keep_status = true;
+                        }
+                        ("recent", []) => self.update(Message::OpenRecent),
+                        ("agenda", []) => self.update(Message::SwitchToAgenda),
+                        ("edit", []) => {
+                            self.update(Message::EditExternally);
+                            keep_status = true;
+                        }
+                        ("config", []) => self.update(Message::OpenSettings),
+                        ("backlinks", []) => {
+                            self.update(Message::ShowBacklinks);
+                            keep_status = true;
+                        }
+                        ("stats", []) => {
+                            match self
+                                .state
+                                .note_list_state
+                                .selected()
+                                .and_then(|index| self.state.notes.get(index))
+                            {
+                                Some(note) => {
+                                    let stats = note.stats();
+                                    let reading = match stats.reading_minutes {
+                                        0 => "nothing to read".to_string(),
+                                        1 => "about 1 min read".to_string(),
+                                        n => format!("about {} min read", n),
+                                    };
+                                    self.state.status_message = format!(
+                                        "{}: {} words, {} chars, {} lines, {}",
+                                        note.title, stats.words, stats.chars, stats.lines, reading
+                                    );
+                                    keep_status = true;
+                                }
+                                None => {
+                                    self.state.status_message =
+                                        "Error: no note selected".to_string();
+                                }
+                            }
+                        }
+                        ("orphans", []) => {
+                            self.update(Message::OpenOrphans);
+                            keep_status = true;
+                        }
+                        ("html", []) => {
+                            self.export_selected_note();
+                            keep_status = true;
+                        }
+                        ("zen", []) => self.update(Message::ToggleZenMode),
+                        ("tag", ["normalize"]) => {
+                            let changed = self.normalize_tags();
+                            self.state.status_message =
+                                format!("Normalized tags in {} note(s).", changed);
+                            keep_status = true;
+                        }
+                        ("new", []) => {
+                            self.state.current_view = View::NoteList;
+                            self.update(Message::NewNote);
+                        }
+                        ("new", title) => {
+                            self.state.current_view = View::NoteList;
+                            self.state.note_list_state.select(None);
+                            self.state.command_input = title.join(" ");
+                            self.update(Message::SetNoteTitle);
+                        }
+                        ("new-from", [name, title @ ..]) => {
+                            match self.data_handler.load_template(name) {
+                                Ok(template) => {
+                                    self.state.current_view = View::NoteList;
+                                    self.update(Message::NewNote);
+                                    self.state.note_template = Some(template);
+                                    if title.is_empty() {
+                                        keep_status = true;
+                                    } else {
+                                        self.state.command_input = title.join(" ");
+                                        self.update(Message::SetNoteTitle);
+                                    }
+                                }
+                                Err(_) => {
+                                    let names = self.data_handler.template_names();
+                                    self.state.status_message = if names.is_empty() {
+                                        format!("Error: no template '{}'", name)
+                                    } else {
+                                        format!(
+                                            "Error: no template '{}' (available: {})",
+                                            name,
+                                            names.join(", ")
+                                        )
+                                    };
+                                }
+                            }
+                        }
+                        ("d" | "delete", []) => {
+                            if self.state.note_list_state.selected().is_some() {
+                                self.state.current_view = View::NoteList;
+                                self.update(Message::DeleteNote);
+                                keep_status = true;
+                            } else {
+                                self.state.status_message = "Error: no note selected".to_string();
+                            }
+                        }
+                        ("tag", tag) if !tag.is_empty() => {
+                            if self.state.note_list_state.selected().is_some() {
+                                self.state.command_input = tag.join(" ");
+                                self.update(Message::AddTag);
+                            } else {
+                                self.state.status_message = "Error: no note selected".to_string();
+                            }
+                        }
+                        ("export", path) if !path.is_empty() => {
+                            self.export_vault(&path.join(" "));
+                            keep_status = true;
+                        }
+                        ("goto", [position]) => {
+                            self.goto_note(position);
+                            keep_status = true;
+                        }
+                        ("sort", []) => {
+                            self.update(Message::CycleSort);
+                            keep_status = true;
+                        }
+                        ("sort", [order]) => match NoteSort::parse(order) {
+                            Some(sort) => {
+                                self.state.note_sort = sort;
+                                self.state.sort_notes();
+                                self.state.status_message =
+                                    format!("Notes sorted by {}", sort.label());
+                                keep_status = true;
+                            }
+                            None => {
+                                self.state.status_message = format!(
+                                    "Error: unknown sort '{}' (title, created or updated)",
+                                    order
+                                );
+                            }
+                        },
+                        ("wq", []) => {
+                            self.update(Message::Save);
+                            if !self.state.dirty {
+                                // only quit if save was successful
+                                self.update(Message::Quit);
+                            }
+                        }
+                        _ => self.state.status_message = format!("Not a command: {}", command),
+                    }
+                }
+                // Leave the mode alone if the command opened a prompt or switched modes
+                if self.state.running && matches!(self.state.mode, Mode::Command) {
+                    // if not quitting, return to normal mode
                     self.state.mode = Mode::Normal;
-                    if !self.state.status_message.starts_with("Error")
+                    if !keep_status
+                        && !self.state.status_message.starts_with("Error")
                         && !self.state.status_message.starts_with("Not a command")
                     {
-                        self.state.status_message = "".to_string();
+                        self.state.status_message = "".to_string();
+                    }
+                }
+            }
+            Message::Char(c) => match self.state.mode {
+                Mode::Insert => {
+                    if let Some(index) = self.state.note_list_state.selected()
+                        && let Some(note) = self.state.notes.get_mut(index)
+                    {
+                        let offset = self.state.cursor_offset.min(note.content.chars().count());
+                        let mut content: Vec<char> = note.content.chars().collect();
+                        content.insert(offset, c);
+                        note.content = content.into_iter().collect();
+                        note.dirty = true;
+                        self.state.invalidate_note_caches();
+                        self.state.cursor_offset += 1;
+                    }
+                }
+                Mode::Command => {
+                    self.state.command_input.push(c);
+                    self.state.status_message = self.state.command_input.clone();
+                }
+                Mode::TitleInput => {
+                    let prefix = self.title_input_prompt();
+                    self.state.command_input.push(c);
+                    self.state.status_message = format!("{}{}", prefix, self.state.command_input);
+                }
+                Mode::TagInput => {
+                    self.state.command_input.push(c);
+                    self.state.status_message = format!("Add Tag: {}", self.state.command_input);
+                }
+                Mode::TagRenameInput => {
+                    self.state.command_input.push(c);
+                    self.state.status_message =
+                        format!("{}{}", self.tag_rename_prompt(), self.state.command_input);
+                }
+                Mode::FindInNote => {
+                    self.state.find_query.push(c);
+                    self.find_from(self.state.find_origin, true);
+                }
+                Mode::DueDateInput => {
+                    self.state.command_input.push(c);
+                    self.state.status_message =
+                        format!("{}{}", DUE_DATE_PROMPT, self.state.command_input);
+                }
+                Mode::ProjectInput => {
+                    self.state.command_input.push(c);
+                    self.state.status_message =
+                        format!("{}{}", PROJECT_PROMPT, self.state.command_input);
+                }
+                Mode::Normal => {
+                    if let View::Search = self.state.current_view {
+                        self.state.search_query.push(c);
+                        self.update_search_results();
+                        self.state.status_message = self.search_prompt();
+                    }
+                }
+                Mode::ConfirmDeletion => {}
+                Mode::ConfirmQuit => {}
+                Mode::ConfirmImport => {}
+                Mode::Visual => {}
+                Mode::QuickSwitch => {
+                    self.state.switcher_query.push(c);
+                    let count = self.state.switcher_matches().len();
+                    self.state
+                        .switcher_list_state
+                        .select(if count == 0 { None } else { Some(0) });
+                }
+                Mode::BrowseOrphans | Mode::BrowseBacklinks => {}
+                Mode::EditTask => {
+                    if let crate::app::state::TaskEditFocus::Description
+                    | crate::app::state::TaskEditFocus::DueDate = self.state.task_edit_focus
+                    {
+                        self.state.task_edit_buffer.push(c);
+                    }
+                }
+                Mode::EditSettings => {
+                    if let Some(buffer) = &mut self.state.settings_buffer {
+                        buffer.push(c);
+                    }
+                }
+            },
+            Message::Backspace => match self.state.mode {
+                Mode::Insert => {
+                    if let Some(index) = self.state.note_list_state.selected()
+                        && let Some(note) = self.state.notes.get_mut(index)
+                        && self.state.cursor_offset > 0
+                    {
+                        let offset = self.state.cursor_offset.min(note.content.chars().count());
+                        let mut content: Vec<char> = note.content.chars().collect();
+                        content.remove(offset - 1);
+                        note.content = content.into_iter().collect();
+                        note.dirty = true;
+                        self.state.invalidate_note_caches();
+                        self.state.cursor_offset -= 1;
+                    }
+                }
+                Mode::Command => {
+                    self.state.command_input.pop();
+                    if self.state.command_input.is_empty() {
+                        self.update(Message::EnterNormalMode);
+                    } else {
+                        self.state.status_message = self.state.command_input.clone();
+                    }
+                }
+                Mode::TitleInput => {
+                    let prefix = self.title_input_prompt();
+                    self.state.command_input.pop();
+                    self.state.status_message = format!("{}{}", prefix, self.state.command_input);
+                }
+                Mode::TagInput => {
+                    self.state.command_input.pop();
+                    self.state.status_message = format!("Add Tag: {}", self.state.command_input);
+                }
+                Mode::TagRenameInput => {
+                    self.state.command_input.pop();
+                    self.state.status_message =
+                        format!("{}{}", self.tag_rename_prompt(), self.state.command_input);
+                }
+                Mode::FindInNote => {
+                    self.state.find_query.pop();
+                    self.find_from(self.state.find_origin, true);
+                }
+                Mode::DueDateInput => {
+                    self.state.command_input.pop();
+                    self.state.status_message =
+                        format!("{}{}", DUE_DATE_PROMPT, self.state.command_input);
+                }
+                Mode::ProjectInput => {
+                    self.state.command_input.pop();
+                    self.state.status_message =
+                        format!("{}{}", PROJECT_PROMPT, self.state.command_input);
+                }
+                Mode::Normal => {
+                    if let View::Search = self.state.current_view {
+                        self.state.search_query.pop();
+                        self.update_search_results();
+                        self.state.status_message = self.search_prompt();
+                    }
+                }
+                Mode::ConfirmDeletion => {}
+                Mode::ConfirmQuit => {}
+                Mode::ConfirmImport => {}
+                Mode::Visual => {}
+                Mode::QuickSwitch => {
+                    self.state.switcher_query.pop();
+                    let count = self.state.switcher_matches().len();
+                    self.state
+                        .switcher_list_state
+                        .select(if count == 0 { None } else { Some(0) });
+                }
+                Mode::BrowseOrphans | Mode::BrowseBacklinks => {}
+                Mode::EditTask => {
+                    if let crate::app::state::TaskEditFocus::Description
+                    | crate::app::state::TaskEditFocus::DueDate = self.state.task_edit_focus
+                    {
+                        self.state.task_edit_buffer.pop();
+                    }
+                }
+                Mode::EditSettings => {
+                    if let Some(buffer) = &mut self.state.settings_buffer {
+                        buffer.pop();
+                    }
+                }
+            },
+            Message::EnterSearch => {
+                self.state.current_view = View::Search;
+                self.state.search_query.clear();
+                self.state.status_message = self.search_prompt();
+                self.update_search_results();
+            }
+            Message::PreviousNoteColumn => {
+                let rows = self.state.note_grid_rows;
+                if rows > 0 {
+                    self.step_note_selection(|position, _| {
+                        position.map(|p| p.saturating_sub(rows))
+                    });
+                }
+            }
+            Message::NextNoteColumn => {
+                let rows = self.state.note_grid_rows;
+                if rows > 0 {
+                    self.step_note_selection(|position, _| position.map(|p| p + rows));
+                }
+            }
+            Message::ExitSearch => {
+                self.state.current_view = View::NoteList;
+                self.state.search_query.clear();
+                self.state.status_message = "".to_string();
+                self.state.search_results.clear();
+                self.state.search_results_focus = false;
+            }
+            Message::ToggleSearchMode => {
+                self.state.search_mode = match self.state.search_mode {
+                    SearchMode::Substring => SearchMode::Fuzzy,
+                    SearchMode::Fuzzy => SearchMode::Substring,
+                };
+                self.update_search_results();
+                self.state.status_message = self.search_prompt();
+                if self.state.search_results_focus {
+                    let selected = (!self.state.search_results.is_empty()).then_some(0);
+                    self.state.search_list_state.select(selected);
+                    self.state.search_results_focus = selected.is_some();
+                }
+            }
+            Message::FocusSearchResults => {
+                if !self.state.search_results.is_empty() {
+                    self.state.search_results_focus = true;
+                    self.state.search_list_state.select(Some(0));
+                }
+            }
+            Message::FocusSearchInput => {
+                // Keep the query so it can be refined
+                self.state.search_results_focus = false;
+                self.state.search_list_state.select(None);
+            }
+            Message::PreviousSearchResult => {
+                if !self.state.search_results.is_empty() {
+                    let new_i = match self.state.search_list_state.selected() {
+                        Some(i) if i > 0 => i - 1,
+                        _ => self.state.search_results.len() - 1,
+                    };
+                    self.state.search_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextSearchResult => {
+                if !self.state.search_results.is_empty() {
+                    let new_i = match self.state.search_list_state.selected() {
+                        Some(i) if i + 1 < self.state.search_results.len() => i + 1,
+                        _ => 0,
+                    };
+                    self.state.search_list_state.select(Some(new_i));
+                }
+            }
+            Message::OpenNthSearchResult(n) => {
+                if n < self.state.search_results.len() {
+                    self.state.search_list_state.select(Some(n));
+                    self.update(Message::OpenSearchResult);
+                }
+            }
+            Message::OpenSearchResult => {
+                if let Some(&(index, _)) = self
+                    .state
+                    .search_list_state
+                    .selected()
+                    .and_then(|i| self.state.search_results.get(i))
+                {
+                    self.state.note_list_state.select(Some(index));
+                    self.update(Message::ExitSearch);
+                    self.update(Message::OpenNote);
+                }
+            }
+            Message::PreviousNote => {
+                self.step_note_selection(|position, len| match position {
+                    Some(p) if p > 0 => Some(p - 1),
+                    _ => Some(len - 1),
+                });
+            }
+            Message::NextNote => {
+                self.step_note_selection(|position, len| match position {
+                    Some(p) if p + 1 < len => Some(p + 1),
+                    _ => Some(0),
+                });
+            }
+            Message::OpenNote => {
+                if let Some(index) = self.state.note_list_state.selected() {
+                    self.state.cursor_offset = 0;
+                    self.state.current_view = View::NoteEditor;
+                    self.state.find_query.clear();
+                    self.state.status_message = "".to_string();
+                    self.remember_recent(index);
+                }
+            }
+            Message::NewNote => {
+                self.state.note_list_state.select(None); // Deselect to indicate new note
+                self.state.note_template = None;
+                self.state.mode = Mode::TitleInput;
+                self.state.command_input.clear();
+                self.state.status_message = "New note title: ".to_string();
+            }
+            Message::NewTask => {
+                self.state.task_list_state.select(None);
+                self.state.mode = Mode::TitleInput;
+                self.state.command_input.clear();
+                self.state.status_message = "New Task: ".to_string();
+            }
+            Message::RenameNote => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    self.state.mode = Mode::TitleInput;
+                    self.state.command_input = note.title.clone();
+                    self.state.status_message =
+                        format!("Rename note to: {}", self.state.command_input);
+                }
+            }
+            Message::RenameTask => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get(index)
+                {
+                    self.state.mode = Mode::TitleInput;
+                    self.state.command_input = task.description.clone();
+                    self.state.status_message = format!("Edit task: {}", self.state.command_input);
+                }
+            }
+            Message::SetNoteTitle => {
+                let input = self.state.command_input.clone();
+                if input.trim().is_empty() {
+                    // Stay in the prompt so the input can be corrected
+                    let what = match self.state.current_view {
+                        View::Tasks => "Task description",
+                        _ => "Note title",
+                    };
+                    self.state.command_input.clear();
+                    self.state.status_message =
+                        format!("{} cannot be empty. {}", what, self.title_input_prompt());
+                    return;
+                }
+
+                match self.state.current_view {
+                    View::NoteList | View::NoteEditor => {
+                        let new_title = input;
+                        if let Some(index) = self.state.note_list_state.selected() {
+                            // This is a rename of an existing note
+                            if let Some(note) = self.state.notes.get_mut(index) {
+                                note.title = new_title;
+                                note.dirty = true;
+                                self.state.invalidate_note_caches();
+                                self.state.dirty = true;
+                                self.rename_note_file(index);
+                            }
+                        } else {
+                            // This is a new note
+                            let now = Local::now();
+                            let content = self
+                                .state
+                                .note_template
+                                .take()
+                                .map(|template| {
+                                    templates::expand(
+                                        &template,
+                                        &new_title,
+                                        now.date_naive(),
+                                        now.time(),
+                                    )
+                                })
+                                .unwrap_or_default();
+                            let new_note = self.data_handler.new_note(
+                                new_title,
+                                content,
+                                vec![],
+                                &self.state.notes,
+                            );
+
+                            self.state.notes.push(new_note);
+                            self.state.invalidate_note_caches();
+                            let new_note_index = self.state.notes.len() - 1;
+                            self.state.note_list_state.select(Some(new_note_index));
+                            self.state.current_view = View::NoteEditor;
+                            self.state.mode = Mode::Insert;
+                            self.state.status_message = "-- INSERT --".to_string();
+                            return; // Skip returning to normal mode
+                        }
+                    }
+                    View::Tasks => {
+                        let description = input;
+                        if let Some(index) = self.state.task_list_state.selected() {
+                            // This is an edit of an existing task's description
+                            if let Some(task) = self.state.tasks.get_mut(index) {
+                                task.description = description;
+                                self.save_tasks();
+                            }
+                        } else {
+                            let new_task = crate::app::state::Task {
+                                // Ids must stay unique so the selection can be tracked by id
+                                id: self.state.take_task_id(),
+                                description,
+                                project: None,
+                                priority: crate::app::state::Priority::Medium,
+                                due_date: None,
+                                completed: false,
+                                created_at: Utc::now(),
+                                sub_tasks: vec![],
+                            };
+                            self.state.tasks.push(new_task);
+                            let new_index = self.state.tasks.len() - 1;
+                            self.state.task_list_state.select(Some(new_index));
+                            self.save_tasks();
+                        }
+                    }
+                    _ => {}
+                }
+                self.update(Message::EnterNormalMode);
+            }
+            Message::DeleteNote => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    if self.config.should_confirm(ConfirmAction::DeleteNote) {
+                        let prompt = format!("Delete '{}'?", note.title);
+                        self.state.ask_confirmation(Mode::ConfirmDeletion, prompt);
+                    } else {
+                        self.update(Message::ConfirmDelete);
+                    }
+                }
+            }
+            Message::DeleteTask => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get(index)
+                {
+                    if self.config.should_confirm(ConfirmAction::DeleteTask) {
+                        let prompt = format!("Delete '{}'?", task.description);
+                        self.state.ask_confirmation(Mode::ConfirmDeletion, prompt);
+                    } else {
+                        self.update(Message::ConfirmDelete);
+                    }
+                }
+            }
+            Message::ConfirmDelete => {
+                // Offer an undo when the deletion happened without a prompt
+                let undo_hint = if let Mode::ConfirmDeletion = self.state.mode {
+                    ""
+                } else {
+                    " Press 'u' to undo."
+                };
+                match self.state.current_view {
+                    View::NoteList if matches!(self.focus, Focus::TagList) => {
+                        if let Some(tag) = self.selected_tag() {
+                            let key = tag_key(&tag);
+                            let previous = self
+                                .state
+                                .notes
+                                .iter()
+                                .filter(|note| note.tags.iter().any(|t| tag_key(t) == key))
+                                .map(|note| (note.path.clone(), note.tags.clone()))
+                                .collect();
+                            let changed = self.retag(&tag, None);
+                            self.state.status_message = format!(
+                                "Removed '{}' from {} note(s).{}{}",
+                                tag,
+                                changed,
+                                self.inline_tag_hint(&tag),
+                                undo_hint
+                            );
+                            self.state.last_deleted = Some(DeletedItem::Tag(tag, previous));
+                        }
+                    }
+                    View::NoteList => {
+                        if let Some(index) = self.state.note_list_state.selected() {
+                            let note_to_delete = self.state.notes[index].clone();
+                            match self.data_handler.delete_note(&note_to_delete) {
+                                Err(e) => {
+                                    self.state.status_message =
+                                        format!("Error deleting note: {}", e);
+                                }
+                                Ok(trash_path) => {
+                                    self.state.notes.remove(index);
+                                    self.state.invalidate_note_caches();
+                                    self.state.dirty = true; // The list of notes has changed
+                                    self.state.status_message = format!(
+                                        "'{}' moved to trash.{}",
+                                        note_to_delete.title, undo_hint
+                                    );
+                                    self.state.last_deleted =
+                                        Some(DeletedItem::Note(index, note_to_delete, trash_path));
+
+                                    if self.state.notes.is_empty() {
+                                        self.state.note_list_state.select(None);
+                                    } else if index >= self.state.notes.len() {
+                                        self.state
+                                            .note_list_state
+                                            .select(Some(self.state.notes.len() - 1));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    View::Trash => {
+                        if let Some(index) = self.state.trash_list_state.selected() {
+                            let trashed = self.state.trash[index].clone();
+                            if let Err(e) = self.data_handler.purge_note(&trashed) {
+                                self.state.status_message = format!("Error deleting note: {}", e);
+                            } else {
+                                self.state.trash.remove(index);
+                                self.state.status_message =
+                                    format!("'{}' permanently deleted.", trashed.title);
+                                if self.state.trash.is_empty() {
+                                    self.state.trash_list_state.select(None);
+                                } else if index >= self.state.trash.len() {
+                                    self.state
+                                        .trash_list_state
+                                        .select(Some(self.state.trash.len() - 1));
+                                }
+                            }
+                        }
+                    }
+                    View::Tasks => {
+                        if let Some(index) = self.state.task_list_state.selected() {
+                            let removed_task = self.state.tasks.remove(index);
+                            self.state.status_message =
+                                format!("'{}' deleted.{}", removed_task.description, undo_hint);
+                            self.state.last_deleted = Some(DeletedItem::Task(index, removed_task));
+                            self.save_tasks();
+
+                            if self.state.tasks.is_empty() {
+                                self.state.task_list_state.select(None);
+                            } else if index >= self.state.tasks.len() {
+                                self.state
+                                    .task_list_state
+                                    .select(Some(self.state.tasks.len() - 1));
+                            }
+                            self.state.keep_task_selection_listed();
+                        }
+                    }
+                    _ => {}
+                }
+                // Return to normal mode without clearing the deletion status
+                self.state.mode = Mode::Normal;
+                self.state.command_input.clear();
+            }
+            Message::ConfirmImport => {
+                self.state.mode = Mode::Normal;
+                if let Some(path) = self.state.pending_import.take() {
+                    match self.data_handler.import_note(&path, &self.state.notes) {
+                        Ok(note) => {
+                            self.own_writes.insert(note.path.clone(), Instant::now());
+                            self.state.notes.push(note);
+                            self.state.invalidate_note_caches();
+                            self.update_tags();
+                            self.state
+                                .note_list_state
+                                .select(Some(self.state.notes.len() - 1));
+                            self.update(Message::OpenNote);
+                        }
+                        Err(e) => {
+                            self.state.status_message = format!("Error importing note: {}", e);
+                        }
+                    }
+                }
+            }
+            Message::UndoDelete => match self.state.last_deleted.take() {
+                Some(DeletedItem::Note(index, mut note, trash_path)) => {
+                    match self
+                        .data_handler
+                        .restore_note(&trash_path, &note.path, &self.state.notes)
+                    {
+                        Err(e) => {
+                            self.state.status_message = format!("Error restoring note: {}", e);
+                            self.state.last_deleted =
+                                Some(DeletedItem::Note(index, note, trash_path));
+                        }
+                        Ok(restored) => {
+                            // Keep any unsaved edits from the in-memory copy
+                            note.path = restored.path;
+                            let index = index.min(self.state.notes.len());
+                            self.state.status_message = format!("'{}' restored.", note.title);
+                            self.state.notes.insert(index, note);
+                            self.state.invalidate_note_caches();
+                            self.state.note_list_state.select(Some(index));
+                            self.update_tags();
+                        }
+                    }
+                }
+                Some(DeletedItem::Task(index, task)) => {
+                    let index = index.min(self.state.tasks.len());
+                    self.state.status_message = format!("'{}' restored.", task.description);
+                    self.state.tasks.insert(index, task);
+                    self.state.task_list_state.select(Some(index));
+                    self.save_tasks();
+                }
+                Some(DeletedItem::Tag(tag, previous)) => {
+                    for (path, tags) in previous {
+                        if let Some(note) = self.state.notes.iter_mut().find(|n| n.path == path) {
+                            note.tags = tags;
+                            note.dirty = true;
+                        }
                     }
+                    self.state.dirty = true;
+                    self.update_tags();
+                    self.state.status_message = format!("'{}' restored.", tag);
+                }
+                None => self.state.status_message = "Nothing to undo.".to_string(),
+            },
+            Message::OpenOrphans => {
+                self.state.orphans = self
+                    .state
+                    .backlink_counts()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, count)| **count == 0)
+                    .map(|(index, _)| index)
+                    .collect();
+                if self.state.orphans.is_empty() {
+                    self.state.status_message = "Every note has incoming links.".to_string();
+                } else {
+                    self.state.orphan_list_state.select(Some(0));
+                    self.state.mode = Mode::BrowseOrphans;
                 }
             }
-            Message::Char(c) => match self.state.mode {
-                Mode::Insert => {
-                    if let Some(index) = self.state.note_list_state.selected() {
-                        if let Some(note) = self.state.notes.get_mut(index) {
-                            let offset = self.state.cursor_offset.min(note.content.chars().count());
-                            let mut content: Vec<char> = note.content.chars().collect();
-                            content.insert(offset, c);
-                            note.content = content.into_iter().collect();
-                            self.state.cursor_offset += 1;
+            Message::PreviousOrphan => {
+                let count = self.state.orphans.len();
+                if count > 0 {
+                    let i = self.state.orphan_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 { count - 1 } else { i - 1 };
+                    self.state.orphan_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextOrphan => {
+                let count = self.state.orphans.len();
+                if count > 0 {
+                    let i = self.state.orphan_list_state.selected().unwrap_or(0);
+                    let new_i = if i >= count - 1 { 0 } else { i + 1 };
+                    self.state.orphan_list_state.select(Some(new_i));
+                }
+            }
+            Message::OpenOrphan => {
+                if let Some(&index) = self
+                    .state
+                    .orphan_list_state
+                    .selected()
+                    .and_then(|i| self.state.orphans.get(i))
+                {
+                    self.state.mode = Mode::Normal;
+                    self.state.note_list_state.select(Some(index));
+                    self.update(Message::OpenNote);
+                }
+            }
+            Message::ShowBacklinks => {
+                let Some(index) = self.state.note_list_state.selected() else {
+                    self.state.status_message = "No note selected.".to_string();
+                    return;
+                };
+                self.state.backlink_sources = linking_notes(&self.state.notes, index);
+                if self.state.backlink_sources.is_empty() {
+                    self.state.status_message =
+                        format!("No notes link to '{}'.", self.state.notes[index].title);
+                } else {
+                    self.state.backlink_list_state.select(Some(0));
+                    self.state.mode = Mode::BrowseBacklinks;
+                }
+            }
+            Message::PreviousBacklink => {
+                let count = self.state.backlink_sources.len();
+                if count > 0 {
+                    let i = self.state.backlink_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 { count - 1 } else { i - 1 };
+                    self.state.backlink_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextBacklink => {
+                let count = self.state.backlink_sources.len();
+                if count > 0 {
+                    let i = self.state.backlink_list_state.selected().unwrap_or(0);
+                    self.state.backlink_list_state.select(Some((i + 1) % count));
+                }
+            }
+            Message::OpenBacklink => {
+                if let Some(&index) = self
+                    .state
+                    .backlink_list_state
+                    .selected()
+                    .and_then(|i| self.state.backlink_sources.get(i))
+                {
+                    self.state.mode = Mode::Normal;
+                    self.state.note_list_state.select(Some(index));
+                    self.update(Message::OpenNote);
+                }
+            }
+            Message::OpenRecent => {
+                let count = self.state.recent_note_indices().len();
+                self.state
+                    .recent_list_state
+                    .select(if count == 0 { None } else { Some(0) });
+                self.state.current_view = View::Recent;
+            }
+            Message::PreviousRecentNote => {
+                let count = self.state.recent_note_indices().len();
+                if count > 0 {
+                    let i = self.state.recent_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 { count - 1 } else { i - 1 };
+                    self.state.recent_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextRecentNote => {
+                let count = self.state.recent_note_indices().len();
+                if count > 0 {
+                    let i = self.state.recent_list_state.selected().unwrap_or(0);
+                    let new_i = if i >= count - 1 { 0 } else { i + 1 };
+                    self.state.recent_list_state.select(Some(new_i));
+                }
+            }
+            Message::OpenRecentNote => {
+                if let Some(index) = self
+                    .state
+                    .recent_list_state
+                    .selected()
+                    .and_then(|i| self.state.recent_note_indices().get(i).copied())
+                {
+                    self.state.note_list_state.select(Some(index));
+                    self.update(Message::OpenNote);
+                }
+            }
+            Message::OpenQuickSwitcher => {
+                self.state.switcher_query.clear();
+                let count = self.state.switcher_matches().len();
+                if count == 0 {
+                    self.state.status_message = "No recently opened notes.".to_string();
+                } else {
+                    self.state.switcher_list_state.select(Some(0));
+                    self.state.mode = Mode::QuickSwitch;
+                }
+            }
+            Message::PreviousSwitcherNote => {
+                let count = self.state.switcher_matches().len();
+                if count > 0 {
+                    let i = self.state.switcher_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 { count - 1 } else { i - 1 };
+                    self.state.switcher_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextSwitcherNote => {
+                let count = self.state.switcher_matches().len();
+                if count > 0 {
+                    let i = self.state.switcher_list_state.selected().unwrap_or(0);
+                    self.state.switcher_list_state.select(Some((i + 1) % count));
+                }
+            }
+            Message::OpenSwitcherNote => {
+                if let Some(index) = self
+                    .state
+                    .switcher_list_state
+                    .selected()
+                    .and_then(|i| self.state.switcher_matches().get(i).copied())
+                {
+                    self.state.mode = Mode::Normal;
+                    self.state.note_list_state.select(Some(index));
+                    self.update(Message::OpenNote);
+                }
+            }
+            Message::OpenTrash => match self.data_handler.load_trash() {
+                Ok(trash) => {
+                    self.state.trash_list_state.select(if trash.is_empty() {
+                        None
+                    } else {
+                        Some(0)
+                    });
+                    self.state.trash = trash;
+                    self.state.current_view = View::Trash;
+                }
+                Err(e) => self.state.status_message = format!("Error loading trash: {}", e),
+            },
+            Message::PreviousTrashedNote => {
+                if !self.state.trash.is_empty() {
+                    let i = self.state.trash_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 {
+                        self.state.trash.len() - 1
+                    } else {
+                        i - 1
+                    };
+                    self.state.trash_list_state.select(Some(new_i));
+                }
+            }
+            Message::NextTrashedNote => {
+                if !self.state.trash.is_empty() {
+                    let i = self.state.trash_list_state.selected().unwrap_or(0);
+                    let new_i = if i >= self.state.trash.len() - 1 {
+                        0
+                    } else {
+                        i + 1
+                    };
+                    self.state.trash_list_state.select(Some(new_i));
+                }
+            }
+            Message::RestoreNote => {
+                if let Some(index) = self.state.trash_list_state.selected() {
+                    let trashed = self.state.trash[index].clone();
+                    match self.data_handler.restore_note(
+                        &trashed.trash_path,
+                        &trashed.original_path,
+                        &self.state.notes,
+                    ) {
+                        Ok(note) => {
+                            self.state.status_message = format!("'{}' restored.", note.title);
+                            self.state.notes.push(note);
+                            self.state.invalidate_note_caches();
+                            self.update_tags();
+                            self.state.trash.remove(index);
+                            if self.state.trash.is_empty() {
+                                self.state.trash_list_state.select(None);
+                            } else if index >= self.state.trash.len() {
+                                self.state
+                                    .trash_list_state
+                                    .select(Some(self.state.trash.len() - 1));
+                            }
                         }
+                        Err(e) => {
+                            self.state.status_message = format!("Error restoring note: {}", e);
+                        }
+                    }
+                }
+            }
+            Message::PurgeNote => {
+                if let Some(index) = self.state.trash_list_state.selected()
+                    && let Some(trashed) = self.state.trash.get(index)
+                {
+                    let prompt = format!("Permanently delete '{}'?", trashed.title);
+                    self.state.ask_confirmation(Mode::ConfirmDeletion, prompt);
+                }
+            }
+            Message::MouseClick(column, row) => {
+                let area = self.state.editor_area;
+                if column < area.x || row < area.y || column >= area.right() || row >= area.bottom()
+                {
+                    return;
+                }
+
+                let now = Instant::now();
+                let count = match self.state.last_click {
+                    Some((at, last_column, last_row, count))
+                        if last_column == column
+                            && last_row == row
+                            && now.duration_since(at) < MULTI_CLICK_INTERVAL =>
+                    {
+                        count % 3 + 1
+                    }
+                    _ => 1,
+                };
+                self.state.last_click = Some((now, column, row, count));
+
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let offset = self.offset_from_screen(column - area.x, row - area.y);
+                    let chars: Vec<char> = note.content.chars().collect();
+                    let (start, end) = match count {
+                        1 => (offset, offset),
+                        2 => word_bounds(&chars, offset),
+                        _ => line_bounds(&chars, offset),
+                    };
+                    self.state.selection_anchor = if start == end { None } else { Some(start) };
+                    self.state.cursor_offset = end;
+                }
+            }
+            Message::ToggleZenMode => {
+                self.state.zen_mode = !self.state.zen_mode;
+            }
+            Message::TogglePreview => {
+                self.state.preview = !self.state.preview;
+                self.state.preview_scroll = 0;
+                self.state.selection_anchor = None;
+                self.state.status_message = if self.state.preview {
+                    "Preview (i to edit)".to_string()
+                } else {
+                    String::new()
+                };
+            }
+            Message::ScrollPreview(rows) => {
+                self.state.preview_scroll = self.state.preview_scroll.saturating_add_signed(rows);
+            }
+            Message::ToggleHelp => {
+                if let View::Help = self.state.current_view {
+                    if let Some(previous_view) = self.state.previous_view.take() {
+                        self.state.current_view = *previous_view;
+                    } else {
+                        // Fallback if there's no previous view
+                        self.state.current_view = View::NoteList;
                     }
+                } else {
+                    self.state.previous_view = Some(Box::new(self.state.current_view.clone()));
+                    self.state.current_view = View::Help;
                 }
-                Mode::Command => {
-                    self.state.command_input.push(c);
-                    self.state.status_message = self.state.command_input.clone();
+            }
+            Message::EnterTagInput => {
+                self.state.mode = Mode::TagInput;
+                self.state.command_input.clear();
+                self.state.status_message = "Add Tag: ".to_string();
+            }
+            Message::AddTag => {
+                let new_tag = self.canonical_tag(&self.state.command_input);
+                if !new_tag.is_empty()
+                    && let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                    && !note.tags.iter().any(|t| tag_key(t) == tag_key(&new_tag))
+                {
+                    note.tags.push(new_tag);
+                    note.dirty = true;
+                    self.state.dirty = true;
                 }
-                Mode::TitleInput => {
-                    let prefix = if self.state.note_list_state.selected().is_none() {
-                        "New note title: "
+                // Return to normal mode and clear status
+                self.update(Message::EnterNormalMode);
+            }
+            Message::ToggleFocus => {
+                self.focus = match self.focus {
+                    Focus::NoteList => Focus::TagList,
+                    Focus::TagList => Focus::NoteList,
+                };
+            }
+            Message::PreviousTag => {
+                if !self.state.tags.is_empty() {
+                    let i = self.state.tag_list_state.selected().unwrap_or(0);
+                    let new_i = if i == 0 {
+                        self.state.tags.len() - 1
                     } else {
-                        "Rename note to: "
+                        i - 1
                     };
-                    self.state.command_input.push(c);
-                    self.state.status_message = format!("{}{}", prefix, self.state.command_input);
-                }
-                Mode::TagInput => {
-                    self.state.command_input.push(c);
-                    self.state.status_message = format!("Add Tag: {}", self.state.command_input);
+                    self.state.tag_list_state.select(Some(new_i));
                 }
-                Mode::Normal => {
-                    if let View::Search = self.state.current_view {
-                        self.state.search_query.push(c);
-                        self.update_search_results();
-                        self.state.status_message = format!("/{}", self.state.search_query);
-                    }
+            }
+            Message::NextTag => {
+                if !self.state.tags.is_empty() {
+                    let i = self.state.tag_list_state.selected().unwrap_or(0);
+                    let new_i = if i >= self.state.tags.len() - 1 {
+                        0
+                    } else {
+                        i + 1
+                    };
+                    self.state.tag_list_state.select(Some(new_i));
                 }
-                Mode::ConfirmDeletion => {}
-                Mode::ConfirmQuit => {}
-                Mode::EditTask => {
-                    if let crate::app::state::TaskEditFocus::Description
-                    | crate::app::state::TaskEditFocus::DueDate = self.state.task_edit_focus
-                    {
-                        self.state.task_edit_buffer.push(c);
+            }
+            Message::SelectTag => {
+                let filter = if !self.state.active_tags.is_empty() {
+                    self.state.active_tags.clone()
+                } else if let Some(tag) = self
+                    .state
+                    .tag_list_state
+                    .selected()
+                    .and_then(|index| self.state.tags.get(index))
+                {
+                    // With nothing marked, Enter toggles the highlighted tag on its own
+                    if self.state.tag_filter == [tag.clone()] {
+                        Vec::new()
+                    } else {
+                        vec![tag.clone()]
                     }
-                }
-            },
-            Message::Backspace => match self.state.mode {
-                Mode::Insert => {
-                    if let Some(index) = self.state.note_list_state.selected() {
-                        if let Some(note) = self.state.notes.get_mut(index) {
-                            if self.state.cursor_offset > 0 {
-                                let offset =
-                                    self.state.cursor_offset.min(note.content.chars().count());
-                                let mut content: Vec<char> = note.content.chars().collect();
-                                content.remove(offset - 1);
-                                note.content = content.into_iter().collect();
-                                self.state.cursor_offset -= 1;
-                            }
-                        }
+                } else {
+                    return;
+                };
+                self.apply_tag_filter(filter);
+            }
+            Message::ToggleTagMark => {
+                if let Some(tag) = self
+                    .state
+                    .tag_list_state
+                    .selected()
+                    .and_then(|index| self.state.tags.get(index))
+                {
+                    if let Some(position) = self.state.active_tags.iter().position(|t| t == tag) {
+                        self.state.active_tags.remove(position);
+                    } else {
+                        self.state.active_tags.push(tag.clone());
                     }
                 }
-                Mode::Command => {
-                    self.state.command_input.pop();
-                    if self.state.command_input.is_empty() {
-                        self.update(Message::EnterNormalMode);
+            }
+            Message::ToggleNoteDetails => {
+                self.state.note_details = !self.state.note_details;
+            }
+            Message::ArchiveNote => {
+                let position = self.state.filtered_position();
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    note.archived = !note.archived;
+                    note.dirty = true;
+                    self.state.dirty = true;
+                    self.state.status_message = if note.archived {
+                        format!("Archived '{}'. Press . to show archived notes.", note.title)
                     } else {
-                        self.state.status_message = self.state.command_input.clone();
+                        format!("Unarchived '{}'.", note.title)
+                    };
+                    self.state.refresh_filter();
+                    // An archived note leaves the list, so select the note that took its place
+                    if self.state.filtered_position().is_none() {
+                        let count = self.state.filtered_indices.len();
+                        let next = position
+                            .filter(|_| count > 0)
+                            .map(|position| self.state.filtered_indices[position.min(count - 1)]);
+                        self.state.note_list_state.select(next);
                     }
                 }
-                Mode::TitleInput => {
-                    let prefix = if self.state.note_list_state.selected().is_none() {
-                        "New note title: "
+            }
+            Message::DuplicateNote => {
+                let Some(original) = self
+                    .state
+                    .note_list_state
+                    .selected()
+                    .and_then(|index| self.state.notes.get(index))
+                else {
+                    return;
+                };
+                let mut copy = self.data_handler.new_note(
+                    format!("{} (copy)", original.title),
+                    original.content.clone(),
+                    original.tags.clone(),
+                    &self.state.notes,
+                );
+                copy.crlf = original.crlf;
+                copy.view = original.view.clone();
+                copy.front_matter = original.front_matter.clone();
+                copy.front_matter_format = original.front_matter_format;
+
+                // Written right away so the copy exists even if the app closes without saving
+                self.own_writes.insert(copy.path.clone(), Instant::now());
+                let written = self.data_handler.save_notes(std::slice::from_ref(&copy));
+                self.state.status_message = match written {
+                    Ok(_) => {
+                        copy.dirty = false;
+                        format!("Created '{}'.", copy.title)
+                    }
+                    Err(e) => {
+                        self.state.dirty = true;
+                        format!("Error writing '{}': {}", copy.title, e)
+                    }
+                };
+                self.state.notes.push(copy);
+                self.state.invalidate_note_caches();
+                self.state
+                    .note_list_state
+                    .select(Some(self.state.notes.len() - 1));
+                self.update_tags();
+            }
+            Message::TogglePin => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    note.pinned = !note.pinned;
+                    note.dirty = true;
+                    self.state.dirty = true;
+                    self.state.status_message = if note.pinned {
+                        format!("Pinned '{}'.", note.title)
                     } else {
-                        "Rename note to: "
+                        format!("Unpinned '{}'.", note.title)
                     };
-                    self.state.command_input.pop();
-                    self.state.status_message = format!("{}{}", prefix, self.state.command_input);
+                    self.state.sort_notes();
+                    self.state.refresh_filter();
                 }
-                Mode::TagInput => {
-                    self.state.command_input.pop();
-                    self.state.status_message = format!("Add Tag: {}", self.state.command_input);
+            }
+            Message::ToggleShowArchived => {
+                self.state.show_archived = !self.state.show_archived;
+                self.state.refresh_filter();
+                self.update_search_results();
+                if self.state.filtered_position().is_none() {
+                    let first = self.state.filtered_indices.first().copied();
+                    self.state.note_list_state.select(first);
                 }
-                Mode::Normal => {
-                    if let View::Search = self.state.current_view {
-                        self.state.search_query.pop();
-                        self.update_search_results();
-                        self.state.status_message = format!("/{}", self.state.search_query);
-                    }
+                self.state.status_message = if self.state.show_archived {
+                    "Showing archived notes.".to_string()
+                } else {
+                    "Hiding archived notes.".to_string()
+                };
+            }
+            Message::ToggleTagMatch => {
+                self.state.tag_match = self.state.tag_match.toggle();
+                self.state.refresh_filter();
+                if self.state.filtered_position().is_none() {
+                    let first = self.state.filtered_indices.first().copied();
+                    self.state.note_list_state.select(first);
                 }
-                Mode::ConfirmDeletion => {}
-                Mode::ConfirmQuit => {}
-                Mode::EditTask => {
-                    if let crate::app::state::TaskEditFocus::Description
-                    | crate::app::state::TaskEditFocus::DueDate = self.state.task_edit_focus
-                    {
-                        self.state.task_edit_buffer.pop();
-                    }
+                self.state.status_message = format!(
+                    "Notes need {} of the filter tags",
+                    self.state.tag_match.label()
+                );
+            }
+            Message::RenameTag => {
+                if self.selected_tag().is_some() {
+                    self.state.mode = Mode::TagRenameInput;
+                    self.state.command_input.clear();
+                    self.state.status_message = self.tag_rename_prompt();
                 }
-            },
-            Message::EnterSearch => {
-                self.state.current_view = View::Search;
-                self.state.search_query.clear();
-                self.state.status_message = "/".to_string();
-                self.update_search_results();
             }
-            Message::ExitSearch => {
-                self.state.current_view = View::NoteList;
-                self.state.search_query.clear();
-                self.state.status_message = "".to_string();
-                self.state.search_results.clear();
+            Message::CommitTagRename => {
+                let new_tag = self.state.command_input.trim().to_string();
+                let Some(old_tag) = self.selected_tag() else {
+                    self.update(Message::EnterNormalMode);
+                    return;
+                };
+                if new_tag.is_empty() {
+                    self.state.command_input.clear();
+                    self.state.status_message =
+                        format!("Tag name cannot be empty. {}", self.tag_rename_prompt());
+                    return;
+                }
+                let changed = self.retag(&old_tag, Some(&new_tag));
+                self.update(Message::EnterNormalMode);
+                self.state.status_message = format!(
+                    "Renamed '{}' to '{}' in {} note(s).{}",
+                    old_tag,
+                    new_tag,
+                    changed,
+                    self.inline_tag_hint(&old_tag)
+                );
             }
-            Message::PreviousNote => {
-                if !self.state.notes.is_empty() {
-                    let i = self.state.note_list_state.selected().unwrap_or(0);
-                    let new_i = if i == 0 {
-                        self.state.notes.len() - 1
+            Message::DeleteTag => {
+                if let Some(tag) = self.selected_tag() {
+                    if self.config.should_confirm(ConfirmAction::DeleteTag) {
+                        let key = tag_key(&tag);
+                        let count = self
+                            .state
+                            .notes
+                            .iter()
+                            .filter(|note| note.tags.iter().any(|t| tag_key(t) == key))
+                            .count();
+                        let prompt = format!("Remove '{}' from {} note(s)?", tag, count);
+                        self.state.ask_confirmation(Mode::ConfirmDeletion, prompt);
                     } else {
-                        i - 1
+                        self.update(Message::ConfirmDelete);
+                    }
+                }
+            }
+            Message::ClearTagFilter => {
+                self.state.active_tags.clear();
+                self.apply_tag_filter(Vec::new());
+            }
+            Message::NewLine => {
+                if let Mode::Insert = self.state.mode
+                    && let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut content: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset.min(content.len());
+                    let (start, end) = line_bounds(&content, offset);
+                    let marker =
+                        list_marker(&content[start..end]).filter(|&(len, _)| offset >= start + len);
+                    match marker {
+                        // Enter on an empty item ends the list instead of adding another
+                        Some((len, _))
+                            if content[start + len..end].iter().all(|c| c.is_whitespace()) =>
+                        {
+                            content.drain(start..end);
+                            self.state.cursor_offset = start;
+                        }
+                        Some((_, next)) => {
+                            let inserted: Vec<char> =
+                                std::iter::once('\n').chain(next.chars()).collect();
+                            self.state.cursor_offset = offset + inserted.len();
+                            content.splice(offset..offset, inserted);
+                        }
+                        None => {
+                            content.insert(offset, '\n');
+                            self.state.cursor_offset = offset + 1;
+                        }
+                    }
+                    note.content = content.into_iter().collect();
+                    note.dirty = true;
+                    self.state.invalidate_note_caches();
+                }
+            }
+            Message::InsertTab => {
+                if let Mode::Insert = self.state.mode
+                    && let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let indent = match self.config.tab_width {
+                        0 => "\t".to_string(),
+                        width => " ".repeat(width),
                     };
-                    self.state.note_list_state.select(Some(new_i));
+                    let offset = self.state.cursor_offset.min(note.content.chars().count());
+                    let mut content: Vec<char> = note.content.chars().collect();
+                    content.splice(offset..offset, indent.chars());
+                    note.content = content.into_iter().collect();
+                    note.dirty = true;
+                    self.state.invalidate_note_caches();
+                    self.state.cursor_offset = offset + indent.len();
                 }
             }
-            Message::NextNote => {
-                if !self.state.notes.is_empty() {
-                    let i = self.state.note_list_state.selected().unwrap_or(0);
-                    let new_i = if i >= self.state.notes.len() - 1 {
-                        0
+            Message::DedentLine => {
+                if let Mode::Insert = self.state.mode
+                    && let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut content: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset.min(content.len());
+                    let (start, end) = line_bounds(&content, offset);
+                    // A leading tab, or up to one indent's worth of leading spaces
+                    let removed = if content.get(start) == Some(&'\t') {
+                        1
                     } else {
-                        i + 1
+                        let width = match self.config.tab_width {
+                            0 => TAB_STOP,
+                            width => width,
+                        };
+                        content[start..end]
+                            .iter()
+                            .take(width)
+                            .take_while(|&&c| c == ' ')
+                            .count()
                     };
-                    self.state.note_list_state.select(Some(new_i));
+                    if removed > 0 {
+                        content.drain(start..start + removed);
+                        note.content = content.into_iter().collect();
+                        note.dirty = true;
+                        self.state.invalidate_note_caches();
+                        self.state.cursor_offset = offset.saturating_sub(removed).max(start);
+                    }
                 }
             }
-            Message::OpenNote => {
-                if self.state.note_list_state.selected().is_some() {
-                    self.state.cursor_offset = 0;
-                    self.state.current_view = View::NoteEditor;
-                    self.state.status_message = "".to_string();
-                }
+            Message::CursorLeft => {
+                self.state.cursor_offset = self.state.cursor_offset.saturating_sub(1);
             }
-            Message::NewNote => {
-                self.state.note_list_state.select(None); // Deselect to indicate new note
-                self.state.mode = Mode::TitleInput;
-                self.state.command_input.clear();
-                self.state.status_message = "New note title: ".to_string();
+            Message::CursorLineStart
+            | Message::CursorLineEnd
+            | Message::CursorWordLeft
+            | Message::CursorWordRight => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let chars: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset;
+                    self.state.cursor_offset = match message {
+                        Message::CursorLineStart => line_bounds(&chars, offset).0,
+                        Message::CursorLineEnd => line_bounds(&chars, offset).1,
+                        Message::CursorWordLeft => previous_word_start(&chars, offset),
+                        _ => next_word_end(&chars, offset),
+                    };
+                }
             }
-            Message::NewTask => {
-                self.state.task_list_state.select(None);
-                self.state.mode = Mode::TitleInput;
-                self.state.command_input.clear();
-                self.state.status_message = "New Task: ".to_string();
+            Message::CursorRight => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                    && self.state.cursor_offset < note.content.chars().count()
+                {
+                    self.state.cursor_offset += 1;
+                }
             }
-            Message::RenameNote => {
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        self.state.mode = Mode::TitleInput;
-                        self.state.command_input = note.title.clone();
-                        self.state.status_message =
-                            format!("Rename note to: {}", self.state.command_input);
+            Message::CursorUp => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let offset = self.state.cursor_offset;
+                    let content_chars: Vec<char> = note.content.chars().collect();
+                    let line_starts: Vec<usize> = std::iter::once(0)
+                        .chain(
+                            content_chars
+                                .iter()
+                                .enumerate()
+                                .filter(|&(_, &c)| c == '\n')
+                                .map(|(i, _)| i + 1),
+                        )
+                        .collect();
+
+                    let current_line_index = line_starts
+                        .iter()
+                        .rposition(|&start| start <= offset)
+                        .unwrap_or(0);
+
+                    if current_line_index > 0 {
+                        let current_col = offset - line_starts[current_line_index];
+                        let prev_line_index = current_line_index - 1;
+                        let prev_line_start = line_starts[prev_line_index];
+                        let prev_line_end = line_starts[current_line_index] - 1;
+                        let prev_line_len = prev_line_end - prev_line_start;
+                        self.state.cursor_offset = prev_line_start + current_col.min(prev_line_len);
                     }
                 }
             }
-            Message::SetNoteTitle => {
-                let input = self.state.command_input.clone();
-                if input.is_empty() {
-                    self.state.status_message = "Input cannot be empty".to_string();
-                    self.state.mode = Mode::Normal;
-                    return;
-                }
+            Message::CursorDown => {
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let offset = self.state.cursor_offset;
+                    let content_chars: Vec<char> = note.content.chars().collect();
 
-                match self.state.current_view {
-                    View::NoteList | View::NoteEditor => {
-                        let new_title = input;
-                        if let Some(index) = self.state.note_list_state.selected() {
-                            // This is a rename of an existing note
-                            if let Some(note) = self.state.notes.get_mut(index) {
-                                note.title = new_title;
-                                self.state.dirty = true;
-                            }
-                        } else {
-                            // This is a new note
-                            let timestamp = Utc::now().timestamp();
-                            // A more robust path generation
-                            let safe_title: String = new_title
-                                .chars()
-                                .filter(|c| c.is_alphanumeric() || *c == ' ')
-                                .collect::<String>()
-                                .replace(' ', "_");
-                            let path = self
-                                .data_handler
-                                .notes_dir
-                                .join(format!("{}_{}.md", safe_title, timestamp));
-                            let new_note = Note {
-                                path,
-                                title: new_title,
-                                content: String::new(),
-                                tags: vec![],
-                                created_at: Utc::now(),
-                                updated_at: Utc::now(),
-                            };
+                    let line_starts: Vec<usize> = std::iter::once(0)
+                        .chain(
+                            content_chars
+                                .iter()
+                                .enumerate()
+                                .filter(|&(_, &c)| c == '\n')
+                                .map(|(i, _)| i + 1),
+                        )
+                        .collect();
 
-                            self.state.notes.push(new_note);
-                            let new_note_index = self.state.notes.len() - 1;
-                            self.state.note_list_state.select(Some(new_note_index));
-                            self.state.current_view = View::NoteEditor;
-                            self.state.mode = Mode::Insert;
-                            self.state.status_message = "-- INSERT --".to_string();
-                            return; // Skip returning to normal mode
-                        }
-                    }
-                    View::Tasks => {
-                        let description = input;
-                        // For tasks, we only handle creation for now.
-                        if self.state.task_list_state.selected().is_none() {
-                            let new_task = crate::app::state::Task {
-                                id: (self.state.tasks.len() + 1) as u64, // simplified ID
-                                description,
-                                project: None,
-                                priority: crate::app::state::Priority::Medium,
-                                due_date: None,
-                                completed: false,
-                                created_at: Utc::now(),
-                                sub_tasks: vec![],
-                            };
-                            self.state.tasks.push(new_task);
-                            let new_index = self.state.tasks.len() - 1;
-                            self.state.task_list_state.select(Some(new_index));
-                            self.save_tasks();
-                        }
+                    let current_line_index = line_starts
+                        .iter()
+                        .rposition(|&start| start <= offset)
+                        .unwrap_or(0);
+
+                    if current_line_index < line_starts.len() - 1 {
+                        let current_col = offset - line_starts[current_line_index];
+                        let next_line_index = current_line_index + 1;
+                        let next_line_start = line_starts[next_line_index];
+                        let next_line_end = if next_line_index + 1 < line_starts.len() {
+                            line_starts[next_line_index + 1] - 1
+                        } else {
+                            content_chars.len()
+                        };
+                        let next_line_len = next_line_end - next_line_start;
+                        self.state.cursor_offset = next_line_start + current_col.min(next_line_len);
                     }
-                    _ => {}
                 }
-                self.update(Message::EnterNormalMode);
             }
-            Message::DeleteNote => {
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        self.state.mode = Mode::ConfirmDeletion;
-                        self.state.status_message = format!("Delete '{}'? (y/n)", note.title);
-                    }
+            Message::PreviousTask => {
+                // Move through the tasks in the order they are listed
+                let order = self.state.task_display_order();
+                if !order.is_empty() {
+                    let selected = self.state.task_list_state.selected().unwrap_or(0);
+                    let i = order.iter().position(|&t| t == selected).unwrap_or(0);
+                    let new_i = if i == 0 { order.len() - 1 } else { i - 1 };
+                    self.state.task_list_state.select(Some(order[new_i]));
+                }
+            }
+            Message::NextTask => {
+                let order = self.state.task_display_order();
+                if !order.is_empty() {
+                    let selected = self.state.task_list_state.selected().unwrap_or(0);
+                    let i = order.iter().position(|&t| t == selected).unwrap_or(0);
+                    let new_i = if i >= order.len() - 1 { 0 } else { i + 1 };
+                    self.state.task_list_state.select(Some(order[new_i]));
+                }
+            }
+            Message::CycleTaskGrouping => {
+                self.state.task_grouping = self.state.task_grouping.next();
+                self.state.status_message = format!("Tasks {}", self.state.task_grouping.label());
+            }
+            Message::CycleTaskFilter => {
+                self.state.task_filter = self.state.task_filter.next();
+                self.state.keep_task_selection_listed();
+                self.state.status_message =
+                    format!("Showing {} tasks", self.state.task_filter.label());
+            }
+            Message::CycleProjectFilter => {
+                // None, then each project in turn, then back to None
+                let projects = self.state.task_projects();
+                let position = self
+                    .state
+                    .task_project_filter
+                    .as_ref()
+                    .and_then(|current| projects.iter().position(|p| p == current));
+                let next = match position {
+                    Some(i) => projects.get(i + 1),
+                    None => projects.first(),
+                };
+                self.state.task_project_filter = next.cloned();
+                self.state.keep_task_selection_listed();
+                self.state.status_message = match &self.state.task_project_filter {
+                    Some(project) => format!("Project: {}", project),
+                    None => "All projects".to_string(),
+                };
+            }
+            Message::ToggleTaskComplete => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get_mut(index)
+                {
+                    task.completed = !task.completed;
+                    self.save_tasks();
+                    self.state.keep_task_selection_listed();
+                }
+            }
+            Message::MoveTaskUp | Message::MoveTaskDown => {
+                let order = self.state.task_display_order();
+                let Some(selected) = self.state.task_list_state.selected() else {
+                    return;
+                };
+                let Some(position) = order.iter().position(|&i| i == selected) else {
+                    return;
+                };
+                // Swap with the neighbor as listed, so tasks hidden by the filters stay put
+                let neighbor = match message {
+                    Message::MoveTaskUp => position.checked_sub(1),
+                    _ => Some(position + 1),
+                }
+                .and_then(|p| order.get(p).copied());
+                let Some(neighbor) = neighbor else {
+                    return;
+                };
+                self.state.tasks.swap(selected, neighbor);
+                // Grouping sorts each task into its group, so one at the edge of a group
+                // can't move past it; the listed order of indices would change if it tried
+                if self.state.task_display_order() != order {
+                    self.state.tasks.swap(selected, neighbor);
+                    self.state.status_message =
+                        "Can't move a task out of its group; press g to change grouping"
+                            .to_string();
+                    return;
+                }
+                self.state.task_list_state.select(Some(neighbor));
+                self.save_tasks();
+            }
+            Message::TaskToNote => {
+                let Some(task) = self
+                    .state
+                    .task_list_state
+                    .selected()
+                    .and_then(|index| self.state.tasks.get(index))
+                else {
+                    return;
+                };
+                // The body is the task as a checklist, with its due date, project and
+                // priority, followed by its sub-tasks
+                let mut content = String::new();
+                push_checklist_item(&mut content, task, 0);
+                let note = self.data_handler.new_note(
+                    task.description.clone(),
+                    content,
+                    vec![],
+                    &self.state.notes,
+                );
+                let status = format!("Created note '{}' from the task", note.title);
+                self.state.notes.push(note);
+                self.state.invalidate_note_caches();
+                self.state.dirty = true;
+                self.state
+                    .note_list_state
+                    .select(Some(self.state.notes.len() - 1));
+                self.update(Message::OpenNote);
+                self.state.status_message = status;
+            }
+            Message::EditExternally => {
+                let Some(index) = self.state.note_list_state.selected() else {
+                    self.state.status_message = "No note selected.".to_string();
+                    return;
+                };
+                if self.data_handler.is_in_memory() {
+                    self.state.status_message =
+                        "Error: notes are only in memory, so there is no file to edit".to_string();
+                    return;
+                }
+                // The editor works on the file, so unsaved edits go there first
+                let note = &mut self.state.notes[index];
+                note.dirty |= !note.path.exists();
+                if let Err(e) = self.data_handler.save_notes(std::slice::from_ref(note)) {
+                    self.state.status_message = format!("Error saving '{}': {}", note.title, e);
+                    return;
                 }
+                note.dirty = false;
+                self.state.mode = Mode::Normal;
+                self.external_edit = Some(note.path.clone());
             }
-            Message::DeleteTask => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get(index) {
-                        self.state.mode = Mode::ConfirmDeletion;
-                        self.state.status_message = format!("Delete '{}'? (y/n)", task.description);
+            Message::LineToTask => {
+                let Some(note) = self
+                    .state
+                    .note_list_state
+                    .selected()
+                    .and_then(|index| self.state.notes.get(index))
+                else {
+                    return;
+                };
+                let chars: Vec<char> = note.content.chars().collect();
+                let (start, end) = line_bounds(&chars, self.state.cursor_offset);
+                let line: String = chars[start..end].iter().collect();
+                // A checklist item keeps its checkbox; any other line loses its list marker
+                let (description, completed) = match parse_checklist_item(&line) {
+                    Some(item) => (item.text, item.checked),
+                    None => {
+                        let marker = list_marker(&chars[start..end]).map_or(0, |(len, _)| len);
+                        (
+                            line.chars()
+                                .skip(marker)
+                                .collect::<String>()
+                                .trim()
+                                .to_string(),
+                            false,
+                        )
                     }
+                };
+                if description.is_empty() {
+                    self.state.status_message =
+                        "No text on this line to make a task of".to_string();
+                    return;
                 }
+                let task = crate::app::state::Task {
+                    id: self.state.take_task_id(),
+                    description,
+                    project: None,
+                    priority: crate::app::state::Priority::Medium,
+                    due_date: None,
+                    completed,
+                    created_at: Utc::now(),
+                    sub_tasks: vec![],
+                };
+                self.state.status_message = format!("Added task '{}'", task.description);
+                self.state.tasks.push(task);
+                self.save_tasks();
             }
-            Message::ConfirmDelete => {
-                match self.state.current_view {
-                    View::NoteList => {
-                        if let Some(index) = self.state.note_list_state.selected() {
-                            let note_to_delete = &self.state.notes[index].clone();
-                            if let Err(e) = self.data_handler.delete_note(note_to_delete) {
-                                self.state.status_message = format!("Error deleting note: {}", e);
-                            } else {
-                                self.state.notes.remove(index);
-                                self.state.dirty = true; // The list of notes has changed
-                                self.state.status_message =
-                                    format!("'{}' deleted.", note_to_delete.title);
-
-                                if self.state.notes.is_empty() {
-                                    self.state.note_list_state.select(None);
-                                } else if index >= self.state.notes.len() {
-                                    self.state
-                                        .note_list_state
-                                        .select(Some(self.state.notes.len() - 1));
-                                }
-                            }
-                        }
-                    }
-                    View::Tasks => {
-                        if let Some(index) = self.state.task_list_state.selected() {
-                            let removed_task = self.state.tasks.remove(index);
-                            self.state.status_message =
-                                format!("'{}' deleted.", removed_task.description);
-                            self.save_tasks();
-
-                            if self.state.tasks.is_empty() {
-                                self.state.task_list_state.select(None);
-                            } else if index >= self.state.tasks.len() {
-                                self.state
-                                    .task_list_state
-                                    .select(Some(self.state.tasks.len() - 1));
-                            }
-                        }
-                    }
-                    _ => {}
+            Message::CyclePriority => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get_mut(index)
+                {
+                    task.priority = task.priority.next();
+                    self.state.status_message = format!("Priority: {:?}", task.priority);
+                    self.save_tasks();
                 }
-                self.update(Message::EnterNormalMode);
             }
-            Message::ToggleHelp => {
-                if let View::Help = self.state.current_view {
-                    if let Some(previous_view) = self.state.previous_view.take() {
-                        self.state.current_view = *previous_view;
-                    } else {
-                        // Fallback if there's no previous view
-                        self.state.current_view = View::NoteList;
-                    }
+            Message::SetDueDate => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get(index)
+                {
+                    self.state.mode = Mode::DueDateInput;
+                    self.state.command_input = task
+                        .due_date
+                        .map(|date| date.format(DATE_FORMAT).to_string())
+                        .unwrap_or_default();
+                    self.state.status_message =
+                        format!("{}{}", DUE_DATE_PROMPT, self.state.command_input);
+                }
+            }
+            Message::CommitDueDate => {
+                let input = self.state.command_input.trim();
+                let due_date = if input.is_empty() {
+                    None
+                } else if let Ok(date) = NaiveDate::parse_from_str(input, DATE_FORMAT) {
+                    Some(date)
                 } else {
-                    self.state.previous_view = Some(Box::new(self.state.current_view.clone()));
-                    self.state.current_view = View::Help;
+                    // Stay in the prompt so the input can be corrected
+                    self.state.status_message = format!(
+                        "Invalid date '{}'. {}{}",
+                        input, DUE_DATE_PROMPT, self.state.command_input
+                    );
+                    return;
+                };
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get_mut(index)
+                {
+                    task.due_date = due_date;
+                    self.save_tasks();
                 }
+                self.update(Message::EnterNormalMode);
             }
-            Message::EnterTagInput => {
-                self.state.mode = Mode::TagInput;
-                self.state.command_input.clear();
-                self.state.status_message = "Add Tag: ".to_string();
+            Message::SetTaskProject => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get(index)
+                {
+                    self.state.mode = Mode::ProjectInput;
+                    self.state.command_input = task.project.clone().unwrap_or_default();
+                    self.state.status_message =
+                        format!("{}{}", PROJECT_PROMPT, self.state.command_input);
+                }
             }
-            Message::AddTag => {
-                let new_tag = self.state.command_input.trim().to_string();
-                if !new_tag.is_empty() {
-                    if let Some(index) = self.state.note_list_state.selected() {
-                        if let Some(note) = self.state.notes.get_mut(index) {
-                            if !note.tags.contains(&new_tag) {
-                                note.tags.push(new_tag);
-                                self.state.dirty = true;
-                            }
-                        }
-                    }
+            Message::CommitTaskProject => {
+                let input = self.state.command_input.trim();
+                let project = (!input.is_empty()).then(|| input.to_string());
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get_mut(index)
+                {
+                    task.project = project;
+                    self.save_tasks();
                 }
-                // Return to normal mode and clear status
                 self.update(Message::EnterNormalMode);
+                // The project filter may now hide the task
+                self.state.keep_task_selection_listed();
             }
-            Message::ToggleFocus => {
-                self.focus = match self.focus {
-                    Focus::NoteList => Focus::TagList,
-                    Focus::TagList => Focus::NoteList,
-                };
+            Message::OpenSettings => {
+                self.state.settings_draft = Some(self.config.clone());
+                self.state.settings_list_state.select(Some(0));
+                self.state.settings_buffer = None;
+                self.state.settings_error = None;
+                self.state.mode = Mode::EditSettings;
             }
-            Message::PreviousTag => {
-                if !self.state.tags.is_empty() {
-                    let i = self.state.tag_list_state.selected().unwrap_or(0);
-                    let new_i = if i == 0 {
-                        self.state.tags.len() - 1
-                    } else {
-                        i - 1
-                    };
-                    self.state.tag_list_state.select(Some(new_i));
-                }
+            Message::PreviousSetting => {
+                let i = self.state.settings_list_state.selected().unwrap_or(0);
+                let new_i = if i == 0 { SETTINGS.len() - 1 } else { i - 1 };
+                self.state.settings_list_state.select(Some(new_i));
             }
-            Message::NextTag => {
-                if !self.state.tags.is_empty() {
-                    let i = self.state.tag_list_state.selected().unwrap_or(0);
-                    let new_i = if i >= self.state.tags.len() - 1 {
-                        0
-                    } else {
-                        i + 1
-                    };
-                    self.state.tag_list_state.select(Some(new_i));
-                }
+            Message::NextSetting => {
+                let i = self.state.settings_list_state.selected().unwrap_or(0);
+                let new_i = if i >= SETTINGS.len() - 1 { 0 } else { i + 1 };
+                self.state.settings_list_state.select(Some(new_i));
             }
-            Message::SelectTag => {
-                if let Some(index) = self.state.tag_list_state.selected() {
-                    let tag = &self.state.tags[index];
-                    if self.state.active_tag.as_ref() == Some(tag) {
-                        self.state.active_tag = None; // Deselect if already active
-                    } else {
-                        self.state.active_tag = Some(tag.clone());
-                    }
-                    // Reset note list selection
-                    if !self.state.notes.is_empty() {
-                        self.state.note_list_state.select(Some(0));
-                    } else {
-                        self.state.note_list_state.select(None);
-                    }
+            Message::EditSetting => {
+                if let Some(draft) = &self.state.settings_draft
+                    && let Some(key) = self
+                        .state
+                        .settings_list_state
+                        .selected()
+                        .and_then(|i| SETTINGS.get(i))
+                {
+                    self.state.settings_buffer = Some(draft.get(key));
+                    self.state.settings_error = None;
                 }
             }
-            Message::NewLine => {
-                if let Mode::Insert = self.state.mode {
-                    if let Some(index) = self.state.note_list_state.selected() {
-                        if let Some(note) = self.state.notes.get_mut(index) {
-                            let offset = self.state.cursor_offset.min(note.content.chars().count());
-                            let mut content: Vec<char> = note.content.chars().collect();
-                            content.insert(offset, '\n');
-                            note.content = content.into_iter().collect();
-                            self.state.cursor_offset += 1;
+            Message::CommitSetting => {
+                if let Some(draft) = &mut self.state.settings_draft
+                    && let Some(key) = self
+                        .state
+                        .settings_list_state
+                        .selected()
+                        .and_then(|i| SETTINGS.get(i))
+                    && let Some(buffer) = &self.state.settings_buffer
+                {
+                    // Invalid values keep the field open with the reason shown in the form
+                    match draft.set(key, buffer) {
+                        Ok(()) => {
+                            self.state.settings_buffer = None;
+                            self.state.settings_error = None;
                         }
+                        Err(e) => self.state.settings_error = Some(format!("{}: {}", key, e)),
                     }
                 }
             }
-            Message::CursorLeft => {
-                self.state.cursor_offset = self.state.cursor_offset.saturating_sub(1);
+            Message::CancelSettingEdit => {
+                self.state.settings_buffer = None;
+                self.state.settings_error = None;
             }
-            Message::CursorRight => {
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        if self.state.cursor_offset < note.content.chars().count() {
-                            self.state.cursor_offset += 1;
+            Message::SaveSettings => {
+                if let Some(draft) = self.state.settings_draft.take() {
+                    match draft.save() {
+                        Ok(()) => {
+                            // Apply the settings that take effect immediately
+                            self.state.wrap = draft.wrap;
+                            self.state.line_numbers = draft.line_numbers;
+                            self.state.note_details = draft.note_details;
+                            self.state.first_weekday = draft.week_starts_on.weekday();
+                            self.theme = draft.theme();
+                            self.config = draft;
+                            self.state.status_message = "Settings saved.".to_string();
+                            self.state.mode = Mode::Normal;
                         }
-                    }
-                }
-            }
-            Message::CursorUp => {
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        let offset = self.state.cursor_offset;
-                        let content_chars: Vec<char> = note.content.chars().collect();
-                        let line_starts: Vec<usize> = std::iter::once(0)
-                            .chain(
-                                content_chars
-                                    .iter()
-                                    .enumerate()
-                                    .filter(|&(_, &c)| c == '\n')
-                                    .map(|(i, _)| i + 1),
-                            )
-                            .collect();
-
-                        let current_line_index = line_starts
-                            .iter()
-                            .rposition(|&start| start <= offset)
-                            .unwrap_or(0);
-
-                        if current_line_index > 0 {
-                            let current_col = offset - line_starts[current_line_index];
-                            let prev_line_index = current_line_index - 1;
-                            let prev_line_start = line_starts[prev_line_index];
-                            let prev_line_end = line_starts[current_line_index] - 1;
-                            let prev_line_len = prev_line_end - prev_line_start;
-                            self.state.cursor_offset =
-                                prev_line_start + current_col.min(prev_line_len);
+                        Err(e) => {
+                            self.state.settings_error =
+                                Some(format!("Error saving settings: {}", e));
+                            self.state.settings_draft = Some(draft);
                         }
                     }
                 }
             }
-            Message::CursorDown => {
-                if let Some(index) = self.state.note_list_state.selected() {
-                    if let Some(note) = self.state.notes.get(index) {
-                        let offset = self.state.cursor_offset;
-                        let content_chars: Vec<char> = note.content.chars().collect();
-
-                        let line_starts: Vec<usize> = std::iter::once(0)
-                            .chain(
-                                content_chars
-                                    .iter()
-                                    .enumerate()
-                                    .filter(|&(_, &c)| c == '\n')
-                                    .map(|(i, _)| i + 1),
-                            )
-                            .collect();
-
-                        let current_line_index = line_starts
-                            .iter()
-                            .rposition(|&start| start <= offset)
-                            .unwrap_or(0);
-
-                        if current_line_index < line_starts.len() - 1 {
-                            let current_col = offset - line_starts[current_line_index];
-                            let next_line_index = current_line_index + 1;
-                            let next_line_start = line_starts[next_line_index];
-                            let next_line_end = if next_line_index + 1 < line_starts.len() {
-                                line_starts[next_line_index + 1] - 1
-                            } else {
-                                content_chars.len()
-                            };
-                            let next_line_len = next_line_end - next_line_start;
-                            self.state.cursor_offset =
-                                next_line_start + current_col.min(next_line_len);
-                        }
-                    }
+            Message::CloseSettings => {
+                self.state.settings_draft = None;
+                self.state.settings_error = None;
+                self.state.mode = Mode::Normal;
+            }
+            Message::EnterEditTask => {
+                if let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get(index)
+                {
+                    self.state.mode = Mode::EditTask;
+                    self.state.task_edit_focus = crate::app::state::TaskEditFocus::Description;
+                    self.state.task_edit_buffer = task.description.clone();
+                    self.state.task_edit_draft = Some(task.clone());
                 }
             }
-            Message::PreviousTask => {
-                if !self.state.tasks.is_empty() {
-                    let i = self.state.task_list_state.selected().unwrap_or(0);
-                    let new_i = if i == 0 {
-                        self.state.tasks.len() - 1
+            Message::StartOperator(operator) => {
+                self.state.pending_operator = Some(operator);
+                self.state.status_message = operator.to_string();
+            }
+            Message::CancelOperator => {
+                self.state.pending_operator = None;
+                self.state.status_message.clear();
+            }
+            Message::DeleteLine => {
+                self.state.pending_operator = None;
+                self.state.status_message.clear();
+                self.push_undo_snapshot();
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut chars: Vec<char> = note.content.chars().collect();
+                    let (start, end) = line_bounds(&chars, self.state.cursor_offset);
+                    self.state.clipboard = chars[start..end].iter().collect();
+                    self.state.clipboard.push('\n');
+                    let range = if end < chars.len() {
+                        start..end + 1
                     } else {
-                        i - 1
+                        // The last line has no newline of its own; take the one before it
+                        start.saturating_sub(1)..end
                     };
-                    self.state.task_list_state.select(Some(new_i));
+                    chars.drain(range);
+                    note.content = chars.iter().collect();
+                    note.dirty = true;
+                    self.state.dirty = true;
+                    self.state.invalidate_note_caches();
+                    self.state.selection_anchor = None;
+                    self.state.cursor_offset = line_bounds(&chars, start.min(chars.len())).0;
                 }
+                self.drop_unchanged_snapshot();
             }
-            Message::NextTask => {
-                if !self.state.tasks.is_empty() {
-                    let i = self.state.task_list_state.selected().unwrap_or(0);
-                    let new_i = if i >= self.state.tasks.len() - 1 {
-                        0
-                    } else {
-                        i + 1
+            Message::DeleteWord => {
+                self.state.pending_operator = None;
+                self.state.status_message.clear();
+                self.push_undo_snapshot();
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut chars: Vec<char> = note.content.chars().collect();
+                    let start = self.state.cursor_offset.min(chars.len());
+                    let end = next_word_start(&chars, start);
+                    self.state.clipboard = chars.drain(start..end).collect();
+                    note.content = chars.into_iter().collect();
+                    note.dirty = true;
+                    self.state.dirty = true;
+                    self.state.invalidate_note_caches();
+                    self.state.selection_anchor = None;
+                    self.state.cursor_offset = start;
+                }
+                self.drop_unchanged_snapshot();
+            }
+            Message::Yank => {
+                self.state.pending_operator = None;
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get(index)
+                {
+                    let chars: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset.min(chars.len());
+                    let selection = self.state.selection(chars.len());
+                    self.state.selection_anchor = None;
+                    self.state.clipboard = match selection {
+                        Some(range) => {
+                            // Like Vim, leave the cursor at the start of what was yanked
+                            self.state.cursor_offset = range.start;
+                            chars[range].iter().collect()
+                        }
+                        None => {
+                            let (start, end) = line_bounds(&chars, offset);
+                            chars[start..end].iter().chain(['\n'].iter()).collect()
+                        }
                     };
-                    self.state.task_list_state.select(Some(new_i));
+                    self.state.status_message =
+                        format!("Yanked {} chars", self.state.clipboard.chars().count());
+                }
+                if let Mode::Visual = self.state.mode {
+                    self.state.mode = Mode::Normal;
                 }
             }
-            Message::ToggleTaskComplete => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get_mut(index) {
-                        task.completed = !task.completed;
-                        self.save_tasks();
+            Message::Cut => {
+                self.push_undo_snapshot();
+                let selection = self
+                    .state
+                    .note_list_state
+                    .selected()
+                    .and_then(|index| self.state.notes.get(index))
+                    .and_then(|note| self.state.selection(note.content.chars().count()));
+                self.state.selection_anchor = None;
+                if let Mode::Visual = self.state.mode {
+                    self.state.mode = Mode::Normal;
+                    self.state.status_message.clear();
+                }
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut chars: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset.min(chars.len());
+                    // Without a selection, cut the character under the cursor
+                    let range = selection.unwrap_or(offset..(offset + 1).min(chars.len()));
+                    if !range.is_empty() {
+                        self.state.cursor_offset = range.start;
+                        self.state.clipboard = chars.drain(range).collect();
+                        note.content = chars.into_iter().collect();
+                        note.dirty = true;
+                        self.state.dirty = true;
+                        self.state.invalidate_note_caches();
                     }
                 }
+                self.drop_unchanged_snapshot();
             }
-            Message::EnterEditTask => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get(index) {
-                        self.state.mode = Mode::EditTask;
-                        self.state.task_edit_focus = crate::app::state::TaskEditFocus::Description;
-                        self.state.task_edit_buffer = task.description.clone();
+            Message::Paste => {
+                if self.state.clipboard.is_empty() {
+                    self.state.status_message = "Nothing to paste".to_string();
+                    return;
+                }
+                self.push_undo_snapshot();
+                if let Some(index) = self.state.note_list_state.selected()
+                    && let Some(note) = self.state.notes.get_mut(index)
+                {
+                    let mut chars: Vec<char> = note.content.chars().collect();
+                    let offset = self.state.cursor_offset.min(chars.len());
+                    let mut text: Vec<char> = self.state.clipboard.chars().collect();
+                    // Whole lines go below the current line with the cursor on the first of
+                    // them; other text goes in at the cursor, which moves past it
+                    let (at, cursor) = if text.last() == Some(&'\n') {
+                        let (_, end) = line_bounds(&chars, offset);
+                        if end < chars.len() {
+                            (end + 1, end + 1)
+                        } else {
+                            // The last line has no newline to paste after, so borrow the
+                            // pasted text's own
+                            text.pop();
+                            text.insert(0, '\n');
+                            (end, end + 1)
+                        }
+                    } else {
+                        (offset, offset + text.len())
+                    };
+                    chars.splice(at..at, text);
+                    note.content = chars.into_iter().collect();
+                    note.dirty = true;
+                    self.state.dirty = true;
+                    self.state.invalidate_note_caches();
+                    self.state.selection_anchor = None;
+                    self.state.cursor_offset = cursor;
+                }
+                self.drop_unchanged_snapshot();
+            }
+            Message::FollowLink => {
+                let Some(target) = self
+                    .state
+                    .note_list_state
+                    .selected()
+                    .and_then(|index| self.state.notes.get(index))
+                    .and_then(|note| link_at(&note.content, self.state.cursor_offset))
+                else {
+                    return;
+                };
+                let key = target.to_lowercase();
+                let existing = self
+                    .state
+                    .notes
+                    .iter()
+                    .position(|note| note.title.to_lowercase() == key);
+                let index = match existing {
+                    Some(index) => index,
+                    None => {
+                        let note = self.data_handler.new_note(
+                            target.clone(),
+                            String::new(),
+                            vec![],
+                            &self.state.notes,
+                        );
+                        self.state.notes.push(note);
+                        self.state.invalidate_note_caches();
+                        self.state.dirty = true;
+                        self.state.refresh_filter();
+                        self.state.notes.len() - 1
                     }
+                };
+                self.state.note_list_state.select(Some(index));
+                self.update(Message::OpenNote);
+                if existing.is_none() {
+                    self.state.status_message = format!("Created '{}'", target);
+                }
+            }
+            Message::StartFind => {
+                self.state.mode = Mode::FindInNote;
+                self.state.find_query.clear();
+                self.state.find_origin = self.state.cursor_offset;
+                self.state.selection_anchor = None;
+                self.state.status_message = "Find: ".to_string();
+            }
+            Message::CommitFind => {
+                self.state.mode = Mode::Normal;
+                if self.state.find_query.is_empty() {
+                    self.state.status_message.clear();
+                }
+            }
+            Message::CancelFind => {
+                self.state.mode = Mode::Normal;
+                self.state.find_query.clear();
+                self.state.cursor_offset = self.state.find_origin;
+                self.state.status_message.clear();
+            }
+            Message::ClearFind => {
+                self.state.find_query.clear();
+                self.state.status_message.clear();
+            }
+            Message::FindNext => self.find_from(self.state.cursor_offset + 1, true),
+            Message::FindPrevious => self.find_from(self.state.cursor_offset, false),
+            Message::Undo => {
+                if !self.step_history(true) {
+                    self.state.status_message = "Already at oldest change".to_string();
+                }
+            }
+            Message::Redo => {
+                if !self.step_history(false) {
+                    self.state.status_message = "Already at newest change".to_string();
                 }
             }
             Message::ExitEditTask => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get_mut(index) {
-                        match self.state.task_edit_focus {
-                            crate::app::state::TaskEditFocus::Description => {
-                                task.description = self.state.task_edit_buffer.clone();
-                            }
-                            crate::app::state::TaskEditFocus::DueDate => {
-                                let buffer = self.state.task_edit_buffer.trim();
-                                if buffer.is_empty() {
-                                    task.due_date = None;
-                                } else if let Ok(date) =
-                                    NaiveDate::parse_from_str(buffer, "%d-%m-%Y")
-                                {
-                                    task.due_date = Some(date);
-                                } else {
-                                    self.state.status_message =
-                                        "Invalid date format (DD-MM-YYYY)".to_string();
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
+                self.state.mode = Mode::Normal;
+                self.state.task_edit_buffer.clear();
+                self.state.task_edit_draft = None;
+                self.state.status_message = "Task edit cancelled".to_string();
+            }
+            Message::CommitEditTask => {
+                if !self.apply_task_edit_buffer() {
+                    return;
+                }
+                if let Some(draft) = self.state.task_edit_draft.take()
+                    && let Some(index) = self.state.task_list_state.selected()
+                    && let Some(task) = self.state.tasks.get_mut(index)
+                {
+                    *task = draft;
+                    self.save_tasks();
                 }
                 self.state.mode = Mode::Normal;
                 self.state.task_edit_buffer.clear();
-                self.save_tasks();
             }
             Message::SwitchTaskEditFocus => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get_mut(index) {
-                        // Save the current field's buffer before switching
-                        match self.state.task_edit_focus {
-                            crate::app::state::TaskEditFocus::Description => {
-                                task.description = self.state.task_edit_buffer.clone();
-                            }
-                            crate::app::state::TaskEditFocus::DueDate => {
-                                let buffer = self.state.task_edit_buffer.trim();
-                                if buffer.is_empty() {
-                                    task.due_date = None;
-                                } else if let Ok(date) =
-                                    NaiveDate::parse_from_str(buffer, "%d-%m-%Y")
-                                {
-                                    task.due_date = Some(date);
-                                } else {
-                                    self.state.status_message =
-                                        "Invalid date format (DD-MM-YYYY)".to_string();
-                                }
-                            }
-                            _ => {}
+                if !self.apply_task_edit_buffer() {
+                    return;
+                }
+                if let Some(draft) = &self.state.task_edit_draft {
+                    self.state.task_edit_focus = match self.state.task_edit_focus {
+                        crate::app::state::TaskEditFocus::Description => {
+                            self.state.task_edit_buffer.clear();
+                            crate::app::state::TaskEditFocus::Priority
                         }
-
-                        // Switch focus and update buffer
-                        self.state.task_edit_focus = match self.state.task_edit_focus {
-                            crate::app::state::TaskEditFocus::Description => {
-                                self.state.task_edit_buffer.clear();
-                                crate::app::state::TaskEditFocus::Priority
-                            }
-                            crate::app::state::TaskEditFocus::Priority => {
-                                self.state.task_edit_buffer = task
-                                    .due_date
-                                    .map(|d| d.format("%d-%m-%Y").to_string())
-                                    .unwrap_or_default();
-                                crate::app::state::TaskEditFocus::DueDate
-                            }
-                            crate::app::state::TaskEditFocus::DueDate => {
-                                self.state.task_edit_buffer = task.description.clone();
-                                crate::app::state::TaskEditFocus::Description
-                            }
-                        };
-                    }
+                        crate::app::state::TaskEditFocus::Priority => {
+                            self.state.task_edit_buffer = draft
+                                .due_date
+                                .map(|d| d.format(DATE_FORMAT).to_string())
+                                .unwrap_or_default();
+                            crate::app::state::TaskEditFocus::DueDate
+                        }
+                        crate::app::state::TaskEditFocus::DueDate => {
+                            self.state.task_edit_buffer = draft.description.clone();
+                            crate::app::state::TaskEditFocus::Description
+                        }
+                    };
                 }
             }
             Message::CyclePriorityForward => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get_mut(index) {
-                        task.priority = match task.priority {
-                            crate::app::state::Priority::Low => crate::app::state::Priority::Medium,
-                            crate::app::state::Priority::Medium => {
-                                crate::app::state::Priority::High
-                            }
-                            crate::app::state::Priority::High => crate::app::state::Priority::Low,
-                        };
-                    }
+                if let Some(task) = &mut self.state.task_edit_draft {
+                    task.priority = task.priority.next();
                 }
             }
             Message::CyclePriorityBackward => {
-                if let Some(index) = self.state.task_list_state.selected() {
-                    if let Some(task) = self.state.tasks.get_mut(index) {
-                        task.priority = match task.priority {
-                            crate::app::state::Priority::Low => crate::app::state::Priority::High,
-                            crate::app::state::Priority::Medium => crate::app::state::Priority::Low,
-                            crate::app::state::Priority::High => {
-                                crate::app::state::Priority::Medium
-                            }
-                        };
-                    }
+                if let Some(task) = &mut self.state.task_edit_draft {
+                    task.priority = match task.priority {
+                        crate::app::state::Priority::Low => crate::app::state::Priority::High,
+                        crate::app::state::Priority::Medium => crate::app::state::Priority::Low,
+                        crate::app::state::Priority::High => crate::app::state::Priority::Medium,
+                    };
                 }
             }
         }
     }
 }
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 /// Sets up the terminal for TUI rendering.
 pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
@@ -1129,3 +4146,168 @@ pub fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -
     terminal.show_cursor()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An app over an empty vault that keeps everything in memory.
+    fn test_app() -> App {
+        let config = Config::default();
+        let (hook_sender, hook_receiver) = mpsc::channel();
+        App {
+            state: AppState::new(),
+            data_handler: DataHandler::in_memory(&config),
+            theme: config.theme(),
+            keybindings: Vec::new(),
+            config,
+            focus: Focus::NoteList,
+            hook_sender,
+            hook_receiver,
+            last_autosave: Instant::now(),
+            _note_watcher: None,
+            note_changes: None,
+            own_writes: HashMap::new(),
+            external_edit: None,
+        }
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.update(Message::Char(c));
+        }
+    }
+
+    #[test]
+    fn empty_note_title_stays_in_the_prompt() {
+        let mut app = test_app();
+        app.update(Message::NewNote);
+        type_text(&mut app, "   ");
+        app.update(Message::SetNoteTitle);
+
+        assert!(matches!(app.state.mode, Mode::TitleInput));
+        assert!(app.state.command_input.is_empty());
+        assert_eq!(
+            app.state.status_message,
+            "Note title cannot be empty. New note title: "
+        );
+        assert!(app.state.notes.is_empty());
+
+        type_text(&mut app, "Groceries");
+        app.update(Message::SetNoteTitle);
+        assert_eq!(app.state.notes.len(), 1);
+        assert_eq!(app.state.notes[0].title, "Groceries");
+    }
+
+    #[test]
+    fn empty_task_description_stays_in_the_prompt() {
+        let mut app = test_app();
+        app.update(Message::SwitchToTasks);
+        app.update(Message::NewTask);
+        app.update(Message::SetNoteTitle);
+
+        assert!(matches!(app.state.mode, Mode::TitleInput));
+        assert_eq!(
+            app.state.status_message,
+            "Task description cannot be empty. New Task: "
+        );
+        assert!(app.state.tasks.is_empty());
+
+        type_text(&mut app, "Water plants");
+        app.update(Message::SetNoteTitle);
+        assert_eq!(app.state.tasks.len(), 1);
+        assert_eq!(app.state.tasks[0].description, "Water plants");
+    }
+
+    #[test]
+    fn own_renames_do_not_look_like_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old_1700000000.md");
+        std::fs::write(&path, "---\ntitle: Old\n---\nBody\n").unwrap();
+        let mut app = test_app();
+        let mut note = app.data_handler.parse_note(&path).unwrap();
+        note.title = "New".to_string();
+        note.dirty = true;
+        app.state.notes.push(note);
+        let (sender, receiver) = mpsc::channel();
+        app.note_changes = Some(receiver);
+
+        app.state.status_message.clear();
+        app.own_writes.insert(path.clone(), Instant::now());
+        sender.send(path.clone()).unwrap();
+        app.reload_changed_notes();
+        assert!(app.state.status_message.is_empty());
+
+        // The same event from another program is still a conflict
+        app.own_writes.clear();
+        sender.send(path).unwrap();
+        app.reload_changed_notes();
+        assert_eq!(
+            app.state.status_message,
+            "Warning: 'New' changed on disk; saving will overwrite those changes"
+        );
+    }
+
+    #[test]
+    fn confirmation_prompt_survives_status_messages() {
+        let mut app = test_app();
+        app.update(Message::NewNote);
+        type_text(&mut app, "Groceries");
+        app.update(Message::SetNoteTitle);
+
+        app.update(Message::DeleteNote);
+        assert!(matches!(app.state.mode, Mode::ConfirmDeletion));
+        assert_eq!(app.state.status_message, "Delete 'Groceries'? (y/n)");
+
+        app.state.status_message = "Reloaded 'Other' (changed on disk)".to_string();
+        assert_eq!(app.state.confirm_prompt, "Delete 'Groceries'?");
+    }
+
+    #[test]
+    fn substitution_without_changes_keeps_the_redo_history() {
+        let mut app = test_app();
+        app.update(Message::NewNote);
+        type_text(&mut app, "Groceries");
+        app.update(Message::SetNoteTitle);
+        app.state.current_view = View::NoteEditor;
+        app.state.notes[0].content = "milk and eggs".to_string();
+        let redo = EditSnapshot {
+            path: app.state.notes[0].path.clone(),
+            content: "milk".to_string(),
+            cursor_offset: 0,
+        };
+        app.state.redo_stack.push(redo);
+
+        app.substitute("/bread/rolls/");
+        assert_eq!(app.state.status_message, "Pattern not found: bread");
+        app.substitute("/milk/milk/g");
+        assert_eq!(app.state.redo_stack.len(), 1);
+        assert!(app.state.undo_stack.is_empty());
+
+        app.substitute("/eggs/ham/");
+        assert_eq!(app.state.notes[0].content, "milk and ham");
+        assert!(app.state.redo_stack.is_empty());
+        assert_eq!(app.state.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn task_editor_rejects_a_blank_description() {
+        let mut app = test_app();
+        app.update(Message::SwitchToTasks);
+        app.update(Message::NewTask);
+        type_text(&mut app, "Water plants");
+        app.update(Message::SetNoteTitle);
+
+        app.update(Message::EnterEditTask);
+        app.state.task_edit_buffer = "  ".to_string();
+        app.update(Message::CommitEditTask);
+        assert!(matches!(app.state.mode, Mode::EditTask));
+        assert_eq!(app.state.status_message, "Task description cannot be empty");
+        assert_eq!(app.state.tasks[0].description, "Water plants");
+
+        app.state.task_edit_buffer = "Water the plants".to_string();
+        app.update(Message::CommitEditTask);
+        assert!(matches!(app.state.mode, Mode::Normal));
+        assert_eq!(app.state.tasks[0].description, "Water the plants");
+    }
+}
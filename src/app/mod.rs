@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod app;
 pub mod state;
 pub mod ui;
@@ -1,7 +1,18 @@
-use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
-use ratatui::widgets::ListState;
+use crate::utils::config::{Config, FrontMatterFormat};
+use crate::utils::dates::due_group;
+use crate::utils::links::backlink_counts;
+use crate::utils::search::{SearchMatch, SearchMode, fuzzy_match};
+use crate::utils::tags::{dedup_tags, parse_inline_tags, tag_key};
+use crate::utils::text::find_matches;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc, Weekday};
+use ratatui::{layout::Rect, widgets::ListState};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 /// Represents the priority of a task.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -11,6 +22,183 @@ pub enum Priority {
     High,
 }
 
+impl Priority {
+    /// Returns the next priority, wrapping from High back to Low.
+    pub fn next(&self) -> Self {
+        match self {
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Low,
+        }
+    }
+}
+
+/// Order of the note list, cycled with `o`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteSort {
+    #[default]
+    TitleAsc,
+    CreatedDesc,
+    UpdatedDesc,
+}
+
+impl NoteSort {
+    /// Returns the next order in the cycle.
+    pub fn next(&self) -> Self {
+        match self {
+            NoteSort::TitleAsc => NoteSort::CreatedDesc,
+            NoteSort::CreatedDesc => NoteSort::UpdatedDesc,
+            NoteSort::UpdatedDesc => NoteSort::TitleAsc,
+        }
+    }
+
+    /// Parses the order named in a `:sort` command.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "title" => Some(NoteSort::TitleAsc),
+            "created" | "newest" => Some(NoteSort::CreatedDesc),
+            "updated" => Some(NoteSort::UpdatedDesc),
+            _ => None,
+        }
+    }
+
+    /// Returns a short description for the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoteSort::TitleAsc => "title",
+            NoteSort::CreatedDesc => "newest",
+            NoteSort::UpdatedDesc => "recently updated",
+        }
+    }
+}
+
+/// How much of the calendar is shown, cycled with `s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CalendarScale {
+    #[default]
+    Month,
+    Week,
+    Day,
+}
+
+impl CalendarScale {
+    /// Returns the next scale in the cycle.
+    pub fn next(&self) -> Self {
+        match self {
+            CalendarScale::Month => CalendarScale::Week,
+            CalendarScale::Week => CalendarScale::Day,
+            CalendarScale::Day => CalendarScale::Month,
+        }
+    }
+
+    /// Returns a short description for the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CalendarScale::Month => "month",
+            CalendarScale::Week => "week",
+            CalendarScale::Day => "day",
+        }
+    }
+}
+
+/// How the tags in the filter combine, toggled with `m` in the Tags pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagMatch {
+    /// A note must carry every filter tag.
+    #[default]
+    All,
+    /// A note must carry at least one filter tag.
+    Any,
+}
+
+impl TagMatch {
+    /// Returns the other way of matching.
+    pub fn toggle(&self) -> Self {
+        match self {
+            TagMatch::All => TagMatch::Any,
+            TagMatch::Any => TagMatch::All,
+        }
+    }
+
+    /// Returns a short description for the status bar and the Tags pane title.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TagMatch::All => "all",
+            TagMatch::Any => "any",
+        }
+    }
+}
+
+/// Headers the Tasks view lists tasks under, cycled with `g`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskGrouping {
+    /// A plain list in stored order.
+    #[default]
+    None,
+    /// Agenda headers such as "Today" and "Later".
+    DueDate,
+    /// One header per project, with "No Project" last.
+    Project,
+}
+
+impl TaskGrouping {
+    /// Returns the next grouping in the cycle.
+    pub fn next(&self) -> Self {
+        match self {
+            TaskGrouping::None => TaskGrouping::DueDate,
+            TaskGrouping::DueDate => TaskGrouping::Project,
+            TaskGrouping::Project => TaskGrouping::None,
+        }
+    }
+
+    /// Returns a short description for the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskGrouping::None => "ungrouped",
+            TaskGrouping::DueDate => "by due date",
+            TaskGrouping::Project => "by project",
+        }
+    }
+}
+
+/// Which tasks the Tasks view lists by completion, cycled with `f`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskFilter {
+    #[default]
+    All,
+    Active,
+    Completed,
+}
+
+impl TaskFilter {
+    /// Returns the next filter in the cycle.
+    pub fn next(&self) -> Self {
+        match self {
+            TaskFilter::All => TaskFilter::Active,
+            TaskFilter::Active => TaskFilter::Completed,
+            TaskFilter::Completed => TaskFilter::All,
+        }
+    }
+
+    /// Returns a short description for the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskFilter::All => "all",
+            TaskFilter::Active => "active",
+            TaskFilter::Completed => "completed",
+        }
+    }
+
+    /// Returns whether a task passes the filter.
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            TaskFilter::All => true,
+            TaskFilter::Active => !task.completed,
+            TaskFilter::Completed => task.completed,
+        }
+    }
+}
+
 /// Represents a single to-do item.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -24,6 +212,20 @@ pub struct Task {
     pub sub_tasks: Vec<Task>,
 }
 
+/// Per-note view preferences, stored under the `ratanotes` front matter key.
+/// Unset fields fall back to the global `:set` defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteView {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<bool>,
+    /// Keys written by other tools or newer versions, kept so they survive a save.
+    #[serde(flatten)]
+    pub other: serde_yaml::Mapping,
+}
+
 /// Represents a single Markdown note.
 #[derive(Debug, Clone)]
 pub struct Note {
@@ -32,7 +234,80 @@ pub struct Note {
     pub content: String,
     pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
+    /// When the note's edits were last saved.
     pub updated_at: DateTime<Utc>,
+    /// Whether the note has edits that haven't been saved yet.
+    pub dirty: bool,
+    /// Whether the file wasn't valid UTF-8 and was decoded with replacement characters.
+    pub lossy: bool,
+    /// Whether the file uses Windows (`\r\n`) line endings, which are restored on save.
+    pub crlf: bool,
+    /// View preferences that override the global defaults for this note.
+    pub view: NoteView,
+    /// Front matter keys Ratanotes doesn't use, written back unchanged on save.
+    pub front_matter: serde_yaml::Mapping,
+    /// Syntax the front matter is written back in.
+    pub front_matter_format: FrontMatterFormat,
+    /// Hidden from the note list and search unless archived notes are shown.
+    pub archived: bool,
+    /// Listed above the other notes, whatever the sort order.
+    pub pinned: bool,
+}
+
+impl Note {
+    /// Returns the front-matter tags merged with any inline `#tags` from the body.
+    pub fn all_tags(&self) -> Vec<String> {
+        dedup_tags(
+            self.tags
+                .iter()
+                .cloned()
+                .chain(parse_inline_tags(&self.content)),
+        )
+    }
+
+    /// Returns whether the note carries a tag, either in front matter or inline.
+    /// Tags are compared ignoring case and surrounding whitespace.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let key = tag_key(tag);
+        self.tags.iter().any(|t| tag_key(t) == key)
+            || parse_inline_tags(&self.content)
+                .iter()
+                .any(|t| tag_key(t) == key)
+    }
+
+    /// Counts the words, characters and lines of the body and estimates its reading time.
+    /// A note with no words takes zero minutes.
+    pub fn stats(&self) -> NoteStats {
+        let words = self.content.split_whitespace().count();
+        NoteStats {
+            words,
+            chars: self.content.chars().count(),
+            lines: self.content.lines().count(),
+            reading_minutes: words.div_ceil(WORDS_PER_MINUTE),
+        }
+    }
+}
+
+/// Length figures for a note's body, shown by `:stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteStats {
+    pub words: usize,
+    pub chars: usize,
+    pub lines: usize,
+    /// Estimated minutes to read the note at [`WORDS_PER_MINUTE`], rounded up.
+    pub reading_minutes: usize,
+}
+
+/// Reading speed used for the reading time estimate.
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// A soft-deleted note sitting in the trash directory.
+#[derive(Debug, Clone)]
+pub struct TrashedNote {
+    pub trash_path: PathBuf,
+    pub original_path: PathBuf,
+    pub title: String,
+    pub deleted_at: DateTime<Utc>,
 }
 
 /// Represents the current active view of the application.
@@ -44,6 +319,26 @@ pub enum View {
     Tasks,
     Search,
     Help,
+    Trash,
+    Recent,
+    Agenda,
+}
+
+impl View {
+    /// Returns the name shown for the view in the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            View::NoteList => "Notes",
+            View::NoteEditor => "Editor",
+            View::Calendar => "Calendar",
+            View::Tasks => "Tasks",
+            View::Search => "Search",
+            View::Help => "Help",
+            View::Trash => "Trash",
+            View::Recent => "Recent",
+            View::Agenda => "Agenda",
+        }
+    }
 }
 
 /// Represents the current operational mode of the application.
@@ -54,8 +349,40 @@ pub enum Mode {
     TitleInput,
     ConfirmDeletion,
     TagInput,
+    TagRenameInput,
+    DueDateInput,
+    ProjectInput,
+    FindInNote,
     ConfirmQuit,
+    ConfirmImport,
     EditTask,
+    EditSettings,
+    BrowseOrphans,
+    BrowseBacklinks,
+    QuickSwitch,
+    Visual,
+}
+
+impl Mode {
+    /// Returns the name shown for the mode in the status bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Command => "COMMAND",
+            Mode::TitleInput
+            | Mode::TagInput
+            | Mode::TagRenameInput
+            | Mode::DueDateInput
+            | Mode::ProjectInput => "INPUT",
+            Mode::FindInNote => "FIND",
+            Mode::ConfirmDeletion | Mode::ConfirmQuit | Mode::ConfirmImport => "CONFIRM",
+            Mode::EditTask | Mode::EditSettings => "EDIT",
+            Mode::BrowseOrphans | Mode::BrowseBacklinks => "BROWSE",
+            Mode::QuickSwitch => "SWITCH",
+            Mode::Visual => "VISUAL",
+        }
+    }
 }
 
 /// Represents which field is being edited in a task.
@@ -65,10 +392,54 @@ pub enum TaskEditFocus {
     DueDate,
 }
 
+/// An item removed by the most recent delete, kept so the deletion can be undone.
+pub enum DeletedItem {
+    /// A note with its list index and the path it was moved to in the trash.
+    Note(usize, Note, PathBuf),
+    Task(usize, Task),
+    /// A tag with the front-matter tags of each note it was removed from, by note path.
+    Tag(String, Vec<(PathBuf, Vec<String>)>),
+}
+
+/// A note's content and cursor position, kept on the editor's undo and redo stacks.
+pub struct EditSnapshot {
+    /// The note the snapshot belongs to, so undo never touches a different note.
+    pub path: PathBuf,
+    pub content: String,
+    pub cursor_offset: usize,
+}
+
+/// The selected note, task and tag recorded by identity, so the selection survives
+/// reloads and resorts. Each entry keeps the old index as a fallback.
+pub struct SavedSelection {
+    note: Option<(PathBuf, usize)>,
+    task: Option<(u64, usize)>,
+    tag: Option<(String, usize)>,
+}
+
+/// Selects the item matching `find`, or the nearest valid index when it is gone.
+fn reselect<T>(
+    list_state: &mut ListState,
+    items: &[T],
+    saved: Option<usize>,
+    find: impl Fn(&T) -> bool,
+) {
+    let Some(old_index) = saved else {
+        return;
+    };
+    let index = items
+        .iter()
+        .position(find)
+        .or_else(|| (!items.is_empty()).then(|| old_index.min(items.len() - 1)));
+    list_state.select(index);
+}
+
 /// The main application state.
 pub struct AppState {
     pub notes: Vec<Note>,
     pub tasks: Vec<Task>,
+    /// Id for the next new task or sub-task. It only grows, so ids are never reused.
+    pub next_task_id: u64,
     pub current_view: View,
     pub previous_view: Option<Box<View>>,
     pub search_query: String,
@@ -77,86 +448,126 @@ pub struct AppState {
     pub dirty: bool,
     pub calendar_year: i32,
     pub calendar_month: u32,
+    /// The selected day within the displayed calendar month.
+    pub calendar_day: u32,
+    pub calendar_scale: CalendarScale,
     pub mode: Mode,
     pub command_input: String,
-    pub search_results: Vec<usize>,
+    /// Body of the template the note being titled starts from, chosen with `:new-from`.
+    pub note_template: Option<String>,
+    /// Indices of the matching notes with what each matched, best match first.
+    pub search_results: Vec<(usize, SearchMatch)>,
+    /// Whether the query is matched as a substring or fuzzily, toggled with `Ctrl+f`.
+    pub search_mode: SearchMode,
+    pub search_list_state: ListState,
+    /// Whether keys in the search view move through the results rather than edit the query.
+    pub search_results_focus: bool,
+    /// The selected note, as an index into `notes`.
     pub note_list_state: ListState,
+    /// Indices of the notes shown in the note list after the tag filter, in list order.
+    pub filtered_indices: Vec<usize>,
+    /// Selection and scroll of the note list as drawn, indexing `filtered_indices`.
+    pub filtered_list_state: ListState,
+    pub note_sort: NoteSort,
+    /// Rows per column when the note list is drawn as a grid, or 0 for a plain list.
+    pub note_grid_rows: usize,
+    /// Show a line of details under each title in the note list.
+    pub note_details: bool,
+    /// List and search archived notes too, toggled with `.`.
+    pub show_archived: bool,
     pub tags: Vec<String>,
     pub tag_list_state: ListState,
-    pub active_tag: Option<String>,
+    /// Tags marked in the Tags pane, applied as a filter on `Enter`.
+    pub active_tags: Vec<String>,
+    /// Tags a note must carry to be listed, combined per `tag_match`; empty shows every note.
+    pub tag_filter: Vec<String>,
+    pub tag_match: TagMatch,
     pub cursor_offset: usize,
     pub task_list_state: ListState,
+    pub task_grouping: TaskGrouping,
+    /// First day of the week, which ends the "This Week" task group.
+    pub first_weekday: Weekday,
+    pub task_filter: TaskFilter,
+    /// Only list tasks in this project, cycled with `P`.
+    pub task_project_filter: Option<String>,
     pub task_edit_focus: TaskEditFocus,
     pub task_edit_buffer: String,
+    /// A copy of the task being edited, written back only when the edit is committed.
+    pub task_edit_draft: Option<Task>,
+    /// A copy of the configuration being edited in the `:config` form.
+    pub settings_draft: Option<Config>,
+    pub settings_list_state: ListState,
+    /// The value being typed for the selected setting, while it is being edited.
+    pub settings_buffer: Option<String>,
+    /// Why the last setting edit was rejected, shown inside the form.
+    pub settings_error: Option<String>,
+    pub last_deleted: Option<DeletedItem>,
+    pub pending_import: Option<PathBuf>,
+    /// The question shown in the confirmation dialog, kept apart from the status message
+    /// so that messages arriving while the dialog is open don't replace it.
+    pub confirm_prompt: String,
+    pub trash: Vec<TrashedNote>,
+    pub trash_list_state: ListState,
+    /// Paths of recently opened notes, most recent first.
+    pub recent: Vec<PathBuf>,
+    pub recent_list_state: ListState,
+    /// Cached number of notes linking to each note, cleared by `invalidate_note_caches`.
+    pub backlinks: Option<Vec<usize>>,
+    /// Indices of notes without incoming links, listed by `:orphans`.
+    pub orphans: Vec<usize>,
+    pub orphan_list_state: ListState,
+    /// Indices of notes linking to the open note, listed by `B`.
+    pub backlink_sources: Vec<usize>,
+    pub backlink_list_state: ListState,
+    /// Text typed into the `Ctrl+e` quick-switcher to filter the recent notes.
+    pub switcher_query: String,
+    pub switcher_list_state: ListState,
+    /// Earlier editor states, most recent last, restored by `u`.
+    pub undo_stack: Vec<EditSnapshot>,
+    /// States undone by `u`, restored again by `Ctrl+r` until the next edit.
+    pub redo_stack: Vec<EditSnapshot>,
+    /// An operator key such as `d` waiting for its motion in the editor.
+    pub pending_operator: Option<char>,
+    /// Text yanked or deleted in the editor, put back by `p`. Ends with a newline when
+    /// it holds whole lines, which are then pasted below the cursor's line.
+    pub clipboard: String,
+    /// Text searched for in the open note; its matches are highlighted while it is set.
+    pub find_query: String,
+    /// Cursor position when the find prompt opened, restored if it is cancelled.
+    pub find_origin: usize,
+    /// The other end of the editor selection, with `cursor_offset` as the moving end.
+    pub selection_anchor: Option<usize>,
+    /// The screen area of the note editor's text, recorded at render time for mouse mapping.
+    pub editor_area: Rect,
+    /// First screen row of the note shown in the editor, kept so the cursor stays visible.
+    pub scroll_offset: u16,
+    /// First column shown in the editor when lines are not wrapped.
+    pub scroll_column: u16,
+    /// Distraction-free editing: no status bar or borders, text in a centered column.
+    pub zen_mode: bool,
+    /// Show the open note as rendered Markdown instead of editable text.
+    pub preview: bool,
+    /// Rows scrolled past in the Markdown preview.
+    pub preview_scroll: u16,
+    /// Rows of the agenda scrolled past.
+    pub agenda_scroll: u16,
+    /// Global `:set wrap` default, overridden by a note's own view preferences.
+    pub wrap: bool,
+    /// Global `:set number` default, overridden by a note's own view preferences.
+    pub line_numbers: bool,
+    /// Time, column, row, and click count of the last mouse click, to detect multi-clicks.
+    pub last_click: Option<(Instant, u16, u16, u8)>,
 }
 
 impl AppState {
     /// Creates a new instance of `AppState`.
     pub fn new() -> Self {
-        let sample_note = Note {
-            path: PathBuf::from("sample-note.md"),
-            title: "Sample Note".to_string(),
-            content: "This is the content of the sample note.".to_string(),
-            tags: vec!["sample".to_string(), "rust".to_string()],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
-
-        let sample_tasks = vec![
-            Task {
-                id: 1,
-                description: "Implement the task list view".to_string(),
-                project: Some("Ratanotes".to_string()),
-                priority: Priority::High,
-                due_date: None,
-                completed: false,
-                created_at: Utc::now(),
-                sub_tasks: vec![],
-            },
-            Task {
-                id: 2,
-                description: "Add sample data".to_string(),
-                project: Some("Ratanotes".to_string()),
-                priority: Priority::Medium,
-                due_date: None,
-                completed: true,
-                created_at: Utc::now(),
-                sub_tasks: vec![],
-            },
-        ];
-
         let now = Local::now();
 
-        let daily_note_filename = now.format("%d-%m-%Y.md").to_string();
-        let daily_note = Note {
-            path: PathBuf::from(daily_note_filename),
-            title: "Daily Note for today".to_string(),
-            content: "This is a sample daily note for today.".to_string(),
-            tags: vec!["daily".to_string()],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
-
-        let notes = vec![sample_note, daily_note];
-        let mut note_list_state = ListState::default();
-        if !notes.is_empty() {
-            note_list_state.select(Some(0));
-        }
-
-        let mut tags: Vec<String> = notes.iter().flat_map(|note| note.tags.clone()).collect();
-        tags.sort_unstable();
-        tags.dedup();
-
-        let tag_list_state = ListState::default();
-
-        let mut task_list_state = ListState::default();
-        if !sample_tasks.is_empty() {
-            task_list_state.select(Some(0));
-        }
-
         Self {
-            notes,
-            tasks: sample_tasks,
+            notes: Vec::new(),
+            tasks: Vec::new(),
+            next_task_id: 1,
             current_view: View::NoteList,
             previous_view: None,
             search_query: String::new(),
@@ -165,19 +576,388 @@ impl AppState {
             dirty: false,
             calendar_year: now.year(),
             calendar_month: now.month(),
+            calendar_day: now.day(),
+            calendar_scale: CalendarScale::default(),
             mode: Mode::Normal,
             command_input: String::new(),
+            note_template: None,
             search_results: Vec::new(),
-            note_list_state,
-            tags,
-            tag_list_state,
-            active_tag: None,
+            search_mode: SearchMode::default(),
+            search_list_state: ListState::default(),
+            search_results_focus: false,
+            note_list_state: ListState::default(),
+            filtered_indices: Vec::new(),
+            filtered_list_state: ListState::default(),
+            note_sort: NoteSort::default(),
+            note_grid_rows: 0,
+            note_details: true,
+            show_archived: false,
+            tags: Vec::new(),
+            tag_list_state: ListState::default(),
+            active_tags: Vec::new(),
+            tag_filter: Vec::new(),
+            tag_match: TagMatch::default(),
             cursor_offset: 0,
-            task_list_state,
+            task_list_state: ListState::default(),
+            task_grouping: TaskGrouping::default(),
+            first_weekday: Weekday::Mon,
+            task_filter: TaskFilter::default(),
+            task_project_filter: None,
             task_edit_focus: TaskEditFocus::Description,
             task_edit_buffer: String::new(),
+            task_edit_draft: None,
+            settings_draft: None,
+            settings_list_state: ListState::default(),
+            settings_buffer: None,
+            settings_error: None,
+            last_deleted: None,
+            pending_import: None,
+            confirm_prompt: String::new(),
+            trash: Vec::new(),
+            trash_list_state: ListState::default(),
+            recent: Vec::new(),
+            recent_list_state: ListState::default(),
+            backlinks: None,
+            orphans: Vec::new(),
+            orphan_list_state: ListState::default(),
+            backlink_sources: Vec::new(),
+            backlink_list_state: ListState::default(),
+            switcher_query: String::new(),
+            switcher_list_state: ListState::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            pending_operator: None,
+            clipboard: String::new(),
+            find_query: String::new(),
+            find_origin: 0,
+            selection_anchor: None,
+            editor_area: Rect::default(),
+            scroll_offset: 0,
+            scroll_column: 0,
+            zen_mode: false,
+            preview: false,
+            preview_scroll: 0,
+            agenda_scroll: 0,
+            wrap: false,
+            line_numbers: false,
+            last_click: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Returns the date selected in the calendar. A day past the end of the month,
+    /// left over from month navigation, is clamped to the month's last day.
+    pub fn calendar_date(&self) -> NaiveDate {
+        (1..=self.calendar_day)
+            .rev()
+            .find_map(|day| NaiveDate::from_ymd_opt(self.calendar_year, self.calendar_month, day))
+            .unwrap_or_default()
+    }
+
+    /// Selects a date in the calendar, showing its month.
+    pub fn set_calendar_date(&mut self, date: NaiveDate) {
+        self.calendar_year = date.year();
+        self.calendar_month = date.month();
+        self.calendar_day = date.day();
+    }
+
+    /// Returns whether `note` wraps long lines, honoring its own preference first.
+    pub fn wraps(&self, note: &Note) -> bool {
+        note.view.wrap.unwrap_or(self.wrap)
+    }
+
+    /// Returns whether `note` shows line numbers, honoring its own preference first.
+    pub fn shows_line_numbers(&self, note: &Note) -> bool {
+        note.view.line_numbers.unwrap_or(self.line_numbers)
+    }
+
+    /// Returns the wrap width for `note` in the editor's text area, if it wraps.
+    pub fn wrap_width(&self, note: &Note) -> Option<usize> {
+        self.wraps(note).then_some(self.editor_area.width as usize)
+    }
+
+    /// Returns the indices of the tasks passing the completion and project filters, in the
+    /// order they are listed: grouped by agenda or project when grouping, otherwise as stored.
+    pub fn task_display_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tasks.len())
+            .filter(|&i| {
+                let task = &self.tasks[i];
+                self.task_filter.matches(task)
+                    && self
+                        .task_project_filter
+                        .as_ref()
+                        .is_none_or(|project| task.project.as_ref() == Some(project))
+            })
+            .collect();
+        match self.task_grouping {
+            TaskGrouping::None => {}
+            TaskGrouping::DueDate => {
+                let today = Local::now().date_naive();
+                order.sort_by_key(|&i| {
+                    let due = self.tasks[i].due_date;
+                    (due_group(due, today, self.first_weekday), due)
+                });
+            }
+            TaskGrouping::Project => {
+                // Projects by name with "No Project" last; within a project the most
+                // urgent first, and undated tasks after dated ones
+                order.sort_by_key(|&i| {
+                    let task = &self.tasks[i];
+                    (
+                        task.project.is_none(),
+                        task.project.clone(),
+                        std::cmp::Reverse(task.priority.clone()),
+                        task.due_date.is_none(),
+                        task.due_date,
+                    )
+                });
+            }
+        }
+        order
+    }
+
+    /// Returns the distinct task projects, sorted.
+    pub fn task_projects(&self) -> Vec<String> {
+        let mut projects: Vec<String> = self
+            .tasks
+            .iter()
+            .filter_map(|task| task.project.clone())
+            .collect();
+        projects.sort();
+        projects.dedup();
+        projects
+    }
+
+    /// Moves the task selection off a task the filters hide, to the next listed task
+    /// or else the last one.
+    pub fn keep_task_selection_listed(&mut self) {
+        let order = self.task_display_order();
+        let Some(selected) = self.task_list_state.selected() else {
+            return;
+        };
+        if order.contains(&selected) {
+            return;
+        }
+        let next = order
+            .iter()
+            .copied()
+            .filter(|&i| i > selected)
+            .min()
+            .or(order.last().copied());
+        self.task_list_state.select(next);
+    }
+
+    /// Returns whether a note carries every tag in the applied filter, or with
+    /// `TagMatch::Any` at least one of them. An empty filter matches every note.
+    pub fn matches_tag_filter(&self, note: &Note) -> bool {
+        match self.tag_match {
+            TagMatch::All => self.tag_filter.iter().all(|tag| note.has_tag(tag)),
+            TagMatch::Any => {
+                self.tag_filter.is_empty() || self.tag_filter.iter().any(|tag| note.has_tag(tag))
+            }
+        }
+    }
+
+    /// Recomputes `filtered_indices` after the notes, their tags, or the filter change.
+    pub fn refresh_filter(&mut self) {
+        self.filtered_indices = (0..self.notes.len())
+            .filter(|&i| {
+                (self.show_archived || !self.notes[i].archived)
+                    && self.matches_tag_filter(&self.notes[i])
+            })
+            .collect();
+    }
+
+    /// Returns the position of the selected note within the filtered list, if it is listed.
+    pub fn filtered_position(&self) -> Option<usize> {
+        let selected = self.note_list_state.selected()?;
+        self.filtered_indices.iter().position(|&i| i == selected)
+    }
+
+    /// Returns the editor selection as a char range within a note of `len` chars. In
+    /// Visual mode it takes in the character under the cursor, as in Vim.
+    pub fn selection(&self, len: usize) -> Option<Range<usize>> {
+        let anchor = self.selection_anchor?;
+        let (start, end) = (
+            anchor.min(self.cursor_offset),
+            anchor.max(self.cursor_offset),
+        );
+        match self.mode {
+            Mode::Visual => Some(start.min(len)..(end + 1).min(len)),
+            _ => Some(start.min(len)..end.min(len)),
+        }
+    }
+
+    /// Returns the char offsets of `find_query` in the selected note.
+    pub fn find_matches(&self) -> Vec<usize> {
+        let Some(note) = self
+            .note_list_state
+            .selected()
+            .and_then(|index| self.notes.get(index))
+        else {
+            return Vec::new();
+        };
+        let chars: Vec<char> = note.content.chars().collect();
+        find_matches(&chars, &self.find_query)
+    }
+
+    /// Returns the number of notes linking to each note, computing it if the cache is stale.
+    pub fn backlink_counts(&mut self) -> &[usize] {
+        self.backlinks
+            .get_or_insert_with(|| backlink_counts(&self.notes))
+    }
+
+    /// Returns the indices of recently opened notes that are still loaded, most recent first.
+    pub fn recent_note_indices(&self) -> Vec<usize> {
+        self.recent
+            .iter()
+            .filter_map(|path| self.notes.iter().position(|note| &note.path == path))
+            .collect()
+    }
+
+    /// Returns the indices of the recent notes whose titles fuzzily match the quick-switcher
+    /// query, best match first. Equal matches and an empty query keep the most recent first.
+    pub fn switcher_matches(&self) -> Vec<usize> {
+        let recent = self.recent_note_indices();
+        if self.switcher_query.is_empty() {
+            return recent;
+        }
+        let mut scored: Vec<(i64, usize)> = recent
+            .into_iter()
+            .filter_map(|index| {
+                fuzzy_match(&self.switcher_query, &self.notes[index].title)
+                    .map(|(score, _)| (score, index))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        scored.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Sorts the notes by `note_sort` with pinned notes first, keeping the same note selected.
+    pub fn sort_notes(&mut self) {
+        let selection = self.save_selection();
+        match self.note_sort {
+            NoteSort::TitleAsc => self
+                .notes
+                .sort_by_cached_key(|note| note.title.to_lowercase()),
+            NoteSort::CreatedDesc => self
+                .notes
+                .sort_by_key(|note| std::cmp::Reverse(note.created_at)),
+            NoteSort::UpdatedDesc => self
+                .notes
+                .sort_by_key(|note| std::cmp::Reverse(note.updated_at)),
+        }
+        // Stable, so pinned and unpinned notes each keep the sort order
+        self.notes.sort_by_key(|note| !note.pinned);
+        self.invalidate_note_caches();
+        self.restore_selection(selection);
+    }
+
+    /// Drops everything kept by index into `notes`, which must be called whenever a note
+    /// is added, removed, reordered or has its links changed. The orphan, backlink and
+    /// search result lists are emptied rather than left pointing at other notes.
+    pub fn invalidate_note_caches(&mut self) {
+        self.backlinks = None;
+        self.orphans.clear();
+        self.orphan_list_state.select(None);
+        self.backlink_sources.clear();
+        self.backlink_list_state.select(None);
+        self.search_results.clear();
+        self.search_list_state.select(None);
+    }
+
+    /// Replaces the task list, giving a fresh id to any task or sub-task whose id is
+    /// already taken, and moves `next_task_id` past every id in use.
+    pub fn set_tasks(&mut self, mut tasks: Vec<Task>) {
+        fn max_id(tasks: &[Task]) -> u64 {
+            tasks
+                .iter()
+                .map(|task| task.id.max(max_id(&task.sub_tasks)))
+                .max()
+                .unwrap_or(0)
+        }
+        fn dedup_ids(tasks: &mut [Task], seen: &mut HashSet<u64>, next_id: &mut u64) {
+            for task in tasks {
+                if !seen.insert(task.id) {
+                    task.id = *next_id;
+                    seen.insert(task.id);
+                    *next_id += 1;
+                }
+                dedup_ids(&mut task.sub_tasks, seen, next_id);
+            }
+        }
+
+        self.next_task_id = self.next_task_id.max(max_id(&tasks) + 1);
+        dedup_ids(&mut tasks, &mut HashSet::new(), &mut self.next_task_id);
+        self.tasks = tasks;
+    }
+
+    /// Returns a new, unique task id.
+    pub fn take_task_id(&mut self) -> u64 {
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        id
+    }
+
+    /// Drops the undo and redo snapshots of the note at `path`, for when its content was
+    /// replaced from outside the editor and undoing would silently revert that change.
+    pub fn clear_history(&mut self, path: &Path) {
+        self.undo_stack.retain(|snapshot| snapshot.path != path);
+        self.redo_stack.retain(|snapshot| snapshot.path != path);
+    }
+
+    /// Opens a yes/no confirmation in `mode` asking `prompt`, which is also shown in the
+    /// status bar.
+    pub fn ask_confirmation(&mut self, mode: Mode, prompt: String) {
+        self.status_message = format!("{} (y/n)", prompt);
+        self.confirm_prompt = prompt;
+        self.mode = mode;
+    }
+
+    /// Records the selected note, task and tag by identity before the lists change.
+    pub fn save_selection(&self) -> SavedSelection {
+        SavedSelection {
+            note: self
+                .note_list_state
+                .selected()
+                .and_then(|i| self.notes.get(i).map(|note| (note.path.clone(), i))),
+            task: self
+                .task_list_state
+                .selected()
+                .and_then(|i| self.tasks.get(i).map(|task| (task.id, i))),
+            ..self.save_tag_selection()
         }
     }
+
+    /// Records only the selected tag, for when the tag list alone is about to change.
+    /// Restoring it leaves the note and task selections as they are.
+    pub fn save_tag_selection(&self) -> SavedSelection {
+        SavedSelection {
+            note: None,
+            task: None,
+            tag: self
+                .tag_list_state
+                .selected()
+                .and_then(|i| self.tags.get(i).map(|tag| (tag.clone(), i))),
+        }
+    }
+
+    /// Re-selects the items recorded by `save_selection` after the lists changed.
+    pub fn restore_selection(&mut self, saved: SavedSelection) {
+        let (note_path, note_index) = saved.note.unzip();
+        reselect(&mut self.note_list_state, &self.notes, note_index, |note| {
+            Some(&note.path) == note_path.as_ref()
+        });
+        let (task_id, task_index) = saved.task.unzip();
+        reselect(&mut self.task_list_state, &self.tasks, task_index, |task| {
+            Some(task.id) == task_id
+        });
+        let (tag_name, tag_index) = saved.tag.unzip();
+        reselect(&mut self.tag_list_state, &self.tags, tag_index, |tag| {
+            Some(tag) == tag_name.as_ref()
+        });
+    }
 }
 
 impl Default for AppState {
@@ -185,3 +965,76 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_with(content: &str) -> Note {
+        Note {
+            path: PathBuf::from("note.md"),
+            title: "Note".to_string(),
+            content: content.to_string(),
+            tags: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            dirty: false,
+            lossy: false,
+            crlf: false,
+            view: NoteView::default(),
+            front_matter: serde_yaml::Mapping::new(),
+            front_matter_format: FrontMatterFormat::default(),
+            archived: false,
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn stats_count_words_chars_and_lines() {
+        let stats = note_with("One two three\nfour\n").stats();
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.chars, 19);
+        assert_eq!(stats.lines, 2);
+        assert_eq!(stats.reading_minutes, 1);
+    }
+
+    #[test]
+    fn reading_time_rounds_up() {
+        let content = vec!["word"; WORDS_PER_MINUTE + 1].join(" ");
+        assert_eq!(note_with(&content).stats().reading_minutes, 2);
+    }
+
+    #[test]
+    fn empty_and_blank_notes_have_no_words() {
+        let empty = note_with("").stats();
+        assert_eq!((empty.words, empty.chars, empty.lines), (0, 0, 0));
+        assert_eq!(empty.reading_minutes, 0);
+
+        let blank = note_with("  \n\t\n").stats();
+        assert_eq!(blank.words, 0);
+        assert_eq!(blank.lines, 2);
+        assert_eq!(blank.reading_minutes, 0);
+    }
+
+    #[test]
+    fn sorting_drops_caches_indexed_by_note() {
+        let mut state = AppState::new();
+        for title in ["Zebra", "Apple"] {
+            let mut note = note_with("");
+            note.title = title.to_string();
+            state.notes.push(note);
+        }
+        state.backlinks = Some(vec![0, 1]);
+        state.orphans = vec![0];
+        state.orphan_list_state.select(Some(0));
+        state.backlink_sources = vec![1];
+        state.note_sort = NoteSort::TitleAsc;
+
+        state.sort_notes();
+        assert_eq!(state.notes[0].title, "Apple");
+        assert_eq!(state.backlinks, None);
+        assert!(state.orphans.is_empty());
+        assert_eq!(state.orphan_list_state.selected(), None);
+        assert!(state.backlink_sources.is_empty());
+    }
+}
@@ -1,21 +1,49 @@
 use crate::app::app::{App, Focus};
 use crate::components::{
-    calendar::CalendarWidget, help::HelpWidget, note_editor::NoteEditorWidget,
-    note_list::NoteListWidget, status_bar::StatusBarWidget, tag_list::TagListWidget,
-    task_editor::TaskEditorWidget, task_list::TaskListWidget,
+    agenda::AgendaWidget,
+    calendar::CalendarWidget,
+    confirm_dialog::ConfirmDialogWidget,
+    help::HelpWidget,
+    note_editor::{NoteEditorWidget, gutter_width},
+    note_list::{NoteListWidget, grid_columns, item_height},
+    note_picker::NotePickerWidget,
+    note_preview::NotePreviewWidget,
+    recent_list::RecentListWidget,
+    settings_editor::SettingsEditorWidget,
+    status_bar::StatusBarWidget,
+    tag_list::TagListWidget,
+    task_editor::TaskEditorWidget,
+    task_list::TaskListWidget,
+    trash_list::TrashListWidget,
 };
+use crate::utils::config::SETTINGS;
+use crate::utils::search::{MatchField, SearchMode};
+use crate::utils::text::{line_column, position_at, scroll_into_view};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
 
-use super::state::AppState;
+/// Width of the text column in zen mode.
+const ZEN_WIDTH: u16 = 80;
 
 /// Renders the user interface.
-pub fn ui(frame: &mut Frame, app: &mut App, cursor_position: Option<(u16, u16)>) {
+pub fn ui(frame: &mut Frame, app: &mut App) {
+    // Zen mode hides the status bar unless a prompt needs it
+    let zen = app.state.zen_mode
+        && matches!(app.state.current_view, super::state::View::NoteEditor)
+        && matches!(
+            app.state.mode,
+            crate::app::state::Mode::Normal
+                | crate::app::state::Mode::Insert
+                | crate::app::state::Mode::Visual
+        );
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(if zen { 0 } else { 1 }),
+        ])
         .split(frame.size());
 
     let content_area = main_layout[0];
@@ -29,46 +57,124 @@ pub fn ui(frame: &mut Frame, app: &mut App, cursor_position: Option<(u16, u16)>)
                 .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
                 .split(content_area);
 
-            // TODO: This clones the notes, which is inefficient. A better approach would be
-            // to store filtered indices in the app state.
-            let notes_to_display: Vec<crate::app::state::Note> =
-                if let Some(tag) = &app.state.active_tag {
-                    app.state
-                        .notes
-                        .iter()
-                        .filter(|note| note.tags.contains(tag))
-                        .cloned()
-                        .collect()
-                } else {
-                    app.state.notes.clone()
-                };
+            app.state.backlink_counts();
+            let position = app.state.filtered_position();
+            app.state.filtered_list_state.select(position);
+            app.state.note_grid_rows = if grid_columns(chunks[0], app.config.note_column_width) > 1
+            {
+                (chunks[0].height.saturating_sub(2) as usize / item_height(app.state.note_details))
+                    .max(1)
+            } else {
+                0
+            };
 
             let note_list = NoteListWidget {
-                notes: &notes_to_display,
+                notes: &app.state.notes,
+                indices: &app.state.filtered_indices,
                 has_focus: matches!(app.focus, Focus::NoteList),
+                backlinks: app.state.backlinks.as_deref().unwrap_or_default(),
+                total_count: app.state.notes.len(),
+                tag_filter: &app.state.tag_filter,
+                tag_match: app.state.tag_match,
+                unsaved_count: app.state.notes.iter().filter(|note| note.dirty).count(),
+                column_width: app.config.note_column_width,
+                sort_label: app.state.note_sort.label(),
+                details: app.state.note_details,
+                theme: app.theme,
             };
-            frame.render_stateful_widget(note_list, chunks[0], &mut app.state.note_list_state);
+            frame.render_stateful_widget(note_list, chunks[0], &mut app.state.filtered_list_state);
 
             let tag_list = TagListWidget {
                 tags: &app.state.tags,
                 has_focus: matches!(app.focus, Focus::TagList),
-                active_tag: &app.state.active_tag,
+                marked_tags: &app.state.active_tags,
+                tag_filter: &app.state.tag_filter,
+                tag_match: app.state.tag_match,
+                theme: app.theme,
             };
             frame.render_stateful_widget(tag_list, chunks[1], &mut app.state.tag_list_state);
         }
         super::state::View::NoteEditor => {
-            if let Some(selected_index) = app.state.note_list_state.selected() {
+            if app.state.preview
+                && let Some(note) = app
+                    .state
+                    .note_list_state
+                    .selected()
+                    .and_then(|index| app.state.notes.get(index))
+            {
+                let area = if app.state.zen_mode {
+                    zen_column(content_area)
+                } else {
+                    content_area
+                };
+                let preview = NotePreviewWidget {
+                    note,
+                    flavor: app.config.markdown_flavor,
+                    zen: app.state.zen_mode,
+                    scroll: app.state.preview_scroll,
+                    theme: app.theme,
+                };
+                frame.render_widget(preview, area);
+            } else if let Some(selected_index) = app.state.note_list_state.selected() {
                 if let Some(note) = app.state.notes.get(selected_index) {
+                    let selection = app.state.selection(note.content.chars().count());
+                    let query_len = app.state.find_query.chars().count();
+                    let matches: Vec<_> = app
+                        .state
+                        .find_matches()
+                        .into_iter()
+                        .map(|start| start..start + query_len)
+                        .collect();
+                    let line_numbers = app.state.shows_line_numbers(note);
+                    let wrap = app.state.wraps(note);
+                    let (editor_area, mut text_area) = if app.state.zen_mode {
+                        let area = zen_column(content_area);
+                        (area, area)
+                    } else {
+                        // The text area is inside the block's borders
+                        (content_area, content_area.inner(Margin::new(1, 1)))
+                    };
+                    if line_numbers {
+                        let gutter = gutter_width(&note.content).min(text_area.width);
+                        text_area.x += gutter;
+                        text_area.width -= gutter;
+                    }
+
+                    // Scroll just far enough to keep the cursor on screen
+                    let wrap_width = wrap.then_some(text_area.width as usize);
+                    let (column, row) =
+                        position_at(&note.content, app.state.cursor_offset, wrap_width);
+                    app.state.scroll_offset =
+                        scroll_into_view(app.state.scroll_offset, row as u16, text_area.height);
+                    app.state.scroll_column = if wrap {
+                        0
+                    } else {
+                        scroll_into_view(app.state.scroll_column, column as u16, text_area.width)
+                    };
+                    let scroll = (app.state.scroll_offset, app.state.scroll_column);
+
                     let note_editor = NoteEditorWidget {
                         note,
                         mode: &app.state.mode,
+                        selection,
+                        matches: &matches,
+                        zen: app.state.zen_mode,
+                        wrap,
+                        line_numbers,
+                        scroll,
+                        theme: app.theme,
                     };
-                    frame.render_widget(note_editor, content_area);
-                    if let Some((cursor_x, cursor_y)) = cursor_position {
-                        // Position the cursor. The text area is inside the block's borders.
+                    frame.render_widget(note_editor, editor_area);
+                    app.state.editor_area = text_area;
+                    // The cursor position is within the whole note; make it relative to the view.
+                    // It is measured against this frame's text area, so it follows resizes.
+                    if matches!(
+                        app.state.mode,
+                        crate::app::state::Mode::Insert | crate::app::state::Mode::Visual
+                    ) {
                         frame.set_cursor(
-                            content_area.x + 1 + cursor_x,
-                            content_area.y + 1 + cursor_y,
+                            text_area.x + (column as u16).saturating_sub(scroll.1),
+                            text_area.y + (row as u16).saturating_sub(scroll.0),
                         );
                     }
                 }
@@ -82,56 +188,332 @@ pub fn ui(frame: &mut Frame, app: &mut App, cursor_position: Option<(u16, u16)>)
             let calendar = CalendarWidget {
                 year: app.state.calendar_year,
                 month: app.state.calendar_month,
+                selected_day: app.state.calendar_day,
+                scale: app.state.calendar_scale,
+                first_weekday: app.config.week_starts_on.weekday(),
                 notes: &app.state.notes,
+                tasks: &app.state.tasks,
+                theme: app.theme,
             };
             frame.render_widget(calendar, content_area);
         }
         super::state::View::Tasks => {
             let task_list = TaskListWidget {
                 tasks: &app.state.tasks,
+                order: app.state.task_display_order(),
+                grouping: app.state.task_grouping,
+                strike_completed: app.config.strikethrough_completed,
+                first_weekday: app.state.first_weekday,
+                theme: app.theme,
             };
             frame.render_stateful_widget(task_list, content_area, &mut app.state.task_list_state);
         }
         super::state::View::Search => {
+            let mode = app.state.search_mode;
+            let match_style = Style::default()
+                .fg(app.theme.header)
+                .add_modifier(Modifier::BOLD);
+            let marker_style = Style::default().fg(app.theme.muted);
+
             let search_results: Vec<ListItem> = app
                 .state
                 .search_results
                 .iter()
-                .filter_map(|&index| app.state.notes.get(index))
-                .map(|note| ListItem::new(note.title.as_str()))
+                .filter_map(|(index, search_match)| {
+                    app.state.notes.get(*index).map(|note| (note, search_match))
+                })
+                .enumerate()
+                .map(|(number, (note, search_match))| {
+                    let matched = || {
+                        highlight_chars(&search_match.text, &search_match.positions, match_style)
+                    };
+                    // Number the results so the first nine can be opened with a digit
+                    let mut spans =
+                        vec![Span::styled(format!("{:>2}. ", number + 1), marker_style)];
+                    // Show what matched when it isn't the title
+                    match search_match.field {
+                        MatchField::Title => spans.extend(matched()),
+                        MatchField::Tag => {
+                            spans.push(Span::raw(note.title.as_str()));
+                            spans.push(Span::styled(" #", marker_style));
+                            spans.extend(matched());
+                        }
+                        MatchField::Body => spans.push(Span::raw(note.title.as_str())),
+                    }
+                    let mut lines = vec![Line::from(spans)];
+                    if search_match.field == MatchField::Body {
+                        let mut snippet = vec![Span::raw("    ")];
+                        snippet.extend(matched());
+                        lines.push(Line::from(snippet).style(marker_style));
+                    }
+                    ListItem::new(lines)
+                })
                 .collect();
 
-            let results_list = List::new(search_results).block(
-                Block::default()
-                    .title("Search Results")
-                    .borders(Borders::ALL),
-            );
+            let results_list = List::new(search_results)
+                .block(
+                    Block::default()
+                        .title(match mode {
+                            SearchMode::Substring => "Search Results",
+                            SearchMode::Fuzzy => "Search Results (fuzzy)",
+                        })
+                        .borders(Borders::ALL)
+                        .border_style(app.theme.border(app.state.search_results_focus)),
+                )
+                .highlight_style(app.theme.highlight());
 
-            frame.render_widget(results_list, content_area);
+            frame.render_stateful_widget(
+                results_list,
+                content_area,
+                &mut app.state.search_list_state,
+            );
+        }
+        super::state::View::Trash => {
+            let trash_list = TrashListWidget {
+                trash: &app.state.trash,
+                theme: app.theme,
+            };
+            frame.render_stateful_widget(trash_list, content_area, &mut app.state.trash_list_state);
+        }
+        super::state::View::Agenda => {
+            let agenda = AgendaWidget {
+                tasks: &app.state.tasks,
+                notes: &app.state.notes,
+                strike_completed: app.config.strikethrough_completed,
+                theme: app.theme,
+                scroll: app.state.agenda_scroll,
+            };
+            frame.render_widget(agenda, content_area);
+        }
+        super::state::View::Recent => {
+            let recent_list = RecentListWidget {
+                notes: app
+                    .state
+                    .recent_note_indices()
+                    .into_iter()
+                    .map(|index| &app.state.notes[index])
+                    .collect(),
+                theme: app.theme,
+            };
+            frame.render_stateful_widget(
+                recent_list,
+                content_area,
+                &mut app.state.recent_list_state,
+            );
         }
         super::state::View::Help => {
-            let help_widget = HelpWidget;
+            let help_widget = HelpWidget { theme: app.theme };
             frame.render_widget(help_widget, content_area);
         }
     };
 
     // Render the status bar
+    let title = app
+        .state
+        .note_list_state
+        .selected()
+        .and_then(|index| app.state.notes.get(index))
+        .map(|note| note.title.clone())
+        .unwrap_or_default();
+    let cursor_info = match app.state.current_view {
+        super::state::View::NoteEditor => app
+            .state
+            .note_list_state
+            .selected()
+            .and_then(|index| app.state.notes.get(index))
+            .map(|note| {
+                let chars: Vec<char> = note.content.chars().collect();
+                let (line, column) = line_column(&chars, app.state.cursor_offset);
+                let words = note.content.split_whitespace().count();
+                format!(
+                    "Ln {}, Col {}  {} words, {} chars ",
+                    line,
+                    column,
+                    words,
+                    chars.len()
+                )
+            })
+            .unwrap_or_default(),
+        super::state::View::Tasks => {
+            let project = app
+                .state
+                .task_project_filter
+                .as_ref()
+                .map(|project| format!(" in {}", project))
+                .unwrap_or_default();
+            format!(
+                "{} tasks{}: {}/{} ",
+                app.state.task_filter.label(),
+                project,
+                app.state.task_display_order().len(),
+                app.state.tasks.len()
+            )
+        }
+        _ => String::new(),
+    };
     let status_bar = StatusBarWidget {
-        message: &app.state.status_message,
+        mode: &app.state.mode,
+        view: &app.state.current_view,
+        note_count: app.state.notes.len(),
+        unsaved_count: app.state.notes.iter().filter(|note| note.dirty).count(),
+        task_count: app.state.tasks.len(),
+        format: &app.config.status_format,
+        fields: vec![
+            ("mode", app.state.mode.label().to_string()),
+            ("view", app.state.current_view.label().to_string()),
+            ("notes", app.state.notes.len().to_string()),
+            ("sort", app.state.note_sort.label().to_string()),
+            ("tasks", app.state.tasks.len().to_string()),
+            (
+                "unsaved",
+                app.state
+                    .notes
+                    .iter()
+                    .filter(|note| note.dirty)
+                    .count()
+                    .to_string(),
+            ),
+            ("title", title),
+            ("message", app.state.status_message.clone()),
+        ],
+        right: cursor_info,
+        theme: app.theme,
     };
     frame.render_widget(status_bar, status_bar_area);
 
     // Render popup widgets over the main UI
-    if let crate::app::state::Mode::EditTask = app.state.mode {
-        if let Some(task_index) = app.state.task_list_state.selected() {
-            if let Some(task) = app.state.tasks.get(task_index) {
-                let task_editor = TaskEditorWidget {
-                    task,
-                    edit_buffer: &app.state.task_edit_buffer,
-                    focus: &app.state.task_edit_focus,
-                };
-                frame.render_widget(task_editor, frame.size());
-            }
+    if let crate::app::state::Mode::EditTask = app.state.mode
+        && let Some(task) = &app.state.task_edit_draft
+    {
+        let task_editor = TaskEditorWidget {
+            task,
+            edit_buffer: &app.state.task_edit_buffer,
+            focus: &app.state.task_edit_focus,
+            theme: app.theme,
+        };
+        frame.render_widget(task_editor, frame.size());
+    }
+
+    // Confirmations get a popup so they can't be missed
+    let confirm = match app.state.mode {
+        crate::app::state::Mode::ConfirmDeletion => Some(("Delete", true)),
+        crate::app::state::Mode::ConfirmQuit => Some(("Quit", false)),
+        crate::app::state::Mode::ConfirmImport => Some(("Import", false)),
+        _ => None,
+    };
+    if let Some((title, destructive)) = confirm {
+        let dialog = ConfirmDialogWidget {
+            title,
+            prompt: &app.state.confirm_prompt,
+            destructive,
+            theme: app.theme,
+        };
+        frame.render_widget(dialog, frame.size());
+    }
+
+    if let crate::app::state::Mode::BrowseOrphans = app.state.mode {
+        let orphan_list = NotePickerWidget {
+            title: "Orphaned Notes",
+            notes: app
+                .state
+                .orphans
+                .iter()
+                .filter_map(|&index| app.state.notes.get(index))
+                .collect(),
+            theme: app.theme,
+        };
+        frame.render_stateful_widget(orphan_list, frame.size(), &mut app.state.orphan_list_state);
+    }
+
+    if let crate::app::state::Mode::BrowseBacklinks = app.state.mode {
+        let backlink_list = NotePickerWidget {
+            title: "Linked From",
+            notes: app
+                .state
+                .backlink_sources
+                .iter()
+                .filter_map(|&index| app.state.notes.get(index))
+                .collect(),
+            theme: app.theme,
+        };
+        frame.render_stateful_widget(
+            backlink_list,
+            frame.size(),
+            &mut app.state.backlink_list_state,
+        );
+    }
+
+    if let crate::app::state::Mode::QuickSwitch = app.state.mode {
+        let title = if app.state.switcher_query.is_empty() {
+            "Recent Notes (type to filter)".to_string()
+        } else {
+            format!("Recent Notes: {}", app.state.switcher_query)
+        };
+        let switcher = NotePickerWidget {
+            title: &title,
+            notes: app
+                .state
+                .switcher_matches()
+                .into_iter()
+                .filter_map(|index| app.state.notes.get(index))
+                .collect(),
+            theme: app.theme,
+        };
+        frame.render_stateful_widget(switcher, frame.size(), &mut app.state.switcher_list_state);
+    }
+
+    if let crate::app::state::Mode::EditSettings = app.state.mode
+        && let Some(draft) = &app.state.settings_draft
+    {
+        let settings_editor = SettingsEditorWidget {
+            entries: SETTINGS.iter().map(|key| (*key, draft.get(key))).collect(),
+            edit_buffer: app.state.settings_buffer.as_deref(),
+            error: app.state.settings_error.as_deref(),
+            theme: app.theme,
+        };
+        frame.render_stateful_widget(
+            settings_editor,
+            frame.size(),
+            &mut app.state.settings_list_state,
+        );
+    }
+}
+
+/// Centers a column of at most `ZEN_WIDTH` cells within `area`, with a margin above and below.
+fn zen_column(area: Rect) -> Rect {
+    let width = area.width.min(ZEN_WIDTH);
+    let margin = if area.height > 4 { 1 } else { 0 };
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + margin,
+        width,
+        height: area.height - 2 * margin,
+    }
+}
+
+/// Splits `text` into spans, styling the characters at the given char indices.
+fn highlight_chars<'a>(text: &'a str, positions: &[usize], style: Style) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut run_start = 0;
+    let mut run_matched = false;
+    for (index, (byte, _)) in text.char_indices().enumerate() {
+        let matched = positions.contains(&index);
+        if matched != run_matched && byte > run_start {
+            let run = &text[run_start..byte];
+            spans.push(if run_matched {
+                Span::styled(run, style)
+            } else {
+                Span::raw(run)
+            });
+            run_start = byte;
         }
+        run_matched = matched;
     }
+    let run = &text[run_start..];
+    spans.push(if run_matched {
+        Span::styled(run, style)
+    } else {
+        Span::raw(run)
+    });
+    spans
 }
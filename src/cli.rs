@@ -0,0 +1,129 @@
+// Ratanotes/src/cli.rs
+
+use std::path::PathBuf;
+
+/// Usage text printed when the command line can't be parsed.
+pub const USAGE: &str = "Usage: ratanotes [PATH | --note TITLE]
+       ratanotes --new TITLE [--content TEXT] [--tag TAG]...
+       ratanotes --stats [--json]";
+
+/// A note to open on startup, given on the command line.
+pub enum NoteTarget {
+    Path(PathBuf),
+    Title(String),
+}
+
+/// A note to create without starting the TUI.
+pub struct NewNote {
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+}
+
+/// Output format for `--stats`.
+pub enum StatsFormat {
+    Text,
+    Json,
+}
+
+/// The parsed command-line arguments.
+#[derive(Default)]
+pub struct CliArgs {
+    /// The note to open in the editor once the app starts.
+    pub open: Option<NoteTarget>,
+    /// A note to capture non-interactively, after which the program exits.
+    pub new_note: Option<NewNote>,
+    /// Print vault statistics in this format and exit.
+    pub stats: Option<StatsFormat>,
+}
+
+impl CliArgs {
+    /// Parses the command-line arguments, excluding the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli = Self::default();
+        let mut args = args.into_iter();
+        let mut new_title = None;
+        let mut content = None;
+        let mut tags = Vec::new();
+        let mut stats = false;
+        let mut json = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--note" => {
+                    let title = args
+                        .next()
+                        .ok_or_else(|| "--note requires a title".to_string())?;
+                    cli.set_open(NoteTarget::Title(title))?;
+                }
+                "--new" => {
+                    let title = args
+                        .next()
+                        .ok_or_else(|| "--new requires a title".to_string())?;
+                    new_title = Some(title);
+                }
+                "--content" => {
+                    let text = args
+                        .next()
+                        .ok_or_else(|| "--content requires some text".to_string())?;
+                    content = Some(text);
+                }
+                "--tag" => {
+                    let tag = args
+                        .next()
+                        .ok_or_else(|| "--tag requires a name".to_string())?;
+                    tags.push(tag);
+                }
+                "--stats" => stats = true,
+                "--json" => json = true,
+                flag if flag.starts_with("--") => {
+                    return Err(format!("Unknown option: {}", flag));
+                }
+                path => cli.set_open(NoteTarget::Path(PathBuf::from(path)))?,
+            }
+        }
+
+        match new_title {
+            Some(title) if title.trim().is_empty() => {
+                return Err("--new requires a non-empty title".to_string());
+            }
+            Some(title) => {
+                if cli.open.is_some() {
+                    return Err("--new can't be combined with opening a note".to_string());
+                }
+                cli.new_note = Some(NewNote {
+                    title,
+                    content: content.unwrap_or_default(),
+                    tags,
+                });
+            }
+            None if content.is_some() || !tags.is_empty() => {
+                return Err("--content and --tag require --new".to_string());
+            }
+            None => {}
+        }
+
+        if stats {
+            if cli.open.is_some() || cli.new_note.is_some() {
+                return Err("--stats can't be combined with other options".to_string());
+            }
+            cli.stats = Some(if json {
+                StatsFormat::Json
+            } else {
+                StatsFormat::Text
+            });
+        } else if json {
+            return Err("--json requires --stats".to_string());
+        }
+        Ok(cli)
+    }
+
+    /// Sets the note to open, rejecting a second one.
+    fn set_open(&mut self, target: NoteTarget) -> Result<(), String> {
+        if self.open.is_some() {
+            return Err("Only one note can be opened at a time".to_string());
+        }
+        self.open = Some(target);
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+// Ratanotes/src/components/agenda.rs
+
+use crate::app::state::{Note, Task};
+use crate::components::task_list::task_line;
+use crate::utils::dates::daily_note_date;
+use crate::utils::theme::Theme;
+use chrono::{Duration, Local};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+/// Number of days the agenda looks ahead, today included.
+const AGENDA_DAYS: i64 = 7;
+
+/// A read-only overview of the coming week: overdue tasks, then each day with its daily
+/// note and the tasks due on it, then the tasks without a due date.
+pub struct AgendaWidget<'a> {
+    pub tasks: &'a [Task],
+    pub notes: &'a [Note],
+    /// Strike through the descriptions of completed tasks.
+    pub strike_completed: bool,
+    pub theme: Theme,
+    /// Rows scrolled past.
+    pub scroll: u16,
+}
+
+impl<'a> Widget for AgendaWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let today = Local::now().date_naive();
+        let header_style = Style::default()
+            .fg(self.theme.header)
+            .add_modifier(Modifier::BOLD);
+        let muted = Style::default().fg(self.theme.muted);
+        let mut lines: Vec<Line> = Vec::new();
+        let section = |lines: &mut Vec<Line<'a>>, title: String, rows: Vec<Line<'a>>| {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::styled(title, header_style));
+            if rows.is_empty() {
+                lines.push(Line::styled("  Nothing planned", muted));
+            }
+            lines.extend(rows);
+        };
+        let task_row = |task: &'a Task| {
+            let mut spans = vec![Span::raw("  ")];
+            spans.extend(task_line(task, self.strike_completed, today, self.theme).spans);
+            Line::from(spans)
+        };
+
+        // Overdue work only needs attention while it is still open
+        let overdue: Vec<Line> = self
+            .tasks
+            .iter()
+            .filter(|task| !task.completed && task.due_date.is_some_and(|due| due < today))
+            .map(task_row)
+            .collect();
+        if !overdue.is_empty() {
+            section(&mut lines, "Overdue".to_string(), overdue);
+        }
+
+        for offset in 0..AGENDA_DAYS {
+            let date = today + Duration::days(offset);
+            let title = match offset {
+                0 => format!("Today, {}", date.format("%A %-d %B")),
+                1 => format!("Tomorrow, {}", date.format("%A %-d %B")),
+                _ => date.format("%A %-d %B").to_string(),
+            };
+            let mut rows: Vec<Line> = self
+                .notes
+                .iter()
+                .filter(|note| daily_note_date(&note.path) == Some(date))
+                .map(|note| Line::styled(format!("  Daily note: {}", note.title), muted))
+                .collect();
+            rows.extend(
+                self.tasks
+                    .iter()
+                    .filter(|task| task.due_date == Some(date))
+                    .map(task_row),
+            );
+            section(&mut lines, title, rows);
+        }
+
+        let undated: Vec<Line> = self
+            .tasks
+            .iter()
+            .filter(|task| !task.completed && task.due_date.is_none())
+            .map(task_row)
+            .collect();
+        section(&mut lines, "No date".to_string(), undated);
+
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Agenda (next 7 days)")
+                    .borders(Borders::ALL),
+            )
+            .scroll((self.scroll, 0))
+            .render(area, buf);
+    }
+}
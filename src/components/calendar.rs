@@ -1,21 +1,72 @@
 // Ratanotes/src/components/calendar.rs
 
-use crate::app::state::Note;
-use chrono::{Datelike, Local, NaiveDate};
+use crate::app::state::{CalendarScale, Note, Task};
+use crate::utils::dates::{daily_note_date, days_into_week};
+use crate::utils::theme::Theme;
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
 };
 use std::collections::HashSet;
 
 pub struct CalendarWidget<'a> {
     pub year: i32,
     pub month: u32,
+    /// The highlighted day, opened with `Enter`.
+    pub selected_day: u32,
+    pub scale: CalendarScale,
+    /// The day in the first column.
+    pub first_weekday: Weekday,
     pub notes: &'a [Note],
+    /// Tasks, listed under their due dates in the week and day views.
+    pub tasks: &'a [Task],
+    pub theme: Theme,
 }
 
 impl<'a> Widget for CalendarWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        match self.scale {
+            CalendarScale::Month => self.render_month(area, buf),
+            CalendarScale::Week => self.render_week(area, buf),
+            CalendarScale::Day => self.render_day(area, buf),
+        }
+    }
+}
+
+impl<'a> CalendarWidget<'a> {
+    /// Returns the selected date, clamped to the last day of the month.
+    fn selected_date(&self) -> NaiveDate {
+        let day = self.selected_day.min(days_in_month(self.year, self.month));
+        NaiveDate::from_ymd_opt(self.year, self.month, day).unwrap_or_default()
+    }
+
+    /// Returns the daily note for `date`, if there is one.
+    fn daily_note(&self, date: NaiveDate) -> Option<&'a Note> {
+        self.notes
+            .iter()
+            .find(|note| daily_note_date(&note.path) == Some(date))
+    }
+
+    /// Returns the tasks due on `date` as list lines.
+    fn due_lines(&self, date: NaiveDate) -> Vec<Line<'static>> {
+        self.tasks
+            .iter()
+            .filter(|task| task.due_date == Some(date))
+            .map(|task| {
+                let marker = if task.completed { "[x] " } else { "[ ] " };
+                let style = if task.completed {
+                    Style::default().fg(self.theme.muted)
+                } else {
+                    Style::default()
+                };
+                Line::styled(format!("{}{}", marker, task.description), style)
+            })
+            .collect()
+    }
+
+    /// Draws the month grid, coloring days that have a daily note.
+    fn render_month(&self, area: Rect, buf: &mut Buffer) {
         let block = Block::default()
             .title(format!("{} {}", month_name(self.month), self.year))
             .borders(Borders::ALL);
@@ -33,7 +84,8 @@ impl<'a> Widget for CalendarWidget<'a> {
         let days_area = layout[1];
 
         // Render weekday headers
-        let weekdays = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+        let mut weekdays = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+        weekdays.rotate_left(self.first_weekday.num_days_from_monday() as usize);
         let weekday_layout = Layout::horizontal(vec![Constraint::Ratio(1, 7); 7]);
         let weekday_cells = weekday_layout.split(weekday_headers_area);
         for (i, weekday) in weekdays.iter().enumerate() {
@@ -45,9 +97,11 @@ impl<'a> Widget for CalendarWidget<'a> {
         // Layout for the grid of days (6 weeks to cover all possibilities)
         let weeks_layout = Layout::vertical(vec![Constraint::Ratio(1, 6); 6]).split(days_area);
 
-        let first_day_of_month = NaiveDate::from_ymd_opt(self.year, self.month, 1).unwrap();
-        let weekday_of_first = first_day_of_month.weekday(); // Monday=1, Sunday=7
-        let start_offset = weekday_of_first.num_days_from_monday() as usize;
+        // An out-of-range month leaves the grid empty rather than panicking
+        let Some(first_day_of_month) = NaiveDate::from_ymd_opt(self.year, self.month, 1) else {
+            return;
+        };
+        let start_offset = days_into_week(first_day_of_month, self.first_weekday) as usize;
 
         let days_in_month = days_in_month(self.year, self.month);
         let today = Local::now().date_naive();
@@ -55,30 +109,21 @@ impl<'a> Widget for CalendarWidget<'a> {
         let days_with_notes: HashSet<u32> = self
             .notes
             .iter()
-            .filter_map(|note| {
-                if let Some(file_name) = note.path.file_stem() {
-                    if let Some(file_name_str) = file_name.to_str() {
-                        if let Ok(date) = NaiveDate::parse_from_str(file_name_str, "%d-%m-%Y") {
-                            if date.year() == self.year && date.month() == self.month {
-                                return Some(date.day());
-                            }
-                        }
-                    }
-                }
-                None
-            })
+            .filter_map(|note| daily_note_date(&note.path))
+            .filter(|date| date.year() == self.year && date.month() == self.month)
+            .map(|date| date.day())
             .collect();
 
         let mut day_counter = 1;
-        for (week_index, week_row) in weeks_layout.into_iter().enumerate() {
+        for (week_index, week_row) in weeks_layout.iter().enumerate() {
             let day_cells = weekday_layout.split(*week_row);
-            for (day_index, cell) in day_cells.into_iter().enumerate() {
+            for (day_index, cell) in day_cells.iter().enumerate() {
                 let current_grid_pos = week_index * 7 + day_index;
                 if current_grid_pos >= start_offset && day_counter <= days_in_month {
                     let mut style = Style::default();
 
                     if days_with_notes.contains(&day_counter) {
-                        style = style.fg(Color::Green);
+                        style = style.fg(self.theme.tag);
                     }
 
                     // Highlight today's date
@@ -86,7 +131,13 @@ impl<'a> Widget for CalendarWidget<'a> {
                         && self.month == today.month()
                         && day_counter == today.day()
                     {
-                        style = style.add_modifier(Modifier::BOLD).bg(Color::Blue);
+                        style = style
+                            .add_modifier(Modifier::BOLD)
+                            .bg(self.theme.highlight_bg);
+                    }
+
+                    if day_counter == self.selected_day.min(days_in_month) {
+                        style = style.add_modifier(Modifier::REVERSED);
                     }
 
                     Paragraph::new(day_counter.to_string())
@@ -98,18 +149,111 @@ impl<'a> Widget for CalendarWidget<'a> {
             }
         }
     }
+
+    /// Draws the week around the selected day as seven columns, each with a preview of
+    /// that day's daily note and the tasks due then.
+    fn render_week(&self, area: Rect, buf: &mut Buffer) {
+        let selected = self.selected_date();
+        let week_start =
+            selected - Duration::days(days_into_week(selected, self.first_weekday) as i64);
+        let block = Block::default()
+            .title(format!("Week of {}", week_start.format("%d %B %Y")))
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let today = Local::now().date_naive();
+        let columns = Layout::horizontal(vec![Constraint::Ratio(1, 7); 7]).split(inner_area);
+        for (offset, column) in columns.iter().enumerate() {
+            let date = week_start + Duration::days(offset as i64);
+            let mut title_style = Style::default();
+            if date == today {
+                title_style = title_style
+                    .add_modifier(Modifier::BOLD)
+                    .bg(self.theme.highlight_bg);
+            }
+            let day_block = Block::default()
+                .title(Span::styled(date.format("%a %d").to_string(), title_style))
+                .borders(Borders::ALL)
+                .border_style(self.theme.border(date == selected));
+
+            let mut lines: Vec<Line> = self
+                .daily_note(date)
+                .map(|note| preview_lines(note).map(Line::raw).collect())
+                .unwrap_or_default();
+            let due = self.due_lines(date);
+            if !due.is_empty() && !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.extend(due);
+            Paragraph::new(lines)
+                .block(day_block)
+                .wrap(Wrap { trim: true })
+                .render(*column, buf);
+        }
+    }
+
+    /// Draws the selected day's daily note beside the tasks due that day.
+    fn render_day(&self, area: Rect, buf: &mut Buffer) {
+        let selected = self.selected_date();
+        let [note_area, tasks_area] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(area);
+
+        let note_block = Block::default()
+            .title(selected.format("%A %d %B %Y").to_string())
+            .borders(Borders::ALL)
+            .border_style(self.theme.border(true));
+        let note_text = match self.daily_note(selected) {
+            Some(note) => Text::raw(note.content.as_str()),
+            None => Text::styled(
+                "No daily note yet. Press Enter to start one.",
+                Style::default().fg(self.theme.muted),
+            ),
+        };
+        Paragraph::new(note_text)
+            .block(note_block)
+            .wrap(Wrap { trim: false })
+            .render(note_area, buf);
+
+        let mut due = self.due_lines(selected);
+        if due.is_empty() {
+            due.push(Line::styled(
+                "Nothing due.",
+                Style::default().fg(self.theme.muted),
+            ));
+        }
+        Paragraph::new(due)
+            .block(Block::default().title("Due").borders(Borders::ALL))
+            .wrap(Wrap { trim: true })
+            .render(tasks_area, buf);
+    }
+}
+
+/// Returns the non-blank lines of a daily note, skipping the date heading it starts with.
+fn preview_lines(note: &Note) -> impl Iterator<Item = &str> {
+    let heading = format!("# {}", note.title);
+    note.content
+        .lines()
+        .map(str::trim)
+        .filter(move |line| !line.is_empty() && *line != heading)
 }
 
 /// Helper function to get the number of days in a given month and year.
+/// An invalid month has no days.
 fn days_in_month(year: i32, month: u32) -> u32 {
-    NaiveDate::from_ymd_opt(
-        if month == 12 { year + 1 } else { year },
-        if month == 12 { 1 } else { month + 1 },
-        1,
-    )
-    .unwrap()
-    .signed_duration_since(NaiveDate::from_ymd_opt(year, month, 1).unwrap())
-    .num_days() as u32
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return 0;
+    };
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    // December of the last year chrono supports has no next month to measure against
+    next.map_or(31, |next| {
+        next.signed_duration_since(first).num_days() as u32
+    })
 }
 
 /// Helper function to get the name of a month from its number.
@@ -130,3 +274,29 @@ fn month_name(month: u32) -> &'static str {
         _ => "Unknown",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn february_has_29_days_only_in_leap_years() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+    }
+
+    #[test]
+    fn december_rolls_over_into_january() {
+        assert_eq!(days_in_month(2023, 12), 31);
+        assert_eq!(days_in_month(2024, 1), 31);
+        assert_eq!(days_in_month(2024, 11), 30);
+    }
+
+    #[test]
+    fn invalid_month_has_no_days() {
+        assert_eq!(days_in_month(2024, 0), 0);
+        assert_eq!(days_in_month(2024, 13), 0);
+    }
+}
@@ -0,0 +1,57 @@
+// Ratanotes/src/components/confirm_dialog.rs
+
+use crate::components::task_editor::centered_rect;
+use crate::utils::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+/// A centered yes/no popup for destructive or irreversible actions.
+pub struct ConfirmDialogWidget<'a> {
+    pub title: &'a str,
+    /// The question to answer, e.g. "Delete 'Groceries'?".
+    pub prompt: &'a str,
+    /// Whether the action destroys data, which colors the border as an error.
+    pub destructive: bool,
+    pub theme: Theme,
+}
+
+impl<'a> Widget for ConfirmDialogWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_area = centered_rect(50, 25, area);
+
+        // Clear the area behind the popup before rendering
+        Clear.render(popup_area, buf);
+
+        let key_style = Style::default()
+            .fg(self.theme.key)
+            .add_modifier(Modifier::BOLD);
+        let text = vec![
+            Line::from(self.prompt),
+            Line::default(),
+            Line::from(vec![
+                Span::styled("[y]", key_style),
+                Span::raw(" Yes    "),
+                Span::styled("[n]", key_style),
+                Span::raw(" No"),
+            ]),
+        ];
+
+        let border_color = if self.destructive {
+            self.theme.error
+        } else {
+            self.theme.header
+        };
+        let paragraph = Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .title(format!(" {} ", self.title))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(border_color)),
+            );
+        paragraph.render(popup_area, buf);
+    }
+}
@@ -1,18 +1,21 @@
 // Ratanotes/src/components/help.rs
 
+use crate::utils::theme::Theme;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Cell, Row, Table},
 };
 
-pub struct HelpWidget;
+pub struct HelpWidget {
+    pub theme: Theme,
+}
 
 impl Widget for HelpWidget {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let key_style = Style::default().fg(Color::LightCyan);
-        let description_style = Style::default().fg(Color::White);
+        let key_style = Style::default().fg(self.theme.key);
+        let description_style = Style::default().fg(self.theme.text);
         let header_style = Style::default()
-            .fg(Color::Yellow)
+            .fg(self.theme.header)
             .add_modifier(Modifier::BOLD);
 
         let header_cells = ["Key(s)", "Action", "Mode(s) / View(s)"]
@@ -37,6 +40,16 @@ impl Widget for HelpWidget {
                 Cell::from("Enter Search Mode").style(description_style),
                 Cell::from("Normal (Global)").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("Ctrl+s").style(key_style),
+                Cell::from("Save all changes").style(description_style),
+                Cell::from("Normal (Notes, Editor)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Ctrl+e").style(key_style),
+                Cell::from("Jump to a recent note").style(description_style),
+                Cell::from("Normal (Global)").style(description_style),
+            ]),
             Row::new(vec![
                 Cell::from("?").style(key_style),
                 Cell::from("Show this help view").style(description_style),
@@ -48,8 +61,9 @@ impl Widget for HelpWidget {
                 Cell::from("All").style(description_style),
             ]),
             Row::new(vec![
-                Cell::from("n, c, T").style(key_style),
-                Cell::from("Switch to Notes, Calendar, Tasks views").style(description_style),
+                Cell::from("n, c, T, W").style(key_style),
+                Cell::from("Switch to Notes, Calendar, Tasks, Agenda views")
+                    .style(description_style),
                 Cell::from("Normal (Global)").style(description_style),
             ]),
             // Note List
@@ -80,26 +94,271 @@ impl Widget for HelpWidget {
             ]),
             Row::new(vec![
                 Cell::from("d").style(key_style),
-                Cell::from("Delete selected note").style(description_style),
+                Cell::from("Move selected note to trash").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("u").style(key_style),
+                Cell::from("Undo the last deletion").style(description_style),
+                Cell::from("Note List, Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("o").style(key_style),
+                Cell::from("Cycle sort: title, newest, updated").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("v").style(key_style),
+                Cell::from("Show / hide note details").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("P").style(key_style),
+                Cell::from("Pin / unpin note at the top").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("y").style(key_style),
+                Cell::from("Duplicate note").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("A").style(key_style),
+                Cell::from("Archive / unarchive note").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from(".").style(key_style),
+                Cell::from("Show / hide archived notes").style(description_style),
                 Cell::from("Note List").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("h / l").style(key_style),
+                Cell::from("Previous / next column in grid layout").style(description_style),
+                Cell::from("Note List").style(description_style),
+            ]),
+            // Tags
+            Row::new(vec![
+                Cell::from("Space").style(key_style),
+                Cell::from("Mark / unmark a tag for filtering").style(description_style),
+                Cell::from("Tags").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Enter").style(key_style),
+                Cell::from("Filter notes by the marked tags").style(description_style),
+                Cell::from("Tags").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("c").style(key_style),
+                Cell::from("Clear the tag filter").style(description_style),
+                Cell::from("Tags").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("m").style(key_style),
+                Cell::from("Match all / any of the filter tags").style(description_style),
+                Cell::from("Tags").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("r / d").style(key_style),
+                Cell::from("Rename / remove the tag in all notes").style(description_style),
+                Cell::from("Tags").style(description_style),
+            ]),
             // Note Editor
             Row::new(vec![
                 Cell::from("i").style(key_style),
                 Cell::from("Enter Insert Mode").style(description_style),
                 Cell::from("Note Editor (Normal)").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("Home / End").style(key_style),
+                Cell::from("Jump to line start / end").style(description_style),
+                Cell::from("Note Editor (Insert)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Ctrl+← / Ctrl+→").style(key_style),
+                Cell::from("Move by word").style(description_style),
+                Cell::from("Note Editor (Insert)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Tab / Shift+Tab").style(key_style),
+                Cell::from("Indent / dedent").style(description_style),
+                Cell::from("Note Editor (Insert)").style(description_style),
+            ]),
             Row::new(vec![
                 Cell::from("r").style(key_style),
                 Cell::from("Rename the current note").style(description_style),
                 Cell::from("Note Editor (Normal)").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("dd / dw").style(key_style),
+                Cell::from("Delete the line / to the next word").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("v").style(key_style),
+                Cell::from("Select text; d / y cuts / yanks it").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("yy / x / p").style(key_style),
+                Cell::from("Yank line or selection / cut / paste").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("u / Ctrl+r").style(key_style),
+                Cell::from("Undo / redo the last edit").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("z").style(key_style),
+                Cell::from("Toggle zen mode").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Ctrl+p").style(key_style),
+                Cell::from("Toggle Markdown preview (i to edit)").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Enter").style(key_style),
+                Cell::from("Follow or create the [[link]] at cursor").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("B").style(key_style),
+                Cell::from("List notes linking to this note").style(description_style),
+                Cell::from("Notes / Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("+").style(key_style),
+                Cell::from("Add the current line as a task").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("E").style(key_style),
+                Cell::from("Open the note in $EDITOR").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Ctrl+/ / n / N").style(key_style),
+                Cell::from("Find in note / next / previous match").style(description_style),
+                Cell::from("Note Editor (Normal)").style(description_style),
+            ]),
+            // Search
+            Row::new(vec![
+                Cell::from("Ctrl+f").style(key_style),
+                Cell::from("Toggle substring / fuzzy matching").style(description_style),
+                Cell::from("Search").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Ctrl+n / Ctrl+p").style(key_style),
+                Cell::from("Highlight a result while typing; Enter opens it")
+                    .style(description_style),
+                Cell::from("Search").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Enter / ↓").style(key_style),
+                Cell::from("Move from the query to the results").style(description_style),
+                Cell::from("Search").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("j / k, Enter").style(key_style),
+                Cell::from("Navigate and open results").style(description_style),
+                Cell::from("Search Results").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("1–9").style(key_style),
+                Cell::from("Open the numbered result").style(description_style),
+                Cell::from("Search Results").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Esc").style(key_style),
+                Cell::from("Back to the query, keeping it").style(description_style),
+                Cell::from("Search Results").style(description_style),
+            ]),
             // Calendar
             Row::new(vec![
                 Cell::from("← / →").style(key_style),
-                Cell::from("Navigate between months").style(description_style),
+                Cell::from("Previous / next month, week or day").style(description_style),
+                Cell::from("Calendar").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("h / l, k / j").style(key_style),
+                Cell::from("Select previous / next day or week").style(description_style),
+                Cell::from("Calendar").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("t").style(key_style),
+                Cell::from("Jump to today").style(description_style),
                 Cell::from("Calendar").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("s").style(key_style),
+                Cell::from("Cycle month / week / day view").style(description_style),
+                Cell::from("Calendar").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("Enter").style(key_style),
+                Cell::from("Open or create the day's daily note").style(description_style),
+                Cell::from("Calendar").style(description_style),
+            ]),
+            // Tasks
+            Row::new(vec![
+                Cell::from("c / Enter").style(key_style),
+                Cell::from("Edit the task description").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("p").style(key_style),
+                Cell::from("Cycle task priority").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("D").style(key_style),
+                Cell::from("Set due date (YYYY-MM-DD)").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("K / J").style(key_style),
+                Cell::from("Move the task up / down").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("N").style(key_style),
+                Cell::from("Create a note from the task").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("e").style(key_style),
+                Cell::from("Edit task fields (Enter saves, Esc discards)").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("g").style(key_style),
+                Cell::from("Group tasks by due date / project").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("m").style(key_style),
+                Cell::from("Set the task's project").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("f / P").style(key_style),
+                Cell::from("Filter by completion / project").style(description_style),
+                Cell::from("Tasks").style(description_style),
+            ]),
+            // Trash
+            Row::new(vec![
+                Cell::from("u / Enter").style(key_style),
+                Cell::from("Restore selected note").style(description_style),
+                Cell::from("Trash").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("d").style(key_style),
+                Cell::from("Permanently delete selected note").style(description_style),
+                Cell::from("Trash").style(description_style),
+            ]),
             // Command Mode
             Row::new(vec![
                 Cell::from("w, write").style(key_style),
@@ -116,6 +375,121 @@ impl Widget for HelpWidget {
                 Cell::from("Save all changes and quit").style(description_style),
                 Cell::from("Command").style(description_style),
             ]),
+            Row::new(vec![
+                Cell::from("new [title]").style(key_style),
+                Cell::from("Create a note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("new-from <name>").style(key_style),
+                Cell::from("Create a note from a template").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("d, delete").style(key_style),
+                Cell::from("Move the selected note to the trash").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("tag <name>").style(key_style),
+                Cell::from("Add a tag to the selected note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("goto <n>").style(key_style),
+                Cell::from("Select the nth listed note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("sort [order]").style(key_style),
+                Cell::from("Sort by title, created or updated").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("trash").style(key_style),
+                Cell::from("Browse deleted notes").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("restore").style(key_style),
+                Cell::from("Restore the most recently deleted note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("recent").style(key_style),
+                Cell::from("List recently opened notes").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("agenda").style(key_style),
+                Cell::from("Show the coming week's tasks and notes").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("edit").style(key_style),
+                Cell::from("Edit the note in $EDITOR").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("config").style(key_style),
+                Cell::from("Edit and save settings").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("backlinks").style(key_style),
+                Cell::from("List notes linking to this note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("stats").style(key_style),
+                Cell::from("Word count and reading time").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("orphans").style(key_style),
+                Cell::from("List notes without incoming links").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("tag normalize").style(key_style),
+                Cell::from("Merge tags differing only by case").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("set [no]wrap").style(key_style),
+                Cell::from("Toggle line wrapping").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("set [no]number").style(key_style),
+                Cell::from("Toggle line numbers").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("set [no]details").style(key_style),
+                Cell::from("Toggle note list details").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("setlocal <option>").style(key_style),
+                Cell::from("Save a set option in the note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("html").style(key_style),
+                Cell::from("Export selected note to HTML").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("export <dir>").style(key_style),
+                Cell::from("Back up all notes and tasks to a folder").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
+            Row::new(vec![
+                Cell::from("s/old/new/[g]").style(key_style),
+                Cell::from("Replace next (or every) match in note").style(description_style),
+                Cell::from("Command").style(description_style),
+            ]),
         ];
 
         let table = Table::new(
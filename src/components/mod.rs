@@ -1,8 +1,16 @@
+pub mod agenda;
 pub mod calendar;
+pub mod confirm_dialog;
 pub mod help;
 pub mod note_editor;
 pub mod note_list;
+pub mod note_picker;
+pub mod note_preview;
+pub mod recent_list;
+pub mod scrollbar;
+pub mod settings_editor;
 pub mod status_bar;
 pub mod tag_list;
 pub mod task_editor;
 pub mod task_list;
+pub mod trash_list;
@@ -1,18 +1,42 @@
 // Ratanotes/src/components/note_editor.rs
 
 use crate::app::state::{Mode, Note};
+use crate::utils::links::wiki_link_ranges;
+use crate::utils::tags::inline_tag_ranges;
+use crate::utils::text::{row_ranges, width_at};
+use crate::utils::theme::Theme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph};
+use std::ops::Range;
 
 pub struct NoteEditorWidget<'a> {
     pub note: &'a Note,
     pub mode: &'a Mode,
+    /// The selected char range within the note content, if any.
+    pub selection: Option<Range<usize>>,
+    /// Char ranges matching the find query.
+    pub matches: &'a [Range<usize>],
+    /// Render only the text, without the surrounding block.
+    pub zen: bool,
+    /// Wrap long lines at the width of the text area.
+    pub wrap: bool,
+    /// Show a line number gutter left of the text.
+    pub line_numbers: bool,
+    /// Rows and columns scrolled past, as `(row, column)`.
+    pub scroll: (u16, u16),
+    pub theme: Theme,
+}
+
+/// Returns the width of the line number gutter for `content`, including a trailing space.
+pub fn gutter_width(content: &str) -> u16 {
+    let lines = content.split('\n').count();
+    lines.to_string().len() as u16 + 1
 }
 
 impl<'a> Widget for NoteEditorWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let border_style = if let Mode::Insert = self.mode {
-            Style::default().fg(Color::Blue)
+            Style::default().fg(self.theme.insert)
         } else {
             Style::default()
         };
@@ -27,10 +51,14 @@ impl<'a> Widget for NoteEditorWidget<'a> {
 
         let title = Line::from(vec![
             Span::raw(self.note.title.as_str()),
+            Span::styled(
+                if self.note.dirty { " [+]" } else { "" },
+                Style::default().fg(self.theme.header),
+            ),
             Span::styled(
                 tags_text,
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(self.theme.tag)
                     .add_modifier(Modifier::ITALIC),
             ),
         ]);
@@ -39,8 +67,131 @@ impl<'a> Widget for NoteEditorWidget<'a> {
             .title(title)
             .borders(Borders::ALL)
             .border_style(border_style);
-        Paragraph::new(self.note.content.as_str())
-            .block(block)
-            .render(area, buf);
+
+        let inner = if self.zen {
+            area
+        } else {
+            let inner = block.inner(area);
+            block.render(area, buf);
+            inner
+        };
+        let gutter = if self.line_numbers {
+            gutter_width(&self.note.content).min(inner.width)
+        } else {
+            0
+        };
+        let [gutter_area, text_area] =
+            Layout::horizontal([Constraint::Length(gutter), Constraint::Min(0)]).areas(inner);
+        let wrap_width = self.wrap.then_some(text_area.width as usize);
+
+        let tag_style = Style::default().fg(self.theme.tag);
+        let link_style = Style::default()
+            .fg(self.theme.link)
+            .add_modifier(Modifier::UNDERLINED);
+        let selection_style = Style::default().bg(self.theme.muted);
+        let match_style = Style::default().fg(Color::Black).bg(self.theme.header);
+        // Later entries win, so the selection shows over matches
+        let mut highlights: Vec<(Range<usize>, Style)> = self
+            .matches
+            .iter()
+            .map(|range| (range.clone(), match_style))
+            .collect();
+        if let Some(selection) = self.selection {
+            highlights.push((selection, selection_style));
+        }
+        let gutter_style = Style::default().fg(self.theme.muted);
+        let mut lines = Vec::new();
+        let mut numbers = Vec::new();
+        let mut line_start = 0;
+        for (number, line) in self.note.content.split('\n').enumerate() {
+            let line_chars: Vec<char> = line.chars().collect();
+            for (row, chars) in row_ranges(&line_chars, wrap_width).into_iter().enumerate() {
+                lines.push(style_line(
+                    line,
+                    line_start,
+                    chars,
+                    &highlights,
+                    tag_style,
+                    link_style,
+                ));
+                // Without line numbers the gutter has no width to pad to
+                if gutter > 0 {
+                    numbers.push(if row == 0 {
+                        Line::styled(
+                            format!("{:>width$} ", number + 1, width = gutter as usize - 1),
+                            gutter_style,
+                        )
+                    } else {
+                        Line::default()
+                    });
+                }
+            }
+            line_start += line_chars.len() + 1;
+        }
+
+        if gutter > 0 {
+            Paragraph::new(numbers)
+                .scroll((self.scroll.0, 0))
+                .render(gutter_area, buf);
+        }
+        Paragraph::new(lines)
+            .scroll(self.scroll)
+            .render(text_area, buf);
+    }
+}
+
+/// Splits the `chars` range of a line into spans, styling inline `#tags`, `[[links]]` and
+/// any characters within the highlighted ranges. `line_start` is the char offset of the line
+/// within the note content.
+fn style_line(
+    line: &str,
+    line_start: usize,
+    chars: Range<usize>,
+    highlights: &[(Range<usize>, Style)],
+    tag_style: Style,
+    link_style: Style,
+) -> Line<'static> {
+    let tag_ranges = inline_tag_ranges(line);
+    let link_ranges = wiki_link_ranges(line);
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_style = Style::default();
+    // Column within the row, so tabs can be drawn up to the next tab stop
+    let mut x = 0;
+
+    for (char_index, (byte_index, c)) in line.char_indices().enumerate() {
+        if !chars.contains(&char_index) {
+            continue;
+        }
+        let mut style = Style::default();
+        if tag_ranges.iter().any(|range| range.contains(&byte_index)) {
+            style = style.patch(tag_style);
+        }
+        if link_ranges
+            .iter()
+            .any(|(range, _)| range.contains(&byte_index))
+        {
+            style = style.patch(link_style);
+        }
+        for (range, highlight) in highlights {
+            if range.contains(&(line_start + char_index)) {
+                style = style.patch(*highlight);
+            }
+        }
+
+        if style != current_style && !current.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut current), current_style));
+        }
+        current_style = style;
+        if c == '\t' {
+            current.push_str(&" ".repeat(width_at(c, x)));
+        } else {
+            current.push(c);
+        }
+        x += width_at(c, x);
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(current, current_style));
     }
+    Line::from(spans)
 }
@@ -1,6 +1,10 @@
 // Ratanotes/src/components/note_list.rs
 
-use crate::app::state::Note;
+use crate::app::state::{Note, TagMatch};
+use crate::components::scrollbar::render_list_scrollbar;
+use crate::utils::dates::DATE_FORMAT;
+use crate::utils::theme::Theme;
+use chrono::Local;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, ListState},
@@ -8,7 +12,38 @@ use ratatui::{
 
 pub struct NoteListWidget<'a> {
     pub notes: &'a [Note],
+    /// Indices into `notes` of the entries to list, in order.
+    pub indices: &'a [usize],
     pub has_focus: bool,
+    /// Number of notes linking to each entry of `notes`.
+    pub backlinks: &'a [usize],
+    /// Number of notes in the vault, before any tag filter.
+    pub total_count: usize,
+    /// The tag filter that produced `indices`, if any.
+    pub tag_filter: &'a [String],
+    pub tag_match: TagMatch,
+    /// Number of notes with unsaved edits across the whole vault.
+    pub unsaved_count: usize,
+    /// Lay the notes out in columns of this width when there is room for more than one.
+    pub column_width: Option<u16>,
+    /// Description of the current order, e.g. "title".
+    pub sort_label: &'a str,
+    /// Show the last update and tag count on a second line under each title.
+    pub details: bool,
+    pub theme: Theme,
+}
+
+/// Returns the number of rows each note takes in the list.
+pub fn item_height(details: bool) -> usize {
+    if details { 2 } else { 1 }
+}
+
+/// Returns the number of grid columns that fit in the list's `area`, or 1 for a plain list.
+pub fn grid_columns(area: Rect, column_width: Option<u16>) -> u16 {
+    match column_width {
+        Some(width) if width > 0 => (area.width.saturating_sub(2) / width).max(1),
+        _ => 1,
+    }
 }
 
 impl<'a> StatefulWidget for NoteListWidget<'a> {
@@ -16,30 +51,111 @@ impl<'a> StatefulWidget for NoteListWidget<'a> {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let items: Vec<ListItem> = self
-            .notes
+            .indices
             .iter()
-            .map(|note| ListItem::new(note.title.clone()))
+            .map(|&index| {
+                let note = &self.notes[index];
+                let backlinks = self.backlinks.get(index).copied().unwrap_or(0);
+                let mut spans = Vec::new();
+                if note.pinned {
+                    spans.push(Span::styled("* ", Style::default().fg(self.theme.tag)));
+                }
+                if note.dirty {
+                    spans.push(Span::styled("● ", Style::default().fg(self.theme.header)));
+                }
+                spans.push(Span::raw(note.title.clone()));
+                if note.archived {
+                    spans.push(Span::styled(
+                        " (archived)",
+                        Style::default().fg(self.theme.muted),
+                    ));
+                }
+                if backlinks > 0 {
+                    spans.push(Span::styled(
+                        format!(" ←{}", backlinks),
+                        Style::default().fg(self.theme.muted),
+                    ));
+                }
+                let mut lines = vec![Line::from(spans)];
+                if self.details {
+                    let tag_count = note.all_tags().len();
+                    lines.push(Line::styled(
+                        format!(
+                            "  updated {} · {} tag{}",
+                            note.updated_at.with_timezone(&Local).format(DATE_FORMAT),
+                            tag_count,
+                            if tag_count == 1 { "" } else { "s" }
+                        ),
+                        Style::default().fg(self.theme.muted),
+                    ));
+                }
+                ListItem::new(lines)
+            })
             .collect();
 
-        let border_style = if self.has_focus {
-            Style::default().fg(Color::Green)
-        } else {
-            Style::default()
-        };
-
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title("Notes")
-                    .borders(Borders::ALL)
-                    .border_style(border_style),
-            )
-            .highlight_style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .bg(Color::Blue),
+        let mut title = format!("Notes — {} total, by {}", self.total_count, self.sort_label);
+        if !self.tag_filter.is_empty() {
+            title.push_str(&format!(
+                ", {} tagged:{}",
+                self.indices.len(),
+                self.tag_filter.join(match self.tag_match {
+                    TagMatch::All => "+",
+                    TagMatch::Any => "|",
+                })
+            ));
+        }
+        if self.unsaved_count > 0 {
+            title.push_str(&format!(", {} unsaved", self.unsaved_count));
+        }
+
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(self.theme.border(self.has_focus));
+        let highlight_style = self.theme.highlight();
+
+        let columns = grid_columns(area, self.column_width) as usize;
+        if columns <= 1 {
+            let height = item_height(self.details);
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(highlight_style);
+            StatefulWidget::render(list, area, buf, state);
+            render_list_scrollbar(
+                area,
+                buf,
+                self.indices.len() * height,
+                state.selected().map(|i| i * height),
             );
+            return;
+        }
 
-        StatefulWidget::render(list, area, buf, state);
+        // Fill the grid column by column, scrolling sideways to keep the selection visible
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let rows = (inner.height as usize / item_height(self.details)).max(1);
+        let selected = state.selected();
+        let first_column = selected.map_or(0, |i| (i / rows).saturating_sub(columns - 1));
+        let width = self.column_width.unwrap_or(inner.width);
+        let mut items = items.into_iter().skip(first_column * rows);
+        for column in 0..columns {
+            let start = (first_column + column) * rows;
+            let column_items: Vec<ListItem> = items.by_ref().take(rows).collect();
+            if column_items.is_empty() {
+                break;
+            }
+            let column_area = Rect {
+                x: inner.x + column as u16 * width,
+                width,
+                ..inner
+            };
+            let mut column_state = ListState::default().with_selected(
+                selected
+                    .filter(|i| (start..start + rows).contains(i))
+                    .map(|i| i - start),
+            );
+            let list = List::new(column_items).highlight_style(highlight_style);
+            StatefulWidget::render(list, column_area, buf, &mut column_state);
+        }
     }
 }
@@ -0,0 +1,45 @@
+// Ratanotes/src/components/note_picker.rs
+
+use crate::app::state::Note;
+use crate::components::task_editor::centered_rect;
+use crate::utils::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+};
+
+/// A popup listing notes to open, such as the orphans or a note's backlinks.
+pub struct NotePickerWidget<'a> {
+    /// Heading of the popup; the number of notes is appended.
+    pub title: &'a str,
+    pub notes: Vec<&'a Note>,
+    pub theme: Theme,
+}
+
+impl<'a> StatefulWidget for NotePickerWidget<'a> {
+    type State = ListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let popup_area = centered_rect(60, 60, area);
+
+        // Clear the area behind the popup before rendering
+        Clear.render(popup_area, buf);
+
+        let items: Vec<ListItem> = self
+            .notes
+            .iter()
+            .map(|note| ListItem::new(note.title.as_str()))
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(" {} ({}) ", self.title, self.notes.len()))
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border(true)),
+            )
+            .highlight_style(self.theme.highlight());
+
+        StatefulWidget::render(list, popup_area, buf, state);
+    }
+}
@@ -0,0 +1,185 @@
+// Ratanotes/src/components/note_preview.rs
+
+use crate::app::state::Note;
+use crate::utils::config::MarkdownFlavor;
+use crate::utils::export::markdown_options;
+use crate::utils::highlight::highlight_line;
+use crate::utils::theme::Theme;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Parser, Tag, TagEnd};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+/// A read-only view of a note with its Markdown rendered as styled text.
+pub struct NotePreviewWidget<'a> {
+    pub note: &'a Note,
+    pub flavor: MarkdownFlavor,
+    /// Render only the text, without the surrounding block.
+    pub zen: bool,
+    /// Rows scrolled past.
+    pub scroll: u16,
+    pub theme: Theme,
+}
+
+impl<'a> Widget for NotePreviewWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let title = Line::from(vec![
+            Span::raw(self.note.title.as_str()),
+            Span::styled(" (preview)", Style::default().fg(self.theme.muted)),
+        ]);
+        let mut paragraph =
+            Paragraph::new(markdown_lines(&self.note.content, self.flavor, &self.theme))
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0));
+        if !self.zen {
+            paragraph = paragraph.block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border(true)),
+            );
+        }
+        paragraph.render(area, buf);
+    }
+}
+
+/// Builds styled lines from Markdown: bold headings, bulleted and numbered lists,
+/// italic and bold emphasis, and code in a distinct color. Fenced code blocks in a known
+/// language also have their keywords, strings, numbers and comments highlighted.
+pub fn markdown_lines(content: &str, flavor: MarkdownFlavor, theme: &Theme) -> Vec<Line<'static>> {
+    let code_style = Style::default().fg(theme.header);
+    let muted = Style::default().fg(theme.muted);
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    let mut current: Vec<Span<'static>> = Vec::new();
+    let mut styles = vec![Style::default()];
+    // One entry per open list: the next number for ordered lists, `None` for bullets
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut quote_depth = 0;
+    // The language of the open code block, empty if it has none
+    let mut code_block: Option<String> = None;
+
+    // Ends the current line, prefixing it with any block quote markers
+    let flush = |current: &mut Vec<Span<'static>>, lines: &mut Vec<Line<'static>>, depth: usize| {
+        let mut spans = Vec::new();
+        if depth > 0 {
+            spans.push(Span::styled("│ ".repeat(depth), muted));
+        }
+        spans.append(current);
+        lines.push(Line::from(spans));
+    };
+    // Separates blocks with one blank line, except at the top and inside lists
+    let blank = |lines: &mut Vec<Line<'static>>, in_list: bool| {
+        if !in_list && lines.last().is_some_and(|line| line.width() > 0) {
+            lines.push(Line::default());
+        }
+    };
+
+    for event in Parser::new_ext(content, markdown_options(flavor)) {
+        let style = *styles.last().unwrap_or(&Style::default());
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let mut heading = Style::default().fg(theme.key).add_modifier(Modifier::BOLD);
+                if level == HeadingLevel::H1 {
+                    heading = heading.add_modifier(Modifier::UNDERLINED);
+                }
+                styles.push(heading);
+            }
+            Event::Start(Tag::Emphasis) => styles.push(style.add_modifier(Modifier::ITALIC)),
+            Event::Start(Tag::Strong) => styles.push(style.add_modifier(Modifier::BOLD)),
+            Event::Start(Tag::Strikethrough) => {
+                styles.push(style.add_modifier(Modifier::CROSSED_OUT))
+            }
+            Event::Start(Tag::Link { .. }) => {
+                styles.push(style.fg(theme.link).add_modifier(Modifier::UNDERLINED))
+            }
+            Event::End(
+                TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link,
+            ) => {
+                styles.pop();
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                styles.pop();
+                flush(&mut current, &mut lines, quote_depth);
+                blank(&mut lines, false);
+            }
+            Event::End(TagEnd::Paragraph) => {
+                flush(&mut current, &mut lines, quote_depth);
+                blank(&mut lines, !lists.is_empty());
+            }
+            Event::Start(Tag::BlockQuote(_)) => quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quote_depth -= 1,
+            Event::Start(Tag::List(start)) => {
+                if !current.is_empty() {
+                    flush(&mut current, &mut lines, quote_depth);
+                }
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                blank(&mut lines, !lists.is_empty());
+            }
+            Event::Start(Tag::Item) => {
+                let indent = "  ".repeat(lists.len().saturating_sub(1));
+                let marker = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}{}. ", indent, *number - 1)
+                    }
+                    _ => format!("{}• ", indent),
+                };
+                current.push(Span::styled(marker, Style::default().fg(theme.key)));
+            }
+            Event::End(TagEnd::Item) if !current.is_empty() => {
+                flush(&mut current, &mut lines, quote_depth);
+            }
+            Event::TaskListMarker(checked) => {
+                current.push(Span::raw(if checked { "[x] " } else { "[ ] " }));
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let mut language = String::new();
+                if let CodeBlockKind::Fenced(info) = kind
+                    && !info.is_empty()
+                {
+                    current.push(Span::styled(info.to_string(), muted));
+                    flush(&mut current, &mut lines, quote_depth);
+                    // The info string may carry attributes after the language, as in `rust,ignore`
+                    language = info
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                }
+                code_block = Some(language);
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                code_block = None;
+                blank(&mut lines, false);
+            }
+            Event::Text(text) if code_block.is_some() => {
+                let language = code_block.as_deref().unwrap_or_default();
+                for line in text.lines() {
+                    current.push(Span::raw("    "));
+                    current.extend(highlight_line(line, language, code_style, theme));
+                    flush(&mut current, &mut lines, quote_depth);
+                }
+            }
+            Event::Text(text) => current.push(Span::styled(text.to_string(), style)),
+            Event::Code(code) => current.push(Span::styled(code.to_string(), code_style)),
+            Event::SoftBreak => current.push(Span::styled(" ", style)),
+            Event::HardBreak => flush(&mut current, &mut lines, quote_depth),
+            Event::Rule => {
+                current.push(Span::styled("────────", muted));
+                flush(&mut current, &mut lines, quote_depth);
+                blank(&mut lines, false);
+            }
+            _ => {}
+        }
+    }
+    if !current.is_empty() {
+        flush(&mut current, &mut lines, quote_depth);
+    }
+    // Drop the blank line left after the last block
+    if lines.last().is_some_and(|line| line.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
@@ -0,0 +1,36 @@
+// Ratanotes/src/components/recent_list.rs
+
+use crate::app::state::Note;
+use crate::utils::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+pub struct RecentListWidget<'a> {
+    /// Recently opened notes, most recent first.
+    pub notes: Vec<&'a Note>,
+    pub theme: Theme,
+}
+
+impl<'a> StatefulWidget for RecentListWidget<'a> {
+    type State = ListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let items: Vec<ListItem> = self
+            .notes
+            .iter()
+            .map(|note| ListItem::new(note.title.as_str()))
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Recent Notes (Enter: open)")
+                    .borders(Borders::ALL),
+            )
+            .highlight_style(self.theme.highlight());
+
+        StatefulWidget::render(list, area, buf, state);
+    }
+}
@@ -0,0 +1,29 @@
+// Ratanotes/src/components/scrollbar.rs
+
+use ratatui::{
+    prelude::*,
+    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+
+/// Draws a scrollbar over the right border of a bordered list in `area` when its `rows`
+/// don't all fit, with the thumb following the selected row.
+pub fn render_list_scrollbar(area: Rect, buf: &mut Buffer, rows: usize, selected: Option<usize>) {
+    let visible = area.height.saturating_sub(2) as usize;
+    if rows <= visible {
+        return;
+    }
+    let mut state = ScrollbarState::new(rows)
+        .viewport_content_length(visible)
+        .position(selected.unwrap_or(0));
+    Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .render(
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            buf,
+            &mut state,
+        );
+}
@@ -0,0 +1,76 @@
+// Ratanotes/src/components/settings_editor.rs
+
+use crate::components::task_editor::centered_rect;
+use crate::utils::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+pub struct SettingsEditorWidget<'a> {
+    /// Setting names with their current values, in display order.
+    pub entries: Vec<(&'a str, String)>,
+    /// The value being typed for the selected setting, if it is being edited.
+    pub edit_buffer: Option<&'a str>,
+    /// Why the last edit was rejected, if it was.
+    pub error: Option<&'a str>,
+    pub theme: Theme,
+}
+
+impl<'a> StatefulWidget for SettingsEditorWidget<'a> {
+    type State = ListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let popup_area = centered_rect(60, 60, area);
+
+        // Clear the area behind the popup before rendering
+        Clear.render(popup_area, buf);
+
+        let block = Block::default()
+            .title(" Settings (Enter: edit, s: save, Esc: cancel) ")
+            .borders(Borders::ALL)
+            .border_style(self.theme.border(true));
+
+        let [list_area, error_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+            .areas(block.inner(popup_area));
+
+        block.render(popup_area, buf);
+
+        let key_width = self
+            .entries
+            .iter()
+            .map(|(key, _)| key.len())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, (key, value))| {
+                let value = match self.edit_buffer {
+                    Some(buffer) if state.selected() == Some(i) => Span::styled(
+                        format!("{}_", buffer),
+                        Style::default().fg(self.theme.focus),
+                    ),
+                    _ => Span::raw(value.clone()),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<width$} = ", key, width = key_width),
+                        Style::default().fg(self.theme.key),
+                    ),
+                    value,
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::BOLD));
+        StatefulWidget::render(list, list_area, buf, state);
+
+        if let Some(error) = self.error {
+            Paragraph::new(error)
+                .style(Style::default().fg(self.theme.error))
+                .render(error_area, buf);
+        }
+    }
+}
@@ -1,16 +1,116 @@
 // Ratanotes/src/components/status_bar.rs
 
+use crate::app::state::{Mode, View};
+use crate::utils::theme::Theme;
 use ratatui::prelude::*;
 use ratatui::widgets::Paragraph;
+use unicode_width::UnicodeWidthStr;
 
 pub struct StatusBarWidget<'a> {
-    pub message: &'a str,
+    /// Shown as a colored segment on the left.
+    pub mode: &'a Mode,
+    /// Shown with the note and task counts in a segment on the right.
+    pub view: &'a View,
+    pub note_count: usize,
+    /// Number of notes with edits not yet written to disk.
+    pub unsaved_count: usize,
+    pub task_count: usize,
+    /// Layout of the middle text, with `{name}` placeholders for the `fields`.
+    pub format: &'a str,
+    /// Placeholder names and their current values, e.g. `("message", ...)`.
+    pub fields: Vec<(&'static str, String)>,
+    /// Text right-aligned before the view segment, such as the cursor position in the
+    /// editor. Empty to hide it.
+    pub right: String,
+    pub theme: Theme,
 }
 
 impl<'a> Widget for StatusBarWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let style = Style::default();
-        let paragraph = Paragraph::new(self.message).style(style);
-        paragraph.render(area, buf);
+        let mode = format!(" {} ", self.mode.label());
+        let unsaved = if self.unsaved_count > 0 {
+            format!(" ({} unsaved)", self.unsaved_count)
+        } else {
+            String::new()
+        };
+        let view = format!(
+            " {} │ {} notes{} │ {} tasks ",
+            self.view.label(),
+            self.note_count,
+            unsaved,
+            self.task_count
+        );
+        let text = expand_format(self.format, &self.fields);
+        let right_width = (self.right.width() as u16).min(area.width);
+        let [mode_area, _, left_area, right_area, view_area] = Layout::horizontal([
+            Constraint::Length(mode.width() as u16),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(right_width),
+            Constraint::Length(view.width() as u16),
+        ])
+        .areas(area);
+
+        Paragraph::new(mode)
+            .style(segment_style(mode_color(self.mode, &self.theme)).add_modifier(Modifier::BOLD))
+            .render(mode_area, buf);
+        Paragraph::new(text).render(left_area, buf);
+        Paragraph::new(self.right)
+            .alignment(Alignment::Right)
+            .render(right_area, buf);
+        Paragraph::new(view)
+            .style(segment_style(self.theme.muted))
+            .render(view_area, buf);
+    }
+}
+
+/// Returns the background color of the mode segment, like the mode colors of a Vim statusline.
+fn mode_color(mode: &Mode, theme: &Theme) -> Color {
+    match mode {
+        Mode::Normal => theme.focus,
+        Mode::Insert => theme.insert,
+        Mode::Visual => theme.highlight_bg,
+        Mode::Command | Mode::FindInNote => theme.header,
+        Mode::ConfirmDeletion | Mode::ConfirmQuit | Mode::ConfirmImport => theme.error,
+        _ => theme.link,
+    }
+}
+
+/// Style of a segment drawn on `background`. Themes without a color for it get reversed text.
+fn segment_style(background: Color) -> Style {
+    if background == Color::Reset {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default().fg(Color::Black).bg(background)
+    }
+}
+
+/// Replaces each `{name}` in `format` with its value from `fields`.
+/// Unknown placeholders and unmatched braces are kept literally.
+fn expand_format(format: &str, fields: &[(&str, String)]) -> String {
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                text.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
     }
+    text.push_str(rest);
+    text
 }
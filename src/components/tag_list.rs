@@ -1,5 +1,8 @@
 // Ratanotes/src/components/tag_list.rs
 
+use crate::app::state::TagMatch;
+use crate::components::scrollbar::render_list_scrollbar;
+use crate::utils::theme::Theme;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, ListState},
@@ -8,7 +11,12 @@ use ratatui::{
 pub struct TagListWidget<'a> {
     pub tags: &'a [String],
     pub has_focus: bool,
-    pub active_tag: &'a Option<String>,
+    /// Tags marked with `Space`, waiting to be applied.
+    pub marked_tags: &'a [String],
+    /// Tags currently filtering the note list.
+    pub tag_filter: &'a [String],
+    pub tag_match: TagMatch,
+    pub theme: Theme,
 }
 
 impl<'a> StatefulWidget for TagListWidget<'a> {
@@ -16,40 +24,41 @@ impl<'a> StatefulWidget for TagListWidget<'a> {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let active_style = Style::default()
-            .fg(Color::Yellow)
+            .fg(self.theme.header)
             .add_modifier(Modifier::BOLD);
 
         let items: Vec<ListItem> = self
             .tags
             .iter()
             .map(|tag| {
-                let mut item = ListItem::new(tag.clone());
-                if self.active_tag.as_deref() == Some(tag) {
+                let checkbox = if self.marked_tags.contains(tag) {
+                    "[x] "
+                } else {
+                    "[ ] "
+                };
+                let mut item = ListItem::new(format!("{}{}", checkbox, tag));
+                if self.tag_filter.contains(tag) {
                     item = item.style(active_style);
                 }
                 item
             })
             .collect();
 
-        let border_style = if self.has_focus {
-            Style::default().fg(Color::Green)
-        } else {
-            Style::default()
+        let title = match (self.tag_filter.len(), self.tag_match) {
+            (0, TagMatch::All) => "Tags".to_string(),
+            (0, tag_match) => format!("Tags ({})", tag_match.label()),
+            (count, tag_match) => format!("Tags ({} of {})", tag_match.label(), count),
         };
-
         let list = List::new(items)
             .block(
                 Block::default()
-                    .title("Tags")
+                    .title(title)
                     .borders(Borders::ALL)
-                    .border_style(border_style),
+                    .border_style(self.theme.border(self.has_focus)),
             )
-            .highlight_style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .bg(Color::Blue),
-            );
+            .highlight_style(self.theme.highlight());
 
         StatefulWidget::render(list, area, buf, state);
+        render_list_scrollbar(area, buf, self.tags.len(), state.selected());
     }
 }
@@ -1,6 +1,8 @@
 // Ratanotes/src/components/task_editor.rs
 
-use crate::app::state::{Priority, Task, TaskEditFocus};
+use crate::app::state::{Task, TaskEditFocus};
+use crate::utils::dates::DATE_FORMAT;
+use crate::utils::theme::Theme;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph},
@@ -10,6 +12,7 @@ pub struct TaskEditorWidget<'a> {
     pub task: &'a Task,
     pub edit_buffer: &'a str,
     pub focus: &'a TaskEditFocus,
+    pub theme: Theme,
 }
 
 impl<'a> Widget for TaskEditorWidget<'a> {
@@ -22,7 +25,7 @@ impl<'a> Widget for TaskEditorWidget<'a> {
         let block = Block::default()
             .title(" Edit Task ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Yellow));
+            .border_style(self.theme.border(true));
 
         let editor_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -41,12 +44,18 @@ impl<'a> Widget for TaskEditorWidget<'a> {
 
         // -- Description Field --
         let description_border_style = if let TaskEditFocus::Description = self.focus {
-            Style::default().fg(Color::Cyan)
+            Style::default().fg(self.theme.key)
         } else {
             Style::default()
         };
 
-        let description_p = Paragraph::new(self.edit_buffer).block(
+        let description_text = if let TaskEditFocus::Description = self.focus {
+            self.edit_buffer
+        } else {
+            &self.task.description
+        };
+
+        let description_p = Paragraph::new(description_text).block(
             Block::default()
                 .title("Description")
                 .borders(Borders::ALL)
@@ -56,7 +65,7 @@ impl<'a> Widget for TaskEditorWidget<'a> {
 
         // -- Priority Field --
         let priority_border_style = if let TaskEditFocus::Priority = self.focus {
-            Style::default().fg(Color::Cyan)
+            Style::default().fg(self.theme.key)
         } else {
             Style::default()
         };
@@ -75,7 +84,7 @@ impl<'a> Widget for TaskEditorWidget<'a> {
 
         // -- Due Date Field --
         let due_date_border_style = if let TaskEditFocus::DueDate = self.focus {
-            Style::default().fg(Color::Cyan)
+            Style::default().fg(self.theme.key)
         } else {
             Style::default()
         };
@@ -85,13 +94,13 @@ impl<'a> Widget for TaskEditorWidget<'a> {
         } else {
             self.task
                 .due_date
-                .map(|d| d.format("%d-%m-%Y").to_string())
-                .unwrap_or_else(|| "".to_string())
+                .map(|d| d.format(DATE_FORMAT).to_string())
+                .unwrap_or_default()
         };
 
         let due_date_p = Paragraph::new(due_date_text).block(
             Block::default()
-                .title("Due Date (DD-MM-YYYY)")
+                .title("Due Date (YYYY-MM-DD)")
                 .borders(Borders::ALL)
                 .border_style(due_date_border_style),
         );
@@ -100,7 +109,7 @@ impl<'a> Widget for TaskEditorWidget<'a> {
 }
 
 /// Helper function to create a centered rect for the popup.
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -1,44 +1,111 @@
 // Ratanotes/src/components/task_list.rs
 
-use crate::app::state::Task;
+use crate::app::state::{Task, TaskGrouping};
+use crate::components::scrollbar::render_list_scrollbar;
+use crate::utils::dates::{DATE_FORMAT, due_group};
+use crate::utils::theme::Theme;
+use chrono::{Local, NaiveDate, Weekday};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
 
 pub struct TaskListWidget<'a> {
     pub tasks: &'a [Task],
+    /// Task indices in display order.
+    pub order: Vec<usize>,
+    /// Which headers to insert before each group of tasks.
+    pub grouping: TaskGrouping,
+    /// Strike through the descriptions of completed tasks.
+    pub strike_completed: bool,
+    /// First day of the week, which ends the "This Week" group.
+    pub first_weekday: Weekday,
+    pub theme: Theme,
 }
 
 impl<'a> StatefulWidget for TaskListWidget<'a> {
     type State = ListState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let items: Vec<ListItem> = self
-            .tasks
-            .iter()
-            .map(|task| {
-                let completed_marker = if task.completed { "[x]" } else { "[ ]" };
-                let priority = format!("[{:?}]", task.priority);
-                let due_date = task
-                    .due_date
-                    .map(|d| d.format(" (%d-%m-%Y)").to_string())
-                    .unwrap_or_default();
-
-                let line = format!(
-                    "{} {} {}{}",
-                    completed_marker, priority, task.description, due_date
-                );
-                ListItem::new(line)
-            })
-            .collect();
+        let today = Local::now().date_naive();
+        let header_style = Style::default()
+            .fg(self.theme.header)
+            .add_modifier(Modifier::BOLD);
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut selected_row = None;
+        let mut current_group = None;
+        for &index in &self.order {
+            let task = &self.tasks[index];
+            let group = match self.grouping {
+                TaskGrouping::None => None,
+                TaskGrouping::DueDate => {
+                    Some(due_group(task.due_date, today, self.first_weekday).label())
+                }
+                TaskGrouping::Project => Some(task.project.as_deref().unwrap_or("No Project")),
+            };
+            if group.is_some() && current_group != group {
+                current_group = group;
+                items.push(ListItem::new(Span::styled(
+                    group.unwrap_or_default(),
+                    header_style,
+                )));
+            }
+            if state.selected() == Some(index) {
+                selected_row = Some(items.len());
+            }
+            items.push(ListItem::new(task_line(
+                task,
+                self.strike_completed,
+                today,
+                self.theme,
+            )));
+        }
 
+        let rows = items.len();
         let list = List::new(items)
             .block(Block::default().title("Tasks").borders(Borders::ALL))
-            .highlight_style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .bg(Color::Blue),
-            );
+            .highlight_style(self.theme.highlight());
 
-        StatefulWidget::render(list, area, buf, state);
+        // The selection refers to a task index; map it to its row among the headers
+        let mut row_state = ListState::default()
+            .with_offset(state.offset())
+            .with_selected(selected_row);
+        StatefulWidget::render(list, area, buf, &mut row_state);
+        *state.offset_mut() = row_state.offset();
+        render_list_scrollbar(area, buf, rows, selected_row);
     }
 }
+
+/// Builds the line shown for a single task. Open tasks past their due date get the
+/// theme's error color and those due `today` its header color.
+pub fn task_line(task: &Task, strike_completed: bool, today: NaiveDate, theme: Theme) -> Line<'_> {
+    let completed_marker = if task.completed { "[x]" } else { "[ ]" };
+    let priority = format!("[{:?}]", task.priority);
+    let due_date = task
+        .due_date
+        .map(|d| format!(" ({})", d.format(DATE_FORMAT)))
+        .unwrap_or_default();
+
+    // Completed tasks are dimmed; only the description is struck through
+    // so the priority and due date stay readable
+    let (style, description_style) = if task.completed {
+        let dim = Style::default().fg(theme.muted).add_modifier(Modifier::DIM);
+        let description_style = if strike_completed {
+            dim.add_modifier(Modifier::CROSSED_OUT)
+        } else {
+            dim
+        };
+        (dim, description_style)
+    } else {
+        let style = match task.due_date {
+            Some(due) if due < today => Style::default().fg(theme.error),
+            Some(due) if due == today => Style::default().fg(theme.header),
+            _ => Style::default(),
+        };
+        (style, style)
+    };
+
+    Line::from(vec![
+        Span::styled(format!("{} {} ", completed_marker, priority), style),
+        Span::styled(task.description.as_str(), description_style),
+        Span::styled(due_date, style),
+    ])
+}
@@ -0,0 +1,48 @@
+// Ratanotes/src/components/trash_list.rs
+
+use crate::app::state::TrashedNote;
+use crate::utils::theme::Theme;
+use chrono::Local;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+pub struct TrashListWidget<'a> {
+    pub trash: &'a [TrashedNote],
+    pub theme: Theme,
+}
+
+impl<'a> StatefulWidget for TrashListWidget<'a> {
+    type State = ListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let items: Vec<ListItem> = self
+            .trash
+            .iter()
+            .map(|trashed| {
+                let deleted_at = trashed
+                    .deleted_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M");
+                ListItem::new(Line::from(vec![
+                    Span::raw(trashed.title.as_str()),
+                    Span::styled(
+                        format!("  (deleted {})", deleted_at),
+                        Style::default().fg(self.theme.muted),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Trash (u/Enter: restore, d: delete forever)")
+                    .borders(Borders::ALL),
+            )
+            .highlight_style(self.theme.highlight());
+
+        StatefulWidget::render(list, area, buf, state);
+    }
+}
@@ -1,16 +1,98 @@
 mod app;
+mod cli;
 mod components;
 mod utils;
 
 use app::app::{App, restore_terminal, setup_terminal};
-use std::io;
+use chrono::Local;
+use cli::{CliArgs, NewNote, StatsFormat, USAGE};
+use std::{io, path::PathBuf};
+use utils::{config::Config, data_handler::DataHandler, tags::dedup_tags};
+
+/// Creates and saves a note without starting the TUI, returning its path.
+fn capture_note(new_note: NewNote) -> io::Result<PathBuf> {
+    let data_handler = DataHandler::new(&Config::load()?)?;
+    let notes = data_handler.load_notes()?;
+    let note = data_handler.new_note(new_note.title, new_note.content, new_note.tags, &notes);
+    data_handler.save_notes(std::slice::from_ref(&note))?;
+    Ok(note.path)
+}
+
+/// Prints note, task and tag counts for the vault without writing anything.
+fn print_stats(format: StatsFormat) -> io::Result<()> {
+    let data_handler = DataHandler::open_existing(&Config::load()?)?;
+    let notes = data_handler.load_notes()?;
+    let tasks = data_handler.load_tasks()?;
+
+    let today = Local::now().date_naive();
+    let completed = tasks.iter().filter(|task| task.completed).count();
+    let overdue = tasks
+        .iter()
+        .filter(|task| !task.completed && task.due_date.is_some_and(|due| due < today))
+        .count();
+    let tags = dedup_tags(notes.iter().flat_map(|note| note.all_tags())).len();
+
+    match format {
+        StatsFormat::Text => {
+            println!("notes: {}", notes.len());
+            println!("tasks: {}", tasks.len());
+            println!("completed: {}", completed);
+            println!("overdue: {}", overdue);
+            println!("tags: {}", tags);
+        }
+        StatsFormat::Json => {
+            let stats = serde_json::json!({
+                "notes": notes.len(),
+                "tasks": tasks.len(),
+                "completed": completed,
+                "overdue": overdue,
+                "tags": tags,
+            });
+            println!("{}", stats);
+        }
+    }
+    Ok(())
+}
 
 fn main() -> io::Result<()> {
+    // Parse the command line before touching the terminal
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("Error: {}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(new_note) = args.new_note {
+        match capture_note(new_note) {
+            Ok(path) => {
+                println!("{}", path.display());
+                return Ok(());
+            }
+            Err(err) => {
+                eprintln!("Error: could not create note: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(format) = args.stats {
+        if let Err(err) = print_stats(format) {
+            eprintln!("Error: could not read the vault: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Setup the terminal
     let mut terminal = setup_terminal()?;
 
     // Create and run the app
     let mut app = App::new();
+    if let Some(target) = args.open {
+        app.open_note_target(target);
+    }
     let result = app.run(&mut terminal);
 
     // Restore the terminal
@@ -0,0 +1,109 @@
+// Ratanotes/src/utils/checklist.rs
+
+use crate::app::state::Task;
+
+/// A Markdown checklist item such as `- [ ] Buy milk` or `* [x] Call Sam`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub checked: bool,
+}
+
+/// Parses a line as a checklist item: optional indentation, a `-`, `*` or `+` bullet, a
+/// `[ ]`, `[x]` or `[X]` box and the item's text. Returns `None` for any other line,
+/// including an item with no text.
+pub fn parse_checklist_item(line: &str) -> Option<ChecklistItem> {
+    let rest = line.trim_start();
+    let rest = rest
+        .strip_prefix(['-', '*', '+'])?
+        .strip_prefix(' ')?
+        .trim_start();
+    let (checked, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest.strip_prefix("[x]").or(rest.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+    // The box must be followed by a space, so `[x]abc` stays plain text
+    if !text.is_empty() && !text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let text = text.trim();
+    (!text.is_empty()).then(|| ChecklistItem {
+        text: text.to_string(),
+        checked,
+    })
+}
+
+/// Appends a task and its sub-tasks to a Markdown checklist, indented by `depth`.
+pub fn push_checklist_item(checklist: &mut String, task: &Task, depth: usize) {
+    let mut details = Vec::new();
+    if let Some(due) = task.due_date {
+        details.push(format!("due {}", due.format("%Y-%m-%d")));
+    }
+    if let Some(project) = &task.project {
+        details.push(project.clone());
+    }
+    details.push(format!("{:?} priority", task.priority));
+    checklist.push_str(&format!(
+        "{}- [{}] {} ({})\n",
+        "  ".repeat(depth),
+        if task.completed { "x" } else { " " },
+        task.description,
+        details.join(", ")
+    ));
+    for sub_task in &task.sub_tasks {
+        push_checklist_item(checklist, sub_task, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, checked: bool) -> Option<ChecklistItem> {
+        Some(ChecklistItem {
+            text: text.to_string(),
+            checked,
+        })
+    }
+
+    #[test]
+    fn parses_each_bullet_and_box() {
+        assert_eq!(
+            parse_checklist_item("- [ ] Buy milk"),
+            item("Buy milk", false)
+        );
+        assert_eq!(
+            parse_checklist_item("* [x] Call Sam"),
+            item("Call Sam", true)
+        );
+        assert_eq!(
+            parse_checklist_item("+ [X] Pay rent"),
+            item("Pay rent", true)
+        );
+    }
+
+    #[test]
+    fn parses_indented_items() {
+        assert_eq!(
+            parse_checklist_item("    - [ ] Nested"),
+            item("Nested", false)
+        );
+        assert_eq!(
+            parse_checklist_item("\t* [x] Tabbed  "),
+            item("Tabbed", true)
+        );
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_items() {
+        assert_eq!(parse_checklist_item("- [x]abc"), None);
+        assert_eq!(parse_checklist_item("- [ ]"), None);
+        assert_eq!(parse_checklist_item("- [ ]   "), None);
+        assert_eq!(parse_checklist_item("- plain bullet"), None);
+        assert_eq!(parse_checklist_item("-[ ] no space"), None);
+        assert_eq!(parse_checklist_item("[ ] no bullet"), None);
+    }
+}
@@ -0,0 +1,389 @@
+// Ratanotes/src/utils/config.rs
+
+use crate::utils::theme::{THEME_NAMES, Theme};
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// Destructive actions that may be routed through a confirmation prompt.
+pub enum ConfirmAction {
+    DeleteNote,
+    DeleteTask,
+    DeleteTag,
+    QuitUnsaved,
+}
+
+/// Markdown dialect used when rendering notes to HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownFlavor {
+    /// GitHub-flavored: tables, strikethrough, task lists and footnotes.
+    #[default]
+    Gfm,
+    /// Plain CommonMark with no extensions.
+    CommonMark,
+}
+
+/// The day the calendar's weeks start on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// Returns the first day of the week.
+    pub fn weekday(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// Syntax of the front matter at the top of a note file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    /// YAML between `---` lines.
+    #[default]
+    Yaml,
+    /// TOML between `+++` lines, as used by Hugo and Zola.
+    Toml,
+}
+
+/// Per-action confirmation flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    pub delete_note: bool,
+    pub delete_task: bool,
+    pub delete_tag: bool,
+    pub quit_unsaved: bool,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        Self {
+            delete_note: true,
+            delete_task: true,
+            delete_tag: true,
+            quit_unsaved: true,
+        }
+    }
+}
+
+/// Keys of the settings editable from the `:config` form, in display order.
+pub const SETTINGS: &[&str] = &[
+    "confirm_destructive",
+    "confirm.delete_note",
+    "confirm.delete_task",
+    "confirm.delete_tag",
+    "confirm.quit_unsaved",
+    "on_save_command",
+    "lift_inline_tags",
+    "seed_on_first_run",
+    "markdown_flavor",
+    "wrap",
+    "line_numbers",
+    "tab_width",
+    "note_column_width",
+    "note_details",
+    "week_starts_on",
+    "strikethrough_completed",
+    "status_format",
+    "autosave_secs",
+    "poll_ms",
+    "theme",
+];
+
+/// Parses a boolean setting, accepting a few common spellings.
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{}' is not true or false", value)),
+    }
+}
+
+/// Expands a leading `~` and any `$VAR` or `${VAR}` in a configured path.
+/// Unset variables are left as written.
+pub fn expand_path(path: &str) -> PathBuf {
+    let mut expanded = String::new();
+    let mut rest = path;
+    if let Some(after) = rest.strip_prefix('~')
+        && (after.is_empty() || after.starts_with('/'))
+        && let Some(home) = dirs::home_dir()
+    {
+        expanded.push_str(&home.to_string_lossy());
+        rest = after;
+    }
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, len) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[start..start + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+/// User configuration, read from `~/.config/ratanotes/config.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Master switch for confirmation prompts. When off, no action asks for confirmation.
+    pub confirm_destructive: bool,
+    /// Granular flags selecting which actions ask for confirmation.
+    pub confirm: ConfirmConfig,
+    /// Shell command run in the notes directory after a successful save.
+    pub on_save_command: Option<String>,
+    /// Copy inline `#tags` into the front matter `tags` list when saving.
+    pub lift_inline_tags: bool,
+    /// Write a welcome note the first time the app starts with an empty vault.
+    pub seed_on_first_run: bool,
+    /// Markdown dialect used by `:html` export.
+    pub markdown_flavor: MarkdownFlavor,
+    /// Wrap long lines in the editor unless a note says otherwise.
+    pub wrap: bool,
+    /// Show line numbers in the editor unless a note says otherwise.
+    pub line_numbers: bool,
+    /// Spaces inserted by `Tab` in Insert mode, or `0` to insert a literal tab.
+    pub tab_width: usize,
+    /// Lay the note list out in columns this wide when the pane fits more than one.
+    pub note_column_width: Option<u16>,
+    /// Show each note's last update and tag count under its title in the note list.
+    pub note_details: bool,
+    /// First column of the calendar: `monday` or `sunday`.
+    pub week_starts_on: WeekStart,
+    /// Strike through completed tasks. Turn off for terminals that render it poorly.
+    pub strikethrough_completed: bool,
+    /// Layout of the status bar text between the mode and view segments. Placeholders:
+    /// `{mode}`, `{view}`, `{notes}`, `{sort}`, `{tasks}`, `{unsaved}`, `{title}` and `{message}`.
+    pub status_format: String,
+    /// Directory holding the notes, instead of `~/.config/ratanotes/notes`.
+    /// `~` and environment variables are expanded. Read at startup.
+    pub notes_dir: Option<String>,
+    /// Tasks file, instead of `~/.config/ratanotes/tasks.json`. Expanded like `notes_dir`.
+    pub tasks_file: Option<String>,
+    /// Front matter syntax for new notes: `yaml` or `toml`. Existing notes keep the syntax
+    /// they were read with. Read at startup.
+    pub front_matter_format: FrontMatterFormat,
+    /// Seconds between automatic saves of unsaved notes. `0` turns autosave off.
+    pub autosave_secs: u64,
+    /// Milliseconds to wait for input before checking for autosave, hook results and
+    /// changed files. Keys and resizes are handled as soon as they arrive either way, so
+    /// a higher value mostly trades how quickly background work shows up for fewer CPU
+    /// wakeups and better battery life. With autosave off it is at least 250.
+    pub poll_ms: u64,
+    /// Name of the color theme, one of [`THEME_NAMES`].
+    pub theme: String,
+    /// Extra key bindings, from action names such as `NextNote` to keys such as `"ctrl-n"`.
+    /// They are tried before the built-in keys of the view they belong to.
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            confirm_destructive: true,
+            confirm: ConfirmConfig::default(),
+            on_save_command: None,
+            lift_inline_tags: false,
+            seed_on_first_run: true,
+            markdown_flavor: MarkdownFlavor::default(),
+            wrap: false,
+            line_numbers: false,
+            note_column_width: None,
+            note_details: true,
+            week_starts_on: WeekStart::default(),
+            strikethrough_completed: true,
+            status_format: "{message}".to_string(),
+            notes_dir: None,
+            tasks_file: None,
+            front_matter_format: FrontMatterFormat::default(),
+            autosave_secs: 30,
+            poll_ms: 50,
+            tab_width: 4,
+            theme: "default".to_string(),
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the path of the configuration file, if the home directory can be found.
+    pub fn path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config").join("ratanotes").join("config.toml"))
+    }
+
+    /// Loads the configuration file, falling back to the defaults when it does not exist.
+    pub fn load() -> Result<Self, std::io::Error> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the configuration file, replacing its previous contents.
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        let Some(path) = Self::path() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Could not find home directory",
+            ));
+        };
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, content)
+    }
+
+    /// Returns the current value of a setting from [`SETTINGS`] as text.
+    pub fn get(&self, key: &str) -> String {
+        match key {
+            "confirm_destructive" => self.confirm_destructive.to_string(),
+            "confirm.delete_note" => self.confirm.delete_note.to_string(),
+            "confirm.delete_task" => self.confirm.delete_task.to_string(),
+            "confirm.delete_tag" => self.confirm.delete_tag.to_string(),
+            "confirm.quit_unsaved" => self.confirm.quit_unsaved.to_string(),
+            "on_save_command" => self.on_save_command.clone().unwrap_or_default(),
+            "lift_inline_tags" => self.lift_inline_tags.to_string(),
+            "seed_on_first_run" => self.seed_on_first_run.to_string(),
+            "markdown_flavor" => match self.markdown_flavor {
+                MarkdownFlavor::Gfm => "gfm".to_string(),
+                MarkdownFlavor::CommonMark => "commonmark".to_string(),
+            },
+            "wrap" => self.wrap.to_string(),
+            "line_numbers" => self.line_numbers.to_string(),
+            "note_column_width" => self
+                .note_column_width
+                .map(|width| width.to_string())
+                .unwrap_or_default(),
+            "note_details" => self.note_details.to_string(),
+            "week_starts_on" => match self.week_starts_on {
+                WeekStart::Monday => "monday".to_string(),
+                WeekStart::Sunday => "sunday".to_string(),
+            },
+            "strikethrough_completed" => self.strikethrough_completed.to_string(),
+            "status_format" => self.status_format.clone(),
+            "autosave_secs" => self.autosave_secs.to_string(),
+            "poll_ms" => self.poll_ms.to_string(),
+            "tab_width" => self.tab_width.to_string(),
+            "theme" => self.theme.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Validates and sets a setting from [`SETTINGS`], leaving it unchanged on error.
+    /// An empty value clears optional settings.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "confirm_destructive" => self.confirm_destructive = parse_bool(value)?,
+            "confirm.delete_note" => self.confirm.delete_note = parse_bool(value)?,
+            "confirm.delete_task" => self.confirm.delete_task = parse_bool(value)?,
+            "confirm.delete_tag" => self.confirm.delete_tag = parse_bool(value)?,
+            "confirm.quit_unsaved" => self.confirm.quit_unsaved = parse_bool(value)?,
+            "on_save_command" => {
+                self.on_save_command = (!value.is_empty()).then(|| value.to_string());
+            }
+            "lift_inline_tags" => self.lift_inline_tags = parse_bool(value)?,
+            "seed_on_first_run" => self.seed_on_first_run = parse_bool(value)?,
+            "markdown_flavor" => {
+                self.markdown_flavor = match value.to_lowercase().as_str() {
+                    "gfm" => MarkdownFlavor::Gfm,
+                    "commonmark" => MarkdownFlavor::CommonMark,
+                    _ => return Err("expected 'gfm' or 'commonmark'".to_string()),
+                };
+            }
+            "wrap" => self.wrap = parse_bool(value)?,
+            "line_numbers" => self.line_numbers = parse_bool(value)?,
+            "note_column_width" => {
+                self.note_column_width = if value.is_empty() {
+                    None
+                } else {
+                    match value.parse::<u16>() {
+                        Ok(width) if width > 0 => Some(width),
+                        _ => return Err("expected a positive number of columns".to_string()),
+                    }
+                };
+            }
+            "note_details" => self.note_details = parse_bool(value)?,
+            "week_starts_on" => {
+                self.week_starts_on = match value.to_lowercase().as_str() {
+                    "monday" => WeekStart::Monday,
+                    "sunday" => WeekStart::Sunday,
+                    _ => return Err("expected 'monday' or 'sunday'".to_string()),
+                };
+            }
+            "strikethrough_completed" => self.strikethrough_completed = parse_bool(value)?,
+            "status_format" => self.status_format = value.to_string(),
+            "tab_width" => {
+                self.tab_width = value
+                    .parse()
+                    .map_err(|_| "expected a number of spaces, 0 for a literal tab".to_string())?;
+            }
+            "poll_ms" => {
+                self.poll_ms = match value.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err("expected a number of milliseconds above 0".to_string());
+                    }
+                    Ok(ms) => ms,
+                };
+            }
+            "autosave_secs" => {
+                self.autosave_secs = value
+                    .parse()
+                    .map_err(|_| "expected a number of seconds, 0 to disable".to_string())?;
+            }
+            "theme" => {
+                if !THEME_NAMES.contains(&value) {
+                    return Err(format!("expected one of {}", THEME_NAMES.join(", ")));
+                }
+                self.theme = value.to_string();
+            }
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Returns the configured color theme, or the default one if the name is unknown.
+    pub fn theme(&self) -> Theme {
+        Theme::named(&self.theme).unwrap_or_default()
+    }
+
+    /// Returns whether the given action should ask for confirmation.
+    pub fn should_confirm(&self, action: ConfirmAction) -> bool {
+        self.confirm_destructive
+            && match action {
+                ConfirmAction::DeleteNote => self.confirm.delete_note,
+                ConfirmAction::DeleteTask => self.confirm.delete_task,
+                ConfirmAction::DeleteTag => self.confirm.delete_tag,
+                ConfirmAction::QuitUnsaved => self.confirm.quit_unsaved,
+            }
+    }
+}
@@ -1,8 +1,14 @@
 // Ratanotes/src/utils/data_handler.rs
 
-use crate::app::state::{Note, Task};
-use chrono::{DateTime, Utc};
-use glob::glob;
+use crate::app::state::{Note, NoteView, Task, TrashedNote};
+use crate::utils::{
+    checklist::push_checklist_item,
+    config::{Config, FrontMatterFormat, MarkdownFlavor, expand_path},
+    export::note_to_html,
+    templates,
+};
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, Utc};
+use glob::{Pattern, glob};
 use serde_yaml;
 use std::{
     fs::{self, File},
@@ -10,15 +16,92 @@ use std::{
     path::{Path, PathBuf},
 };
 
+/// Body of the welcome note written on first run.
+const WELCOME_NOTE: &str = include_str!("../../assets/welcome.md");
+
+/// Writes `contents` to `path` through a temporary sibling file (`path` + `.tmp`) that is
+/// then renamed over it, so a crash mid-write leaves the old file intact instead of
+/// truncated. The rename is atomic on most filesystems.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    fs::rename(&temp, path)
+}
+
+/// Lists the `.md` files anywhere under `dir`. The directory is matched literally, so
+/// characters like `[` in a configured path don't act as glob syntax.
+fn glob_markdown(dir: &Path) -> Result<glob::Paths, std::io::Error> {
+    let dir = dir.to_str().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not valid UTF-8", dir.display()),
+        )
+    })?;
+    let pattern = Path::new(&Pattern::escape(dir)).join("**/*.md");
+    glob(&pattern.to_string_lossy())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Moves a file, copying it and removing the original when `from` and `to` are on
+/// different filesystems, which a plain rename can't cross.
+fn move_file(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
 /// Handles data persistence for the application.
 pub struct DataHandler {
     pub notes_dir: PathBuf,
     tasks_file: PathBuf,
+    trash_dir: PathBuf,
+    exports_dir: PathBuf,
+    /// Directory of `*.md` note templates.
+    templates_dir: PathBuf,
+    recent_file: PathBuf,
+    /// The date last selected in the calendar, so it reopens where it was left.
+    calendar_file: PathBuf,
+    /// Marker file recording that first-run seeding has already happened.
+    seeded_marker: PathBuf,
+    /// Front matter syntax for new notes.
+    front_matter_format: FrontMatterFormat,
+    /// Whether nothing may be written, because the data directory couldn't be set up.
+    in_memory: bool,
 }
 
 impl DataHandler {
     /// Creates a new `DataHandler` and ensures the necessary directories and files exist.
-    pub fn new() -> Result<Self, std::io::Error> {
+    pub fn new(config: &Config) -> Result<Self, std::io::Error> {
+        let handler = Self::open_existing(config)?;
+        fs::create_dir_all(handler.notes_dir.join("daily-notes"))?;
+        fs::create_dir_all(&handler.trash_dir)?;
+
+        if !handler.tasks_file.exists() {
+            if let Some(dir) = handler.tasks_file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            File::create(&handler.tasks_file)?;
+        }
+        Ok(handler)
+    }
+
+    /// Creates a `DataHandler` for read-only use, without creating any directories or files.
+    /// The notes directory and tasks file come from `config` when set there.
+    pub fn open_existing(config: &Config) -> Result<Self, std::io::Error> {
         let home_dir = dirs::home_dir().ok_or_else(|| {
             std::io::Error::new(
                 std::io::ErrorKind::NotFound,
@@ -26,49 +109,151 @@ impl DataHandler {
             )
         })?;
         let config_dir = home_dir.join(".config").join("ratanotes");
-        let notes_dir = config_dir.join("notes");
-        let daily_notes_dir = notes_dir.join("daily-notes");
-        let tasks_file = config_dir.join("tasks.json");
-
-        fs::create_dir_all(&daily_notes_dir)?;
-
-        if !tasks_file.exists() {
-            File::create(&tasks_file)?;
-        }
+        let notes_dir = config
+            .notes_dir
+            .as_deref()
+            .map_or_else(|| config_dir.join("notes"), expand_path);
+        let tasks_file = config
+            .tasks_file
+            .as_deref()
+            .map_or_else(|| config_dir.join("tasks.json"), expand_path);
+        let trash_dir = config_dir.join(".trash");
+        let exports_dir = config_dir.join("exports");
+        let templates_dir = config_dir.join("templates");
+        let recent_file = config_dir.join("recent.json");
+        let calendar_file = config_dir.join("calendar.json");
+        let seeded_marker = config_dir.join(".seeded");
 
         Ok(Self {
             notes_dir,
             tasks_file,
+            trash_dir,
+            exports_dir,
+            templates_dir,
+            recent_file,
+            calendar_file,
+            seeded_marker,
+            front_matter_format: config.front_matter_format,
+            in_memory: false,
         })
     }
 
+    /// Creates a `DataHandler` that never writes, for when the data directory can't be set
+    /// up. Whatever already exists is still read; without a home directory nothing is.
+    pub fn in_memory(config: &Config) -> Self {
+        let mut handler = Self::open_existing(config).unwrap_or_else(|_| Self {
+            notes_dir: PathBuf::new(),
+            tasks_file: PathBuf::new(),
+            trash_dir: PathBuf::new(),
+            exports_dir: PathBuf::new(),
+            templates_dir: PathBuf::new(),
+            recent_file: PathBuf::new(),
+            calendar_file: PathBuf::new(),
+            seeded_marker: PathBuf::new(),
+            front_matter_format: config.front_matter_format,
+            in_memory: false,
+        });
+        handler.in_memory = true;
+        handler
+    }
+
+    /// Returns whether changes are kept in memory only.
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    /// Fails when running in memory, so nothing under the data directory is written.
+    fn check_writable(&self) -> Result<(), std::io::Error> {
+        if self.in_memory {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "running in memory, nothing is saved",
+            ));
+        }
+        Ok(())
+    }
+
     /// Loads all notes from the filesystem.
     pub fn load_notes(&self) -> Result<Vec<Note>, std::io::Error> {
         let mut notes = Vec::new();
-        let pattern = self.notes_dir.join("**/*.md");
-        let pattern_str = pattern.to_str().unwrap_or_default();
-
-        for entry in glob(pattern_str).expect("Failed to read glob pattern") {
-            if let Ok(path) = entry {
-                if let Ok(note) = self.parse_note(&path) {
-                    notes.push(note);
-                }
+        if !self.notes_dir.is_dir() {
+            return Ok(notes);
+        }
+        for entry in glob_markdown(&self.notes_dir)? {
+            if let Ok(path) = entry
+                && let Ok(note) = self.parse_note(&path)
+            {
+                notes.push(note);
             }
         }
         Ok(notes)
     }
 
     /// Parses a single note file.
-    fn parse_note(&self, path: &Path) -> Result<Note, std::io::Error> {
-        let mut file = File::open(path)?;
-        let mut full_content = String::new();
-        file.read_to_string(&mut full_content)?;
+    /// Files that aren't valid UTF-8 are decoded lossily and flagged instead of dropped.
+    pub fn parse_note(&self, path: &Path) -> Result<Note, std::io::Error> {
+        let bytes = fs::read(path)?;
+        let (full_content, lossy) = match String::from_utf8(bytes) {
+            Ok(content) => (content, false),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+        };
+        // Edit with `\n` line endings and restore Windows endings on save
+        let crlf = full_content.contains("\r\n");
+        let full_content = if crlf {
+            full_content.replace("\r\n", "\n")
+        } else {
+            full_content
+        };
+
+        let (mut front_matter, content_body, front_matter_format) =
+            self.parse_file_parts(&full_content);
+        let mut timestamp = |key: &str| {
+            front_matter
+                .remove(key)
+                .and_then(|value| {
+                    value
+                        .as_str()
+                        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                })
+                .map(|time| time.with_timezone(&Utc))
+        };
+        let (created, updated) = (timestamp("created"), timestamp("updated"));
 
+        // Fall back to filesystem times for notes saved before timestamps were stored.
+        // Not every platform records a creation time, so that falls back to the mtime.
         let metadata = fs::metadata(path)?;
-        let created_at: DateTime<Utc> = metadata.created()?.into();
-        let updated_at: DateTime<Utc> = metadata.modified()?.into();
+        let modified: Option<DateTime<Utc>> = metadata.modified().ok().map(Into::into);
+        let updated_at = updated.or(modified).unwrap_or_else(Utc::now);
+        let created_at = created
+            .or_else(|| metadata.created().ok().map(Into::into))
+            .unwrap_or(updated_at);
 
-        let (tags, title, content_body) = self.parse_file_parts(&full_content);
+        let tags = front_matter
+            .remove("tags")
+            .and_then(|tags| {
+                tags.as_sequence().map(|s| {
+                    s.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+            })
+            .unwrap_or_default();
+        let title = front_matter
+            .remove("title")
+            .and_then(|title| title.as_str().map(String::from))
+            .unwrap_or_default();
+        let archived = front_matter
+            .remove("archived")
+            .and_then(|archived| archived.as_bool())
+            .unwrap_or(false);
+        let pinned = front_matter
+            .remove("pinned")
+            .and_then(|pinned| pinned.as_bool())
+            .unwrap_or(false);
+        let view = front_matter
+            .remove("ratanotes")
+            .and_then(|view| serde_yaml::from_value(view).ok())
+            .unwrap_or_default();
 
         let final_title = if !title.is_empty() {
             title
@@ -86,38 +271,86 @@ impl DataHandler {
             tags,
             created_at,
             updated_at,
+            dirty: false,
+            lossy,
+            crlf,
+            view,
+            front_matter,
+            front_matter_format,
+            archived,
+            pinned,
         })
     }
 
-    /// Parses the file content into tags, title, and body.
-    /// It reads title and tags from YAML front matter.
-    fn parse_file_parts<'a>(&self, content: &'a str) -> (Vec<String>, String, &'a str) {
-        if content.starts_with("---") {
-            if let Some(end_front_matter) = content.get(3..).and_then(|s| s.find("---")) {
-                let front_matter_str = &content[3..3 + end_front_matter];
-                let body = content[3 + end_front_matter + 3..].trim_start();
-                if let Ok(front_matter) =
-                    serde_yaml::from_str::<serde_yaml::Value>(front_matter_str)
-                {
-                    let tags = front_matter["tags"]
-                        .as_sequence()
-                        .map(|s| {
-                            s.iter()
-                                .filter_map(|v| v.as_str().map(String::from))
-                                .collect()
-                        })
-                        .unwrap_or_default();
-                    let title = front_matter["title"].as_str().unwrap_or("").to_string();
-                    return (tags, title, body);
+    /// Parses the file content into its YAML front matter mapping and body.
+    fn parse_file_parts<'a>(
+        &self,
+        content: &'a str,
+    ) -> (serde_yaml::Mapping, &'a str, FrontMatterFormat) {
+        // Front matter runs from a first line of exactly `---` (YAML) or `+++` (TOML) to
+        // the next such line, so a fence inside a value or as a rule in the body doesn't
+        // end it early.
+        let mut lines = content.split_inclusive('\n');
+        let format = match lines.next().map(str::trim_end) {
+            Some("---") => Some(FrontMatterFormat::Yaml),
+            Some("+++") => Some(FrontMatterFormat::Toml),
+            _ => None,
+        };
+        if let Some(format) = format {
+            let fence = match format {
+                FrontMatterFormat::Yaml => "---",
+                FrontMatterFormat::Toml => "+++",
+            };
+            let is_fence = |line: &str| line.trim_end() == fence;
+            let start = content.find('\n').map_or(content.len(), |i| i + 1);
+            let mut end = start;
+            let mut closing = None;
+            for line in lines {
+                if is_fence(line) {
+                    closing = Some(end + line.len());
+                    break;
+                }
+                end += line.len();
+            }
+            let Some(body_start) = closing else {
+                return (
+                    serde_yaml::Mapping::new(),
+                    content,
+                    self.front_matter_format,
+                );
+            };
+            let front_matter_str = &content[start..end];
+            let body = content[body_start..].trim_start_matches(['\r', '\n']);
+            let front_matter = match format {
+                FrontMatterFormat::Yaml => {
+                    match serde_yaml::from_str::<serde_yaml::Value>(front_matter_str) {
+                        Ok(serde_yaml::Value::Mapping(front_matter)) => Some(front_matter),
+                        Ok(serde_yaml::Value::Null) => Some(serde_yaml::Mapping::new()),
+                        _ => None,
+                    }
                 }
+                FrontMatterFormat::Toml => front_matter_str
+                    .parse::<toml::Table>()
+                    .ok()
+                    .map(toml_to_yaml_mapping),
+            };
+            if let Some(front_matter) = front_matter {
+                return (front_matter, body, format);
             }
         }
         // No valid front matter found, treat the whole file as content
-        (vec![], String::new(), content)
+        (
+            serde_yaml::Mapping::new(),
+            content,
+            self.front_matter_format,
+        )
     }
 
     /// Loads all tasks from the filesystem.
     pub fn load_tasks(&self) -> Result<Vec<Task>, std::io::Error> {
+        if !self.tasks_file.exists() {
+            return Ok(Vec::new());
+        }
         let mut file = File::open(&self.tasks_file)?;
         let mut content = String::new();
         file.read_to_string(&mut content)?;
@@ -132,19 +365,404 @@ impl DataHandler {
 
     /// Saves all tasks to the filesystem.
     pub fn save_tasks(&self, tasks: &[Task]) -> Result<(), std::io::Error> {
-        let mut file = File::create(&self.tasks_file)?;
+        self.check_writable()?;
         let content = serde_json::to_string_pretty(tasks)?;
-        file.write_all(content.as_bytes())?;
-        Ok(())
+        write_atomic(&self.tasks_file, content.as_bytes())
+    }
+
+    /// Loads the paths of recently opened notes, most recent first.
+    pub fn load_recent(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        if !self.recent_file.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.recent_file)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Saves the paths of recently opened notes.
+    pub fn save_recent(&self, recent: &[PathBuf]) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        let content = serde_json::to_string_pretty(recent)?;
+        write_atomic(&self.recent_file, content.as_bytes())
     }
 
-    /// Saves all notes to the filesystem.
-    pub fn save_notes(&self, notes: &[Note]) -> Result<(), std::io::Error> {
+    /// Loads the date last selected in the calendar, if one was saved.
+    pub fn load_calendar_date(&self) -> Result<Option<NaiveDate>, std::io::Error> {
+        if !self.calendar_file.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.calendar_file)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Saves the date selected in the calendar.
+    pub fn save_calendar_date(&self, date: NaiveDate) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        let content = serde_json::to_string(&date)?;
+        write_atomic(&self.calendar_file, content.as_bytes())
+    }
+
+    /// Writes the notes that have unsaved changes, returning how many were written.
+    /// Untouched files keep their modified time, and lossily decoded notes keep their bytes.
+    pub fn save_notes(&self, notes: &[Note]) -> Result<usize, std::io::Error> {
+        self.check_writable()?;
+        let mut written = 0;
+        for note in notes.iter().filter(|note| note.dirty) {
+            write_atomic(&note.path, note_file_contents(note)?.as_bytes())?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Exports the vault to the directory `dest`, creating it if needed, and returns the
+    /// number of notes written. Notes are exported as they are in memory, unsaved edits
+    /// included. The layout mirrors the vault, so it can be copied back or imported:
+    ///
+    /// ```text
+    /// dest/
+    ///   notes/<path within the notes directory>.md   front matter and body, as saved
+    ///   tasks.json                                   same format as the tasks file
+    ///   tasks.md                                     the tasks as a Markdown checklist
+    /// ```
+    pub fn export(
+        &self,
+        notes: &[Note],
+        tasks: &[Task],
+        dest: &Path,
+    ) -> Result<usize, std::io::Error> {
+        let dest = std::path::absolute(dest)?;
+        if dest.starts_with(&self.notes_dir) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the export would be loaded as notes; choose a folder outside the vault",
+            ));
+        }
+
+        let notes_dest = dest.join("notes");
         for note in notes {
-            let mut file = File::create(&note.path)?;
-            let mut full_content = String::new();
+            let relative = note
+                .path
+                .strip_prefix(&self.notes_dir)
+                .ok()
+                .map(Path::to_path_buf)
+                .or_else(|| note.path.file_name().map(PathBuf::from))
+                .unwrap_or_else(|| PathBuf::from("note.md"));
+            let path = notes_dest.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomic(&path, note_file_contents(note)?.as_bytes())?;
+        }
+        fs::create_dir_all(&dest)?;
 
-            // Front matter
+        let json = serde_json::to_string_pretty(tasks)?;
+        write_atomic(&dest.join("tasks.json"), json.as_bytes())?;
+        let mut checklist = String::from("# Tasks\n\n");
+        for task in tasks {
+            push_checklist_item(&mut checklist, task, 0);
+        }
+        write_atomic(&dest.join("tasks.md"), checklist.as_bytes())?;
+        Ok(notes.len())
+    }
+
+    /// Writes the welcome note on first run if the vault is empty.
+    /// Seeding is only ever attempted once; later launches return `None`.
+    pub fn seed_first_run(&self, notes: &[Note]) -> Result<Option<Note>, std::io::Error> {
+        self.check_writable()?;
+        if self.seeded_marker.exists() {
+            return Ok(None);
+        }
+        File::create(&self.seeded_marker)?;
+        if !notes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut note = self.new_note(
+            "Welcome to Ratanotes".to_string(),
+            WELCOME_NOTE.to_string(),
+            vec!["ratanotes".to_string()],
+            notes,
+        );
+        self.save_notes(std::slice::from_ref(&note))?;
+        note.dirty = false;
+        Ok(Some(note))
+    }
+
+    /// Builds a unique path for a new note from its title.
+    /// A counter is appended if the path already exists on disk or belongs to a loaded note.
+    pub fn new_note_path(&self, title: &str, notes: &[Note]) -> PathBuf {
+        let base_name = format!("{}_{}", safe_file_title(title), Utc::now().timestamp());
+        unique_path(&self.notes_dir, &base_name, |path| {
+            path.exists() || notes.iter().any(|note| note.path == path)
+        })
+    }
+
+    /// Renames a note's file to match its current title, keeping the timestamp suffix of
+    /// the old name, and returns the new path. Daily notes keep their date filename, since
+    /// the calendar finds them by it. A note not yet written to disk is only given the new path.
+    pub fn rename_note(&self, note: &Note, notes: &[Note]) -> Result<PathBuf, std::io::Error> {
+        let is_daily = note
+            .path
+            .parent()
+            .is_some_and(|parent| parent == self.notes_dir.join("daily-notes"));
+        let stem = note.path.file_stem().and_then(|s| s.to_str());
+        let Some(stem) = stem.filter(|_| !is_daily) else {
+            return Ok(note.path.clone());
+        };
+
+        // `{title}_{timestamp}` or `{title}_{timestamp}_{counter}`; imported files may have neither
+        let timestamp = stem
+            .rsplit('_')
+            .take(2)
+            .find(|part| part.len() >= 9 && part.chars().all(|c| c.is_ascii_digit()))
+            .map(str::to_string)
+            .unwrap_or_else(|| note.created_at.timestamp().to_string());
+        let base_name = format!("{}_{}", safe_file_title(&note.title), timestamp);
+        if stem == base_name {
+            return Ok(note.path.clone());
+        }
+
+        let dir = note.path.parent().unwrap_or(&self.notes_dir);
+        // The note's own file doesn't count as a collision
+        let path = unique_path(dir, &base_name, |path| {
+            path != note.path && (path.exists() || notes.iter().any(|other| other.path == path))
+        });
+        if path == note.path {
+            return Ok(path);
+        }
+        if note.path.exists() {
+            self.check_writable()?;
+            fs::rename(&note.path, &path)?;
+        }
+        Ok(path)
+    }
+
+    /// Copies a Markdown file from outside the notes directory into it and parses it.
+    pub fn import_note(&self, source: &Path, notes: &[Note]) -> Result<Note, std::io::Error> {
+        self.check_writable()?;
+        if source.file_name().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Not a file path",
+            ));
+        }
+
+        // Only `.md` files are loaded, so a `.txt` or `.markdown` source is renamed
+        let stem = source
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("imported");
+        let mut destination = self.notes_dir.join(format!("{}.md", stem));
+        let mut counter = 1;
+        while destination.exists() || notes.iter().any(|note| note.path == destination) {
+            destination = self.notes_dir.join(format!("{}_{}.md", stem, counter));
+            counter += 1;
+        }
+
+        fs::copy(source, &destination)?;
+        self.parse_note(&destination)
+    }
+
+    /// Returns the path of the daily note for `date`, e.g. `daily-notes/2024-01-31.md`.
+    pub fn daily_note_path(&self, date: NaiveDate) -> PathBuf {
+        self.notes_dir
+            .join("daily-notes")
+            .join(format!("{}.md", date.format("%Y-%m-%d")))
+    }
+
+    /// Builds a new, unsaved daily note for `date` from the `daily` template, or starting
+    /// with the date as its heading when there is no such template.
+    pub fn new_daily_note(&self, date: NaiveDate) -> Note {
+        let title = date.format("%Y-%m-%d").to_string();
+        let content = match self.load_template("daily") {
+            Ok(template) => templates::expand(&template, &title, date, Local::now().time()),
+            Err(_) => format!("# {}\n\n", title),
+        };
+        let mut note = self.new_note(title, content, vec![], &[]);
+        note.path = self.daily_note_path(date);
+        note
+    }
+
+    /// Builds a new, unsaved note with a unique path derived from its title.
+    pub fn new_note(
+        &self,
+        title: String,
+        content: String,
+        tags: Vec<String>,
+        notes: &[Note],
+    ) -> Note {
+        Note {
+            path: self.new_note_path(&title, notes),
+            title,
+            content,
+            tags,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            dirty: true,
+            lossy: false,
+            crlf: false,
+            view: NoteView::default(),
+            front_matter: serde_yaml::Mapping::new(),
+            front_matter_format: self.front_matter_format,
+            archived: false,
+            pinned: false,
+        }
+    }
+
+    /// Moves a note file into the trash directory and returns its new path.
+    /// Trashed files keep their location relative to the notes directory and gain a
+    /// deletion timestamp, e.g. `.trash/daily-notes/01-02-2024.1706745600.md`.
+    pub fn delete_note(&self, note: &Note) -> Result<PathBuf, std::io::Error> {
+        self.check_writable()?;
+        let relative = note.path.strip_prefix(&self.notes_dir).unwrap_or_else(|_| {
+            note.path
+                .file_name()
+                .map(Path::new)
+                .unwrap_or(note.path.as_path())
+        });
+        let stem = relative
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled");
+        let trash_parent = match relative.parent() {
+            Some(parent) => self.trash_dir.join(parent),
+            None => self.trash_dir.clone(),
+        };
+        fs::create_dir_all(&trash_parent)?;
+
+        let trash_path = trash_parent.join(format!("{}.{}.md", stem, Utc::now().timestamp()));
+        move_file(&note.path, &trash_path)?;
+        Ok(trash_path)
+    }
+
+    /// Loads all notes in the trash directory, most recently deleted first.
+    pub fn load_trash(&self) -> Result<Vec<TrashedNote>, std::io::Error> {
+        let mut trash = Vec::new();
+        for path in glob_markdown(&self.trash_dir)?.flatten() {
+            let Some((original_path, deleted_at)) = self.parse_trash_path(&path) else {
+                continue;
+            };
+            let title = match self.parse_note(&path) {
+                Ok(note) => note.title,
+                Err(_) => continue,
+            };
+            trash.push(TrashedNote {
+                trash_path: path,
+                original_path,
+                title,
+                deleted_at,
+            });
+        }
+        trash.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted_at));
+        Ok(trash)
+    }
+
+    /// Recovers the original path and deletion time encoded in a trashed file's path.
+    fn parse_trash_path(&self, trash_path: &Path) -> Option<(PathBuf, DateTime<Utc>)> {
+        let relative = trash_path.strip_prefix(&self.trash_dir).ok()?;
+        let stem = relative.file_stem()?.to_str()?;
+        let (original_stem, timestamp) = stem.rsplit_once('.')?;
+        let deleted_at = DateTime::from_timestamp(timestamp.parse().ok()?, 0)?;
+
+        let original_name = format!("{}.md", original_stem);
+        let original_path = match relative.parent() {
+            Some(parent) => self.notes_dir.join(parent).join(original_name),
+            None => self.notes_dir.join(original_name),
+        };
+        Some((original_path, deleted_at))
+    }
+
+    /// Moves a trashed note back into the notes directory and loads it.
+    /// A counter is appended if its original path has since been taken.
+    pub fn restore_note(
+        &self,
+        trash_path: &Path,
+        original_path: &Path,
+        notes: &[Note],
+    ) -> Result<Note, std::io::Error> {
+        self.check_writable()?;
+        let stem = original_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled");
+        let parent = original_path.parent().unwrap_or(&self.notes_dir);
+        fs::create_dir_all(parent)?;
+
+        let mut destination = original_path.to_path_buf();
+        let mut counter = 1;
+        while destination.exists() || notes.iter().any(|note| note.path == destination) {
+            destination = parent.join(format!("{}_{}.md", stem, counter));
+            counter += 1;
+        }
+
+        move_file(trash_path, &destination)?;
+        self.parse_note(&destination)
+    }
+
+    /// Permanently deletes a note from the trash directory.
+    pub fn purge_note(&self, trashed: &TrashedNote) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        fs::remove_file(&trashed.trash_path)
+    }
+
+    /// Reads the template `name` from the templates directory; the `.md` extension is optional.
+    pub fn load_template(&self, name: &str) -> Result<String, std::io::Error> {
+        let file_name = if name.ends_with(".md") {
+            name.to_string()
+        } else {
+            format!("{}.md", name)
+        };
+        fs::read_to_string(self.templates_dir.join(file_name))
+    }
+
+    /// Returns the names of the available templates, without their extension, sorted.
+    pub fn template_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.templates_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "md").then_some(())?;
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Renders a note to HTML in the exports directory and returns the written path.
+    pub fn export_html(
+        &self,
+        note: &Note,
+        flavor: MarkdownFlavor,
+    ) -> Result<PathBuf, std::io::Error> {
+        self.check_writable()?;
+        fs::create_dir_all(&self.exports_dir)?;
+        let stem = note
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("note");
+        let path = self.exports_dir.join(format!("{}.html", stem));
+        fs::write(&path, note_to_html(note, flavor))?;
+        Ok(path)
+    }
+}
+
+/// Returns the file contents for a note: front matter in the note's format followed by the body.
+fn note_file_contents(note: &Note) -> Result<String, std::io::Error> {
+    let mut full_content = String::new();
+    let created = note.created_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let updated = note.updated_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut extra = note.front_matter.clone();
+    if note.view != NoteView::default() {
+        let view = serde_yaml::to_value(&note.view).map_err(std::io::Error::other)?;
+        extra.insert("ratanotes".into(), view);
+    }
+
+    // Front matter
+    match note.front_matter_format {
+        FrontMatterFormat::Yaml => {
             full_content.push_str("---\n");
             full_content.push_str(&format!("title: {}\n", note.title));
             if !note.tags.is_empty() {
@@ -153,18 +771,346 @@ impl DataHandler {
                     full_content.push_str(&format!("  - {}\n", tag));
                 }
             }
+            full_content.push_str(&format!("created: {}\nupdated: {}\n", created, updated));
+            if note.pinned {
+                full_content.push_str("pinned: true\n");
+            }
+            if note.archived {
+                full_content.push_str("archived: true\n");
+            }
+            if !extra.is_empty() {
+                let extra = serde_yaml::to_string(&extra).map_err(std::io::Error::other)?;
+                full_content.push_str(&extra);
+            }
             full_content.push_str("---\n\n");
+        }
+        FrontMatterFormat::Toml => {
+            full_content.push_str("+++\n");
+            full_content.push_str(&format!(
+                "title = {}\n",
+                toml::Value::String(note.title.clone())
+            ));
+            if !note.tags.is_empty() {
+                let tags = note.tags.iter().cloned().map(toml::Value::String).collect();
+                full_content.push_str(&format!("tags = {}\n", toml::Value::Array(tags)));
+            }
+            // Unquoted, so Hugo and Zola read them as dates
+            full_content.push_str(&format!("created = {}\nupdated = {}\n", created, updated));
+            if note.pinned {
+                full_content.push_str("pinned = true\n");
+            }
+            if note.archived {
+                full_content.push_str("archived = true\n");
+            }
+            if !extra.is_empty() {
+                let extra = toml::to_string(&extra).map_err(std::io::Error::other)?;
+                full_content.push_str(&extra);
+            }
+            full_content.push_str("+++\n\n");
+        }
+    }
 
-            // Content
-            full_content.push_str(&note.content);
+    // Content
+    full_content.push_str(&note.content);
+    if note.crlf {
+        full_content = full_content.replace('\n', "\r\n");
+    }
+    Ok(full_content)
+}
 
-            file.write_all(full_content.as_bytes())?;
+/// Converts parsed TOML front matter to the YAML mapping notes keep their extra keys in.
+/// Dates and times become RFC 3339 strings, as Ratanotes writes them in YAML.
+fn toml_to_yaml_mapping(table: toml::Table) -> serde_yaml::Mapping {
+    fn convert(value: toml::Value) -> serde_yaml::Value {
+        match value {
+            toml::Value::String(s) => serde_yaml::Value::String(s),
+            toml::Value::Integer(i) => i.into(),
+            toml::Value::Float(f) => f.into(),
+            toml::Value::Boolean(b) => b.into(),
+            toml::Value::Datetime(datetime) => serde_yaml::Value::String(datetime.to_string()),
+            toml::Value::Array(array) => {
+                serde_yaml::Value::Sequence(array.into_iter().map(convert).collect())
+            }
+            toml::Value::Table(table) => serde_yaml::Value::Mapping(toml_to_yaml_mapping(table)),
         }
-        Ok(())
     }
+    table
+        .into_iter()
+        .map(|(key, value)| (serde_yaml::Value::String(key), convert(value)))
+        .collect()
+}
+
+/// Reduces a title to the characters safe in a filename, with spaces as underscores.
+fn safe_file_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ')
+        .collect::<String>()
+        .replace(' ', "_")
+}
+
+/// Returns `{dir}/{base_name}.md`, or the first `{base_name}_{n}.md` that isn't taken.
+fn unique_path(dir: &Path, base_name: &str, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+    let mut path = dir.join(format!("{}.md", base_name));
+    let mut counter = 1;
+    while is_taken(&path) {
+        path = dir.join(format!("{}_{}.md", base_name, counter));
+        counter += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Returns a handler whose notes, tasks and other files all live in a fresh temporary
+    /// directory, which is removed when the returned guard is dropped.
+    fn temp_handler() -> (TempDir, DataHandler) {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            notes_dir: Some(dir.path().join("notes").to_string_lossy().into_owned()),
+            tasks_file: Some(dir.path().join("tasks.json").to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let mut handler = DataHandler::open_existing(&config).unwrap();
+        handler.trash_dir = dir.path().join(".trash");
+        handler.exports_dir = dir.path().join("exports");
+        handler.templates_dir = dir.path().join("templates");
+        handler.recent_file = dir.path().join("recent.json");
+        handler.calendar_file = dir.path().join("calendar.json");
+        handler.seeded_marker = dir.path().join(".seeded");
+        fs::create_dir_all(&handler.notes_dir).unwrap();
+        (dir, handler)
+    }
+
+    #[test]
+    fn notes_save_and_load_in_the_configured_directory() {
+        let (dir, handler) = temp_handler();
+        assert_eq!(handler.notes_dir, dir.path().join("notes"));
+
+        let note = handler.new_note("Hello".into(), "Body\n".into(), vec![], &[]);
+        assert!(note.path.starts_with(dir.path().join("notes")));
+        assert_eq!(handler.save_notes(std::slice::from_ref(&note)).unwrap(), 1);
+
+        let loaded = handler.load_notes().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].path, note.path);
+        assert_eq!(loaded[0].title, "Hello");
+        assert_eq!(loaded[0].content, "Body\n");
+    }
+
+    #[test]
+    fn failed_temp_write_leaves_the_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        fs::write(&path, "original").unwrap();
+        // A directory in the temp file's place makes creating it fail
+        fs::create_dir(dir.path().join("tasks.json.tmp")).unwrap();
+
+        assert!(write_atomic(&path, b"replacement").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_file_and_removes_the_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        fs::write(&path, "original").unwrap();
+
+        write_atomic(&path, b"replacement").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "replacement");
+        assert!(!dir.path().join("note.md.tmp").exists());
+    }
+
+    #[test]
+    fn toml_front_matter_round_trips() {
+        let (_dir, handler) = temp_handler();
+        let path = handler.notes_dir.join("hugo.md");
+        fs::write(
+            &path,
+            "+++\n\
+             title = \"Hugo post\"\n\
+             tags = [\"rust\", \"notes\"]\n\
+             created = 2024-01-02T03:04:05Z\n\
+             updated = 2024-02-03T04:05:06Z\n\
+             draft = true\n\
+             +++\n\
+             \n\
+             Body\n",
+        )
+        .unwrap();
+
+        let note = handler.parse_note(&path).unwrap();
+        assert_eq!(note.front_matter_format, FrontMatterFormat::Toml);
+        assert_eq!(note.title, "Hugo post");
+        assert_eq!(note.tags, ["rust", "notes"]);
+        assert_eq!(note.content, "Body\n");
+        assert_eq!(note.created_at.to_rfc3339(), "2024-01-02T03:04:05+00:00");
+        assert_eq!(note.updated_at.to_rfc3339(), "2024-02-03T04:05:06+00:00");
+        assert_eq!(note.front_matter.get("draft"), Some(&true.into()));
+
+        let written = note_file_contents(&note).unwrap();
+        assert!(written.starts_with("+++\n"));
+        let copy = handler.notes_dir.join("copy.md");
+        fs::write(&copy, written).unwrap();
+        let reparsed = handler.parse_note(&copy).unwrap();
+        assert_eq!(reparsed.front_matter_format, FrontMatterFormat::Toml);
+        assert_eq!(reparsed.title, note.title);
+        assert_eq!(reparsed.tags, note.tags);
+        assert_eq!(reparsed.content, note.content);
+        assert_eq!(reparsed.created_at, note.created_at);
+        assert_eq!(reparsed.updated_at, note.updated_at);
+        assert_eq!(reparsed.front_matter, note.front_matter);
+    }
+
+    #[test]
+    fn non_utf8_notes_load_lossily() {
+        let (_dir, handler) = temp_handler();
+        fs::write(handler.notes_dir.join("latin1.md"), b"caf\xe9 au lait").unwrap();
+        fs::write(handler.notes_dir.join("plain.md"), "fine").unwrap();
+
+        let notes = handler.load_notes().unwrap();
+        assert_eq!(notes.len(), 2);
+        let latin1 = notes.iter().find(|note| note.title == "latin1").unwrap();
+        assert!(latin1.lossy);
+        assert_eq!(latin1.content, "caf\u{fffd} au lait");
+        assert!(
+            notes
+                .iter()
+                .any(|note| note.title == "plain" && !note.lossy)
+        );
+    }
+
+    #[test]
+    fn crlf_notes_edit_with_lf_and_save_with_crlf() {
+        let (_dir, handler) = temp_handler();
+        let path = handler.notes_dir.join("windows.md");
+        fs::write(
+            &path,
+            "---\r\ntitle: Windows\r\n---\r\n\r\nfirst\r\nsecond\r\n",
+        )
+        .unwrap();
 
-    /// Deletes a note file from the filesystem.
-    pub fn delete_note(&self, note: &Note) -> Result<(), std::io::Error> {
-        fs::remove_file(&note.path)
+        let mut note = handler.parse_note(&path).unwrap();
+        assert!(note.crlf);
+        assert_eq!(note.title, "Windows");
+        assert_eq!(note.content, "first\nsecond\n");
+        // Cursor positions count no stray `\r` characters
+        assert_eq!(
+            crate::utils::text::position_at(&note.content, 6, None),
+            (0, 1)
+        );
+        assert_eq!(crate::utils::text::offset_at(&note.content, 6, 1, None), 12);
+
+        note.content.push_str("third\n");
+        note.dirty = true;
+        handler.save_notes(std::slice::from_ref(&note)).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.ends_with("first\r\nsecond\r\nthird\r\n"));
+        assert!(!saved.replace("\r\n", "").contains('\n'));
+
+        let reloaded = handler.parse_note(&path).unwrap();
+        assert!(reloaded.crlf);
+        assert_eq!(reloaded.content, note.content);
+    }
+
+    #[test]
+    fn horizontal_rules_in_the_body_do_not_end_front_matter() {
+        let (_dir, handler) = temp_handler();
+        let content = "---\ntitle: Rules\n---\n\nAbove\n\n---\n\nBelow\n";
+        let (front_matter, body, format) = handler.parse_file_parts(content);
+        assert_eq!(format, FrontMatterFormat::Yaml);
+        assert_eq!(front_matter.get("title"), Some(&"Rules".into()));
+        assert_eq!(body, "Above\n\n---\n\nBelow\n");
+    }
+
+    #[test]
+    fn dashes_inside_a_value_do_not_end_front_matter() {
+        let (_dir, handler) = temp_handler();
+        let content = "---\ntitle: \"a --- b\"\n---\nBody";
+        let (front_matter, body, _) = handler.parse_file_parts(content);
+        assert_eq!(front_matter.get("title"), Some(&"a --- b".into()));
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn empty_front_matter_leaves_the_body() {
+        let (_dir, handler) = temp_handler();
+        let (front_matter, body, format) = handler.parse_file_parts("---\n---\nBody\n");
+        assert!(front_matter.is_empty());
+        assert_eq!(format, FrontMatterFormat::Yaml);
+        assert_eq!(body, "Body\n");
+    }
+
+    #[test]
+    fn unclosed_front_matter_is_body() {
+        let (_dir, handler) = temp_handler();
+        let content = "---\ntitle: Open\nBody\n";
+        let (front_matter, body, _) = handler.parse_file_parts(content);
+        assert!(front_matter.is_empty());
+        assert_eq!(body, content);
+    }
+
+    #[test]
+    fn multi_line_tag_lists_are_read() {
+        let (_dir, handler) = temp_handler();
+        let path = handler.notes_dir.join("tagged.md");
+        fs::write(
+            &path,
+            "---\ntitle: Tagged\ntags:\n  - rust\n  - notes\n---\n\nBody\n",
+        )
+        .unwrap();
+        let note = handler.parse_note(&path).unwrap();
+        assert_eq!(note.tags, ["rust", "notes"]);
+        assert_eq!(note.content, "Body\n");
+    }
+
+    #[test]
+    fn deleting_moves_the_note_to_the_trash() {
+        let (_dir, handler) = temp_handler();
+        let note = handler.new_note("Doomed".into(), "Bye\n".into(), vec![], &[]);
+        handler.save_notes(std::slice::from_ref(&note)).unwrap();
+
+        let trash_path = handler.delete_note(&note).unwrap();
+        assert!(!note.path.exists());
+        assert!(trash_path.exists());
+        assert!(trash_path.starts_with(&handler.trash_dir));
+
+        let trash = handler.load_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].title, "Doomed");
+        assert_eq!(trash[0].original_path, note.path);
+
+        let restored = handler
+            .restore_note(&trash[0].trash_path, &trash[0].original_path, &[])
+            .unwrap();
+        assert_eq!(restored.path, note.path);
+        assert!(note.path.exists());
+        assert!(!trash_path.exists());
+    }
+
+    #[test]
+    fn notes_with_the_same_file_name_get_distinct_paths() {
+        let (_dir, handler) = temp_handler();
+        let first = handler.new_note("Same title!".into(), "One".into(), vec![], &[]);
+        handler.save_notes(std::slice::from_ref(&first)).unwrap();
+        let second = handler.new_note("Same title?".into(), "Two".into(), vec![], &[]);
+        handler.save_notes(std::slice::from_ref(&second)).unwrap();
+
+        assert_ne!(first.path, second.path);
+        assert_eq!(handler.load_notes().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn glob_characters_in_the_notes_directory_are_literal() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["Notes [work]", "Notes [unclosed"] {
+            let notes_dir = dir.path().join(name);
+            fs::create_dir_all(&notes_dir).unwrap();
+            fs::write(notes_dir.join("a.md"), "Hello").unwrap();
+            assert_eq!(glob_markdown(&notes_dir).unwrap().flatten().count(), 1);
+        }
     }
 }
@@ -0,0 +1,98 @@
+// Ratanotes/src/utils/dates.rs
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::path::Path;
+
+/// Format of the dates typed into and shown throughout the UI: ISO `YYYY-MM-DD`.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Agenda groups for tasks, in the order they are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DueGroup {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl DueGroup {
+    /// Returns the human-readable header for the group.
+    pub fn label(self) -> &'static str {
+        match self {
+            DueGroup::Overdue => "Overdue",
+            DueGroup::Today => "Today",
+            DueGroup::Tomorrow => "Tomorrow",
+            DueGroup::ThisWeek => "This Week",
+            DueGroup::Later => "Later",
+            DueGroup::NoDate => "No Date",
+        }
+    }
+}
+
+/// Returns the date a daily note belongs to, from its file name. Daily notes are named
+/// `YYYY-MM-DD`; older ones used `DD-MM-YYYY`. Other notes return `None`.
+pub fn daily_note_date(path: &Path) -> Option<NaiveDate> {
+    let stem = path.file_stem()?.to_str()?;
+    NaiveDate::parse_from_str(stem, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(stem, "%d-%m-%Y"))
+        .ok()
+}
+
+/// Returns how many days `date` is past the start of its week, for weeks starting on
+/// `first_day`.
+pub fn days_into_week(date: NaiveDate, first_day: Weekday) -> u32 {
+    (date.weekday().num_days_from_monday() + 7 - first_day.num_days_from_monday()) % 7
+}
+
+/// Places a due date into an agenda group relative to `today`.
+/// "This Week" runs until the last day of a week starting on `first_day`.
+pub fn due_group(due: Option<NaiveDate>, today: NaiveDate, first_day: Weekday) -> DueGroup {
+    let Some(due) = due else {
+        return DueGroup::NoDate;
+    };
+    let end_of_week = today + Duration::days(6 - days_into_week(today, first_day) as i64);
+    if due < today {
+        DueGroup::Overdue
+    } else if due == today {
+        DueGroup::Today
+    } else if due == today + Duration::days(1) {
+        DueGroup::Tomorrow
+    } else if due <= end_of_week {
+        DueGroup::ThisWeek
+    } else {
+        DueGroup::Later
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, 6, day)
+    }
+
+    #[test]
+    fn groups_relative_to_today() {
+        // Wednesday 2024-06-12
+        let today = date(12).unwrap();
+        assert_eq!(due_group(date(11), today, Weekday::Mon), DueGroup::Overdue);
+        assert_eq!(due_group(date(12), today, Weekday::Mon), DueGroup::Today);
+        assert_eq!(due_group(date(13), today, Weekday::Mon), DueGroup::Tomorrow);
+        assert_eq!(due_group(None, today, Weekday::Mon), DueGroup::NoDate);
+    }
+
+    #[test]
+    fn this_week_ends_before_the_configured_first_day() {
+        let today = date(12).unwrap();
+        // Weeks starting on Monday end on Sunday the 16th
+        assert_eq!(due_group(date(15), today, Weekday::Mon), DueGroup::ThisWeek);
+        assert_eq!(due_group(date(16), today, Weekday::Mon), DueGroup::ThisWeek);
+        assert_eq!(due_group(date(17), today, Weekday::Mon), DueGroup::Later);
+        // Weeks starting on Sunday end on Saturday the 15th
+        assert_eq!(due_group(date(15), today, Weekday::Sun), DueGroup::ThisWeek);
+        assert_eq!(due_group(date(16), today, Weekday::Sun), DueGroup::Later);
+    }
+}
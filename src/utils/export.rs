@@ -0,0 +1,47 @@
+// Ratanotes/src/utils/export.rs
+
+use crate::app::state::Note;
+use crate::utils::config::MarkdownFlavor;
+use pulldown_cmark::{Options, Parser, html};
+
+/// Returns the `pulldown-cmark` extensions enabled for a markdown flavor.
+pub fn markdown_options(flavor: MarkdownFlavor) -> Options {
+    match flavor {
+        MarkdownFlavor::Gfm => {
+            Options::ENABLE_TABLES
+                | Options::ENABLE_STRIKETHROUGH
+                | Options::ENABLE_TASKLISTS
+                | Options::ENABLE_FOOTNOTES
+                | Options::ENABLE_GFM
+        }
+        MarkdownFlavor::CommonMark => Options::empty(),
+    }
+}
+
+/// Escapes the characters that are special in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a note as a standalone HTML document.
+pub fn note_to_html(note: &Note, flavor: MarkdownFlavor) -> String {
+    let parser = Parser::new_ext(&note.content, markdown_options(flavor));
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&note.title),
+        body
+    )
+}
@@ -0,0 +1,428 @@
+// Ratanotes/src/utils/highlight.rs
+
+use crate::utils::theme::Theme;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Span;
+
+/// What the highlighter needs to know about a language. Each line is highlighted on its
+/// own, so strings and comments spanning several lines are not recognized.
+struct Syntax {
+    keywords: &'static [&'static str],
+    line_comment: &'static [&'static str],
+    quotes: &'static [char],
+    /// Whether keywords match in any case, as in SQL.
+    ignore_case: bool,
+}
+
+const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+        "type", "unsafe", "use", "where", "while",
+    ],
+    line_comment: &["//"],
+    quotes: &['"'],
+    ignore_case: false,
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True",
+        "try", "while", "with", "yield",
+    ],
+    line_comment: &["#"],
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "return",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "while",
+        "yield",
+    ],
+    line_comment: &["//"],
+    quotes: &['"', '\'', '`'],
+    ignore_case: false,
+};
+
+const GO: Syntax = Syntax {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "for",
+        "func",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+    line_comment: &["//"],
+    quotes: &['"', '`'],
+    ignore_case: false,
+};
+
+const C_LIKE: Syntax = Syntax {
+    keywords: &[
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "catch",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "false",
+        "final",
+        "float",
+        "for",
+        "if",
+        "import",
+        "int",
+        "long",
+        "namespace",
+        "new",
+        "null",
+        "nullptr",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typedef",
+        "unsigned",
+        "using",
+        "void",
+        "while",
+    ],
+    line_comment: &["//"],
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+const SHELL: Syntax = Syntax {
+    keywords: &[
+        "case", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+        "function", "if", "in", "local", "return", "then", "until", "while",
+    ],
+    line_comment: &["#"],
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+const SQL: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "by", "create", "delete", "from", "group", "insert", "into", "join", "left",
+        "limit", "not", "null", "on", "or", "order", "select", "set", "table", "update", "values",
+        "where",
+    ],
+    line_comment: &["--"],
+    quotes: &['\''],
+    ignore_case: true,
+};
+
+/// Looks up a language by the name or alias written after a code fence.
+fn syntax(language: &str) -> Option<&'static Syntax> {
+    let syntax = match language.to_lowercase().as_str() {
+        "rust" | "rs" => &RUST,
+        "python" | "py" => &PYTHON,
+        "javascript" | "js" | "typescript" | "ts" | "jsx" | "tsx" => &JAVASCRIPT,
+        "go" | "golang" => &GO,
+        "c" | "h" | "cpp" | "c++" | "java" | "cs" | "csharp" => &C_LIKE,
+        "sh" | "bash" | "shell" | "zsh" => &SHELL,
+        "sql" => &SQL,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Splits one line of code into styled spans, coloring keywords, strings, numbers and
+/// comments for the fence's `language` with the `theme`'s colors. Unknown languages get
+/// `base` for the whole line, which is also the style of everything not highlighted.
+pub fn highlight_line(
+    line: &str,
+    language: &str,
+    base: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let Some(syntax) = syntax(language) else {
+        return vec![Span::styled(line.to_string(), base)];
+    };
+    let keyword = Style::default().fg(theme.key).add_modifier(Modifier::BOLD);
+    let string = Style::default().fg(theme.tag);
+    let number = Style::default().fg(theme.link);
+    let comment = Style::default()
+        .fg(theme.muted)
+        .add_modifier(Modifier::ITALIC);
+
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let (len, style) = if syntax.line_comment.iter().any(|m| rest.starts_with(m)) {
+            (rest.len(), comment)
+        } else if syntax.quotes.contains(&c) {
+            (string_len(rest, c), string)
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            (len, number)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            let is_keyword = syntax.keywords.iter().any(|k| {
+                if syntax.ignore_case {
+                    k.eq_ignore_ascii_case(word)
+                } else {
+                    *k == word
+                }
+            });
+            if !is_keyword {
+                plain.push_str(word);
+                rest = &rest[len..];
+                continue;
+            }
+            (len, keyword)
+        } else {
+            plain.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        if !plain.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut plain), base));
+        }
+        spans.push(Span::styled(rest[..len].to_string(), style));
+        rest = &rest[len..];
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+/// Byte length of the string literal at the start of `text`, opened by `quote`, up to
+/// and including its closing quote or the end of the line. Backslashes escape quotes.
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(line: &str, language: &str) -> Vec<(String, Style)> {
+        highlight_line(line, language, Style::default(), &Theme::default())
+            .into_iter()
+            .map(|span| (span.content.into_owned(), span.style))
+            .collect()
+    }
+
+    fn keyword() -> Style {
+        Style::default()
+            .fg(Theme::default().key)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    fn string() -> Style {
+        Style::default().fg(Theme::default().tag)
+    }
+
+    fn number() -> Style {
+        Style::default().fg(Theme::default().link)
+    }
+
+    fn comment() -> Style {
+        Style::default()
+            .fg(Theme::default().muted)
+            .add_modifier(Modifier::ITALIC)
+    }
+
+    fn plain(text: &str) -> (String, Style) {
+        (text.to_string(), Style::default())
+    }
+
+    #[test]
+    fn highlights_keywords_and_numbers() {
+        assert_eq!(
+            highlight("let x = 42;", "rust"),
+            vec![
+                ("let".to_string(), keyword()),
+                plain(" x = "),
+                ("42".to_string(), number()),
+                plain(";"),
+            ]
+        );
+        // Keywords are case-sensitive outside SQL
+        assert_eq!(highlight("Let", "rs"), vec![plain("Let")]);
+    }
+
+    #[test]
+    fn sql_keywords_match_in_any_case() {
+        assert_eq!(
+            highlight("SELECT name From t", "sql"),
+            vec![
+                ("SELECT".to_string(), keyword()),
+                plain(" name "),
+                ("From".to_string(), keyword()),
+                plain(" t"),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_quotes_stay_inside_the_string() {
+        assert_eq!(
+            highlight(r#"s = "a \"b\" c";"#, "python"),
+            vec![
+                plain("s = "),
+                (r#""a \"b\" c""#.to_string(), string()),
+                plain(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_strings_run_to_the_end_of_the_line() {
+        assert_eq!(
+            highlight("x = 'abc", "py"),
+            vec![plain("x = "), ("'abc".to_string(), string())]
+        );
+    }
+
+    #[test]
+    fn comments_after_code_run_to_the_end_of_the_line() {
+        assert_eq!(
+            highlight("x += 1; // \"done\"", "rust"),
+            vec![
+                plain("x += "),
+                ("1".to_string(), number()),
+                plain("; "),
+                ("// \"done\"".to_string(), comment()),
+            ]
+        );
+        assert_eq!(
+            highlight("echo hi # bye", "sh"),
+            vec![
+                ("echo".to_string(), keyword()),
+                plain(" hi "),
+                ("# bye".to_string(), comment()),
+            ]
+        );
+    }
+
+    #[test]
+    fn digits_inside_identifiers_are_not_numbers() {
+        assert_eq!(highlight("x1 = y_2", "go"), vec![plain("x1 = y_2")]);
+    }
+
+    #[test]
+    fn unknown_languages_use_the_base_style() {
+        let base = Style::default().fg(Theme::default().header);
+        let spans = highlight_line("let x = 1", "cobol", base, &Theme::default());
+        assert_eq!(spans, vec![Span::styled("let x = 1", base)]);
+    }
+
+    #[test]
+    fn non_ascii_text_is_kept_whole() {
+        let lines = [
+            "let café = \"naïve ✓\"; // 日本語",
+            "ß = '€",
+            "ñ1 = 2 # ü",
+            "\"\\é",
+        ];
+        for line in lines {
+            for language in ["rust", "python", "sql"] {
+                let text: String = highlight(line, language)
+                    .into_iter()
+                    .map(|(text, _)| text)
+                    .collect();
+                assert_eq!(text, line);
+            }
+        }
+        assert_eq!(highlight("let café = 1", "rust")[1], plain(" café = "));
+    }
+}
@@ -0,0 +1,64 @@
+// Ratanotes/src/utils/hooks.rs
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc::Sender,
+    thread,
+};
+
+/// Runs a shell command on a background thread with `cwd` as its working directory.
+/// A failure is reported back through `sender` as a status message.
+pub fn spawn_shell_command(command: String, cwd: PathBuf, sender: Sender<String>) {
+    thread::spawn(move || {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+
+        let result = shell
+            .arg(&command)
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        let failure = match result {
+            Ok(status) if status.success() => return,
+            Ok(status) => match status.code() {
+                Some(code) => format!("On-save command exited with status {}", code),
+                None => "On-save command was terminated by a signal".to_string(),
+            },
+            Err(e) => format!("Error running on-save command: {}", e),
+        };
+        // The receiver is gone if the app has already quit
+        let _ = sender.send(failure);
+    });
+}
+
+/// Returns the user's editor command from `$VISUAL` or `$EDITOR`, falling back to `vi`
+/// (`notepad` on Windows) when neither is set.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+/// Runs an editor command on `path` in the foreground and waits for it to exit. The
+/// command may carry its own arguments, as in `code --wait`.
+pub fn run_editor(command: &str, path: &Path) -> io::Result<ExitStatus> {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "no editor set"));
+    };
+    Command::new(program).args(words).arg(path).status()
+}
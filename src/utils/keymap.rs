@@ -0,0 +1,77 @@
+// Ratanotes/src/utils/keymap.rs
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key with its modifiers, parsed from a config entry such as `"ctrl-n"` or `"Down"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    /// Parses a key specification: optional `ctrl-`, `alt-` and `shift-` prefixes followed
+    /// by a single character or a key name like `Enter`, `Space`, `PageDown` or `F2`.
+    /// Names and prefixes are case-insensitive; single characters are not, so `G` is
+    /// distinct from `g`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec.trim();
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, prefix_len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            // A lone `-` after a prefix is the minus key, not another prefix
+            if rest.len() == prefix_len {
+                break;
+            }
+            modifiers |= modifier;
+            rest = &rest[prefix_len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (None, _) => return Err("empty key".to_string()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", rest)),
+                },
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+
+    /// Returns whether a key press matches this key. For characters, Shift is already
+    /// reflected in the character itself, so it is ignored.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let ignored = if matches!(self.code, KeyCode::Char(_)) {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        key.code == self.code && key.modifiers - ignored == self.modifiers - ignored
+    }
+}
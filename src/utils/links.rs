@@ -0,0 +1,95 @@
+// Ratanotes/src/utils/links.rs
+
+use crate::app::state::Note;
+use std::ops::Range;
+
+/// Finds the `[[wiki-links]]` in a note body. Returns each link's byte range, brackets
+/// included, with its target. For `[[Title|label]]` the target is the title.
+pub fn wiki_link_ranges(content: &str) -> Vec<(Range<usize>, String)> {
+    let mut links = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = content[offset..].find("[[") {
+        let inner_start = offset + start + 2;
+        offset = inner_start;
+        let Some(end) = content[inner_start..].find("]]") else {
+            break;
+        };
+        let inner = &content[inner_start..inner_start + end];
+        // A link never spans lines
+        if !inner.contains('\n') {
+            let target = inner.split('|').next().unwrap_or_default().trim();
+            if !target.is_empty() {
+                links.push((inner_start - 2..inner_start + end + 2, target.to_string()));
+            }
+            offset = inner_start + end + 2;
+        }
+    }
+    links
+}
+
+/// Collects the targets of `[[wiki-links]]` in a note body.
+/// For `[[Title|label]]` only the title is returned.
+pub fn parse_wiki_links(content: &str) -> Vec<String> {
+    wiki_link_ranges(content)
+        .into_iter()
+        .map(|(_, target)| target)
+        .collect()
+}
+
+/// Returns the indices of the notes that link to the note at `target`, in order.
+pub fn linking_notes(notes: &[Note], target: usize) -> Vec<usize> {
+    let Some(title) = notes.get(target).map(|note| note.title.to_lowercase()) else {
+        return Vec::new();
+    };
+    notes
+        .iter()
+        .enumerate()
+        .filter(|&(source, note)| {
+            source != target
+                && parse_wiki_links(&note.content)
+                    .iter()
+                    .any(|link| link.to_lowercase() == title)
+        })
+        .map(|(source, _)| source)
+        .collect()
+}
+
+/// Returns the target of the wiki-link containing the char `offset`, if any.
+pub fn link_at(content: &str, offset: usize) -> Option<String> {
+    let byte = content
+        .char_indices()
+        .nth(offset)
+        .map_or(content.len(), |(byte, _)| byte);
+    wiki_link_ranges(content)
+        .into_iter()
+        .find(|(range, _)| range.contains(&byte))
+        .map(|(_, target)| target)
+}
+
+/// Counts, for each note, how many other notes link to it by title.
+/// Titles are matched ignoring case.
+pub fn backlink_counts(notes: &[Note]) -> Vec<usize> {
+    let titles: Vec<String> = notes.iter().map(|note| note.title.to_lowercase()).collect();
+    let mut counts = vec![0; notes.len()];
+    for (source, note) in notes.iter().enumerate() {
+        let mut targets: Vec<usize> = parse_wiki_links(&note.content)
+            .iter()
+            .flat_map(|link| {
+                let link = link.to_lowercase();
+                titles
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, title)| **title == link)
+                    .map(|(target, _)| target)
+            })
+            .filter(|&target| target != source)
+            .collect();
+        // Several links from one note count once
+        targets.sort_unstable();
+        targets.dedup();
+        for target in targets {
+            counts[target] += 1;
+        }
+    }
+    counts
+}
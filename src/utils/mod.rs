@@ -1 +1,15 @@
+pub mod checklist;
+pub mod config;
 pub mod data_handler;
+pub mod dates;
+pub mod export;
+pub mod highlight;
+pub mod hooks;
+pub mod keymap;
+pub mod links;
+pub mod search;
+pub mod tags;
+pub mod templates;
+pub mod text;
+pub mod theme;
+pub mod watcher;
@@ -0,0 +1,151 @@
+// Ratanotes/src/utils/search.rs
+
+use crate::app::state::Note;
+use crate::utils::text::find_matches;
+
+/// Characters of context kept before a body match in its snippet.
+const SNIPPET_BEFORE: usize = 20;
+
+/// Longest snippet shown for a body match, in characters, not counting ellipses.
+const SNIPPET_LEN: usize = 60;
+
+/// How the search query is matched against notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// The query must appear as written, ignoring case.
+    #[default]
+    Substring,
+    /// The query's characters must appear in order, so `rst` finds "Rust Notes".
+    Fuzzy,
+}
+
+/// Matches `query` as a case-insensitive subsequence of `text`.
+/// Returns a score and the char indices of the matched characters, or `None` if the
+/// characters don't all appear in order. Runs of consecutive characters and matches at
+/// the start of a word score higher, and gaps between matches cost a little.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+
+    for (index, c) in text.chars().enumerate() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            score += 1;
+            match last_match {
+                Some(last) if last + 1 == index => score += 5,
+                Some(last) => score -= (index - last - 1).min(5) as i64,
+                None => {}
+            }
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric())
+                || previous.is_some_and(|p| p.is_lowercase() && c.is_uppercase());
+            if word_start {
+                score += 3;
+            }
+            positions.push(index);
+            last_match = Some(index);
+            query.next();
+        }
+        previous = Some(c);
+    }
+
+    query.peek().is_none().then_some((score, positions))
+}
+
+/// The part of a note a search query matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchField {
+    Title,
+    Tag,
+    Body,
+}
+
+/// Why a note matched a search: the matched text and the char indices to highlight in it.
+/// For body matches the text is a snippet of the first matching line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub field: MatchField,
+    pub text: String,
+    pub positions: Vec<usize>,
+}
+
+/// Matches the query against a note, returning a score and what matched, or `None` if it
+/// doesn't match at all. Title matches rank above tag matches, which rank above matches
+/// in the body. The body is only searched by substring, since almost any long text
+/// contains a short query as a subsequence.
+pub fn note_match(note: &Note, query: &str, mode: SearchMode) -> Option<(i64, SearchMatch)> {
+    if query.is_empty() {
+        return None;
+    }
+    let field_match = |text: &str| match mode {
+        SearchMode::Substring => substring_positions(text, query).map(|positions| (0, positions)),
+        SearchMode::Fuzzy => fuzzy_match(query, text),
+    };
+    let found = |field, text: &str, (score, positions)| {
+        let search_match = SearchMatch {
+            field,
+            text: text.to_string(),
+            positions,
+        };
+        (score, search_match)
+    };
+
+    if let Some(title) = field_match(&note.title) {
+        let (score, search_match) = found(MatchField::Title, &note.title, title);
+        return Some((2000 + score, search_match));
+    }
+    let tag = note
+        .all_tags()
+        .iter()
+        .filter_map(|tag| field_match(tag).map(|result| found(MatchField::Tag, tag, result)))
+        .max_by_key(|(score, _)| *score);
+    if let Some((score, search_match)) = tag {
+        return Some((1000 + score, search_match));
+    }
+    body_snippet(&note.content, query).map(|(text, positions)| {
+        let search_match = SearchMatch {
+            field: MatchField::Body,
+            text,
+            positions,
+        };
+        (0, search_match)
+    })
+}
+
+/// Returns the char indices of the first case-insensitive occurrence of `query` in `text`.
+fn substring_positions(text: &str, query: &str) -> Option<Vec<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let start = *find_matches(&chars, query).first()?;
+    Some((start..start + query.chars().count()).collect())
+}
+
+/// Returns a snippet of the first line of `content` containing `query`, cut down to the
+/// text around the match, with the char indices of the match within the snippet.
+fn body_snippet(content: &str, query: &str) -> Option<(String, Vec<usize>)> {
+    let query_len = query.chars().count();
+    content.lines().find_map(|line| {
+        let chars: Vec<char> = line.chars().collect();
+        let start = *find_matches(&chars, query).first()?;
+        let indent = chars.iter().take_while(|c| c.is_whitespace()).count();
+        let from = start.saturating_sub(SNIPPET_BEFORE).max(indent.min(start));
+        let to = (from + SNIPPET_LEN).max(start + query_len).min(chars.len());
+
+        let mut snippet = String::new();
+        if from > indent {
+            snippet.push('…');
+        }
+        let shift = snippet.chars().count();
+        snippet.extend(&chars[from..to]);
+        if to < chars.len() {
+            snippet.push('…');
+        }
+        let positions = (start..start + query_len)
+            .map(|index| index - from + shift)
+            .collect();
+        Some((snippet, positions))
+    })
+}
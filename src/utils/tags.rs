@@ -0,0 +1,108 @@
+// Ratanotes/src/utils/tags.rs
+
+use std::ops::Range;
+
+/// Returns whether a character may appear in an inline tag.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '/')
+}
+
+/// Finds the byte ranges of inline `#tag` hashtags in a single line, including the `#`.
+/// A tag must start the line or follow whitespace, so `# Heading` and `##` are not tags.
+/// Purely numeric tags such as `#123` are ignored.
+pub fn inline_tag_ranges(line: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            let mut end = start + 1;
+            while let Some(&(index, next)) = chars.peek() {
+                if !is_tag_char(next) {
+                    break;
+                }
+                end = index + next.len_utf8();
+                chars.next();
+            }
+
+            let name = &line[start + 1..end];
+            if name.chars().any(|c| !c.is_ascii_digit()) {
+                ranges.push(start..end);
+            }
+            previous = line[..end].chars().last();
+            continue;
+        }
+        previous = Some(c);
+    }
+    ranges
+}
+
+/// Collects the names of all inline `#tag` hashtags in a note body, without the `#`.
+pub fn parse_inline_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for line in content.lines() {
+        for range in inline_tag_ranges(line) {
+            let tag = &line[range.start + 1..range.end];
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    tags
+}
+
+/// Returns the comparison key for a tag, so `Rust`, `rust` and ` rust ` are treated as one.
+pub fn tag_key(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Deduplicates tags by their comparison key, keeping the first-seen display form.
+pub fn dedup_tags<I>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut unique: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !unique.iter().any(|t| tag_key(t) == tag_key(tag)) {
+            unique.push(tag.to_string());
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_are_not_tags() {
+        assert_eq!(parse_inline_tags("# Heading"), Vec::<String>::new());
+        assert_eq!(
+            parse_inline_tags("## Sub heading\n###"),
+            Vec::<String>::new()
+        );
+        assert_eq!(parse_inline_tags("#heading-tag"), ["heading-tag"]);
+    }
+
+    #[test]
+    fn tags_follow_whitespace_or_start_the_line() {
+        assert_eq!(
+            parse_inline_tags("Plan #work and #home/chores\n#work again"),
+            ["work", "home/chores"]
+        );
+        assert_eq!(parse_inline_tags("email@x.com a#b"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn numeric_tags_are_ignored() {
+        assert_eq!(parse_inline_tags("Issue #123 and #v2"), ["v2"]);
+    }
+
+    #[test]
+    fn tag_ranges_include_the_hash() {
+        let ranges = inline_tag_ranges("a #tag b #x");
+        assert_eq!(ranges, [2..6, 9..11]);
+    }
+}
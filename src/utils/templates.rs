@@ -0,0 +1,27 @@
+// Ratanotes/src/utils/templates.rs
+
+use chrono::{NaiveDate, NaiveTime};
+
+/// Expands the placeholders in a note template: `{{title}}`, `{{date}}` as `YYYY-MM-DD`
+/// and `{{time}}` as `HH:MM`. Unknown placeholders are left as written.
+pub fn expand(template: &str, title: &str, date: NaiveDate, time: NaiveTime) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match after[..end].trim() {
+            "title" => expanded.push_str(title),
+            "date" => expanded.push_str(&date.format("%Y-%m-%d").to_string()),
+            "time" => expanded.push_str(&time.format("%H:%M").to_string()),
+            _ => expanded.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
@@ -0,0 +1,362 @@
+// Ratanotes/src/utils/text.rs
+
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// Returns whether a character belongs to a word for selection and motions.
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the char range `(start, end)` of the line containing `offset`, excluding its newline.
+pub fn line_bounds(chars: &[char], offset: usize) -> (usize, usize) {
+    let offset = offset.min(chars.len());
+    let start = chars[..offset]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |i| i + 1);
+    let end = chars[offset..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(chars.len(), |i| offset + i);
+    (start, end)
+}
+
+/// Returns the 1-based `(line, column)` of `offset`, counting columns in characters.
+pub fn line_column(chars: &[char], offset: usize) -> (usize, usize) {
+    let offset = offset.min(chars.len());
+    let (start, _) = line_bounds(chars, offset);
+    let line = chars[..start].iter().filter(|&&c| c == '\n').count();
+    (line + 1, offset - start + 1)
+}
+
+/// Returns the char offsets where `query` starts in `chars`, ignoring case.
+/// Matches don't overlap.
+pub fn find_matches(chars: &[char], query: &str) -> Vec<usize> {
+    let query: Vec<char> = query.chars().collect();
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+    let mut start = 0;
+    while start + query.len() <= chars.len() {
+        if chars[start..start + query.len()]
+            .iter()
+            .zip(&query)
+            .all(|(&a, &b)| same(a, b))
+        {
+            matches.push(start);
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+/// Returns the char range `(start, end)` of the word under `offset`.
+/// Outside a word, the range covers just the character at `offset`.
+pub fn word_bounds(chars: &[char], offset: usize) -> (usize, usize) {
+    if offset >= chars.len() {
+        return (chars.len(), chars.len());
+    }
+    if !is_word_char(chars[offset]) {
+        return (offset, offset + 1);
+    }
+    let start = chars[..offset]
+        .iter()
+        .rposition(|&c| !is_word_char(c))
+        .map_or(0, |i| i + 1);
+    let end = chars[offset..]
+        .iter()
+        .position(|&c| !is_word_char(c))
+        .map_or(chars.len(), |i| offset + i);
+    (start, end)
+}
+
+/// Returns the start of the word before `offset`, skipping any spaces and punctuation
+/// in between. Used by `Ctrl+Left`.
+pub fn previous_word_start(chars: &[char], offset: usize) -> usize {
+    let mut start = offset.min(chars.len());
+    while start > 0 && !is_word_char(chars[start - 1]) {
+        start -= 1;
+    }
+    while start > 0 && is_word_char(chars[start - 1]) {
+        start -= 1;
+    }
+    start
+}
+
+/// Returns the end of the word after `offset`, skipping any spaces and punctuation
+/// in between. Used by `Ctrl+Right`.
+pub fn next_word_end(chars: &[char], offset: usize) -> usize {
+    let mut end = offset.min(chars.len());
+    while end < chars.len() && !is_word_char(chars[end]) {
+        end += 1;
+    }
+    while end < chars.len() && is_word_char(chars[end]) {
+        end += 1;
+    }
+    end
+}
+
+/// Returns the end of a `dw` deletion starting at `offset`: past the rest of the
+/// word (or run of punctuation) and the spaces after it, stopping at the line end.
+pub fn next_word_start(chars: &[char], offset: usize) -> usize {
+    let Some(&first) = chars.get(offset) else {
+        return chars.len();
+    };
+    if first == '\n' {
+        return offset + 1;
+    }
+    let same_class = |c: char| {
+        if is_word_char(first) {
+            is_word_char(c)
+        } else if first.is_whitespace() {
+            false
+        } else {
+            !is_word_char(c) && !c.is_whitespace()
+        }
+    };
+    let mut end = offset;
+    while end < chars.len() && same_class(chars[end]) {
+        end += 1;
+    }
+    while end < chars.len() && chars[end] != '\n' && chars[end].is_whitespace() {
+        end += 1;
+    }
+    end
+}
+
+/// Returns the length of the list marker starting `line`, including its indentation,
+/// and the marker that continues the list on the next line: the same bullet for `- `
+/// and `* `, the next number for `1. `.
+pub fn list_marker(line: &[char]) -> Option<(usize, String)> {
+    let indent = line.iter().take_while(|&&c| c == ' ' || c == '\t').count();
+    let prefix: String = line[..indent].iter().collect();
+    let rest = &line[indent..];
+    match rest {
+        ['-' | '*', ' ', ..] => Some((indent + 2, format!("{}{} ", prefix, rest[0]))),
+        _ => {
+            let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 || rest.get(digits..digits + 2) != Some(&['.', ' ']) {
+                return None;
+            }
+            let number: u64 = rest[..digits].iter().collect::<String>().parse().ok()?;
+            Some((indent + digits + 2, format!("{}{}. ", prefix, number + 1)))
+        }
+    }
+}
+
+/// Returns the scroll offset that keeps `cursor` inside a viewport of `visible` cells,
+/// moving the current `scroll` as little as possible.
+pub fn scroll_into_view(scroll: u16, cursor: u16, visible: u16) -> u16 {
+    if cursor < scroll {
+        cursor
+    } else if visible > 0 && cursor >= scroll + visible {
+        cursor - visible + 1
+    } else {
+        scroll
+    }
+}
+
+/// Returns the number of terminal columns a character occupies: two for wide CJK and
+/// emoji, zero for combining marks, matching how ratatui lays out text.
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Columns between tab stops, for notes containing literal tabs.
+pub const TAB_STOP: usize = 4;
+
+/// Returns the number of columns `c` occupies when drawn at column `x` of a screen row.
+/// A tab reaches the next tab stop; anything else takes its [`char_width`].
+pub fn width_at(c: char, x: usize) -> usize {
+    if c == '\t' {
+        TAB_STOP - x % TAB_STOP
+    } else {
+        char_width(c)
+    }
+}
+
+/// Returns the display width of a run of characters starting a screen row.
+fn chars_width(chars: &[char]) -> usize {
+    chars.iter().fold(0, |x, &c| x + width_at(c, x))
+}
+
+/// Splits a line into the char ranges shown on each screen row.
+/// A wrapped row holds as many characters as fit in `wrap_width` columns, and a line
+/// that fills its last row keeps an empty row after it for the cursor.
+pub fn row_ranges(line: &[char], wrap_width: Option<usize>) -> Vec<Range<usize>> {
+    let Some(width) = wrap_width.filter(|&width| width > 0) else {
+        return std::iter::once(0..line.len()).collect();
+    };
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (index, &c) in line.iter().enumerate() {
+        if used + width_at(c, used) > width && index > start {
+            rows.push(start..index);
+            start = index;
+            used = 0;
+        }
+        used += width_at(c, used);
+    }
+    rows.push(start..line.len());
+    if used >= width {
+        rows.push(line.len()..line.len());
+    }
+    rows
+}
+
+/// Converts a char offset into a `(column, row)` screen position within the text,
+/// measuring columns in display width. With a `wrap_width`, long lines continue on the
+/// following rows. This is the inverse of [`offset_at`].
+pub fn position_at(content: &str, offset: usize, wrap_width: Option<usize>) -> (usize, usize) {
+    let mut row = 0;
+    let mut line_start = 0;
+    for line in content.split('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        let rows = row_ranges(&chars, wrap_width);
+        if offset <= line_start + chars.len() {
+            let column = offset.saturating_sub(line_start);
+            let sub_row = rows
+                .iter()
+                .rposition(|range| range.start <= column)
+                .unwrap_or(0);
+            let x = chars_width(&chars[rows[sub_row].start..column]);
+            return (x, row + sub_row);
+        }
+        row += rows.len();
+        line_start += chars.len() + 1;
+    }
+    (0, row)
+}
+
+/// Converts a `(column, row)` screen position within the text into a char offset,
+/// clamping to the last line and to the end of the target line. A column in the
+/// right half of a wide character lands before that character.
+pub fn offset_at(content: &str, column: usize, line: usize, wrap_width: Option<usize>) -> usize {
+    let mut offset = 0;
+    let mut lines = content.split('\n').peekable();
+    let mut row = 0;
+    while let Some(text) = lines.next() {
+        let chars: Vec<char> = text.chars().collect();
+        let rows = row_ranges(&chars, wrap_width);
+        if line < row + rows.len() || lines.peek().is_none() {
+            let range = rows[line.saturating_sub(row).min(rows.len() - 1)].clone();
+            let mut x = 0;
+            let mut index = range.start;
+            while index < range.end && x + width_at(chars[index], x) <= column {
+                x += width_at(chars[index], x);
+                index += 1;
+            }
+            return offset + index;
+        }
+        offset += chars.len() + 1;
+        row += rows.len();
+    }
+    offset
+}
+
+/// A parsed `:s/pattern/replacement/flags` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    /// Replace every occurrence instead of only the next one.
+    pub global: bool,
+}
+
+/// Parses the part of a substitute command after the `s`, such as `/foo/bar/g`. The first
+/// character is the delimiter, and a backslash before it makes it part of the text.
+/// The closing delimiter may be left out when there are no flags.
+pub fn parse_substitution(spec: &str) -> Result<Substitution, String> {
+    let usage = "expected s/pattern/replacement/";
+    let mut chars = spec.chars();
+    let delimiter = match chars.next() {
+        Some(c) if !c.is_alphanumeric() && !c.is_whitespace() && c != '\\' => c,
+        _ => return Err(usage.to_string()),
+    };
+
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => parts.last_mut().unwrap().extend(['\\', next]),
+                None => parts.last_mut().unwrap().push('\\'),
+            },
+            c if c == delimiter && parts.len() < 3 => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+
+    let mut parts = parts.into_iter();
+    let (Some(pattern), Some(replacement)) = (parts.next(), parts.next()) else {
+        return Err(usage.to_string());
+    };
+    if pattern.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    let flags = parts.next().unwrap_or_default();
+    if let Some(flag) = flags.chars().find(|&c| c != 'g') {
+        return Err(format!("unknown flag '{}'", flag));
+    }
+    Ok(Substitution {
+        pattern,
+        replacement,
+        global: !flags.is_empty(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_at_inverts_position_at() {
+        let content = "first line\n\nthird";
+        for offset in 0..=content.chars().count() {
+            let (column, row) = position_at(content, offset, None);
+            assert_eq!(offset_at(content, column, row, None), offset);
+        }
+    }
+
+    #[test]
+    fn offset_at_clamps_to_lines() {
+        let content = "short\nlonger line";
+        assert_eq!(offset_at(content, 40, 0, None), 5);
+        assert_eq!(offset_at(content, 3, 9, None), 9);
+        assert_eq!(offset_at(content, 40, 9, None), 17);
+    }
+
+    #[test]
+    fn wrapped_lines_map_both_ways() {
+        let content = "abcdefgh\nij";
+        assert_eq!(position_at(content, 5, Some(4)), (1, 1));
+        assert_eq!(offset_at(content, 1, 1, Some(4)), 5);
+        // A line that fills its last row leaves an empty row for the cursor
+        assert_eq!(position_at(content, 8, Some(4)), (0, 2));
+        assert_eq!(position_at(content, 9, Some(4)), (0, 3));
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        let content = "你好😀x";
+        assert_eq!(position_at(content, 0, None), (0, 0));
+        assert_eq!(position_at(content, 1, None), (2, 0));
+        assert_eq!(position_at(content, 3, None), (6, 0));
+        assert_eq!(position_at(content, 4, None), (7, 0));
+    }
+
+    #[test]
+    fn clicks_on_wide_characters_land_before_them() {
+        let content = "你好😀x";
+        assert_eq!(offset_at(content, 1, 0, None), 0);
+        assert_eq!(offset_at(content, 2, 0, None), 1);
+        assert_eq!(offset_at(content, 5, 0, None), 2);
+        assert_eq!(offset_at(content, 6, 0, None), 3);
+    }
+}
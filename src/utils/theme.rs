@@ -0,0 +1,108 @@
+// Ratanotes/src/utils/theme.rs
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Names of the built-in themes, selectable with the `theme` setting.
+pub const THEME_NAMES: &[&str] = &["default", "high-contrast", "monochrome"];
+
+/// Colors shared by the widgets, chosen by name in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Border of the focused pane.
+    pub focus: Color,
+    /// Border of the editor in Insert mode.
+    pub insert: Color,
+    /// Text of the selected row. `Reset` keeps the row's own text color.
+    pub highlight_fg: Color,
+    /// Background of the selected row.
+    pub highlight_bg: Color,
+    /// Table and group headers, applied tags, and unsaved markers.
+    pub header: Color,
+    /// Tags in the editor and days with notes in the calendar.
+    pub tag: Color,
+    /// `[[wiki-links]]` in the editor.
+    pub link: Color,
+    /// Secondary text such as backlink counts and line numbers.
+    pub muted: Color,
+    /// Key names in the help table.
+    pub key: Color,
+    /// Plain descriptive text in the help table.
+    pub text: Color,
+    /// Errors, overdue tasks and confirmations of destructive actions.
+    pub error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            focus: Color::Green,
+            insert: Color::Blue,
+            highlight_fg: Color::Reset,
+            highlight_bg: Color::Blue,
+            header: Color::Yellow,
+            tag: Color::Green,
+            link: Color::Cyan,
+            muted: Color::DarkGray,
+            key: Color::LightCyan,
+            text: Color::White,
+            error: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the built-in theme with the given name.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "high-contrast" => Some(Self {
+                focus: Color::LightYellow,
+                insert: Color::LightMagenta,
+                highlight_fg: Color::Black,
+                highlight_bg: Color::LightCyan,
+                header: Color::LightYellow,
+                tag: Color::LightGreen,
+                link: Color::LightBlue,
+                muted: Color::Gray,
+                key: Color::LightCyan,
+                text: Color::White,
+                error: Color::LightRed,
+            }),
+            "monochrome" => Some(Self {
+                focus: Color::White,
+                insert: Color::White,
+                highlight_fg: Color::Black,
+                highlight_bg: Color::White,
+                header: Color::Reset,
+                tag: Color::Reset,
+                link: Color::Reset,
+                muted: Color::DarkGray,
+                key: Color::Reset,
+                text: Color::Reset,
+                error: Color::Reset,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Style of the selected row in lists.
+    pub fn highlight(&self) -> Style {
+        let style = Style::default()
+            .add_modifier(Modifier::BOLD)
+            .bg(self.highlight_bg);
+        if self.highlight_fg == Color::Reset {
+            style
+        } else {
+            style.fg(self.highlight_fg)
+        }
+    }
+
+    /// Border style of a pane, colored when it has focus.
+    pub fn border(&self, has_focus: bool) -> Style {
+        if has_focus {
+            Style::default().fg(self.focus)
+        } else {
+            Style::default()
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// Ratanotes/src/utils/watcher.rs
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+/// Watches `dir` and its subdirectories for Markdown files being created or changed,
+/// whether by Ratanotes or another program. The watcher stops when it is dropped.
+pub fn watch_notes(dir: &Path) -> notify::Result<(RecommendedWatcher, Receiver<PathBuf>)> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in event.paths {
+            if path.extension().is_some_and(|ext| ext == "md") {
+                // The receiver is gone if the app has already quit
+                let _ = sender.send(path);
+            }
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::Recursive)?;
+    Ok((watcher, receiver))
+}